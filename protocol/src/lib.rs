@@ -0,0 +1,429 @@
+//! Wire types for `POST /api/v1/devices/sync`, shared by the management server
+//! and the desktop client so the two can't drift apart.
+//!
+//! Everything is camelCase JSON. Readers default missing fields and ignore
+//! unknown ones, so older and newer builds keep talking to each other; snapshot
+//! sections for apps a reader doesn't know are kept verbatim so they survive a
+//! round trip through the server.
+
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+mod canonical;
+
+pub use canonical::canonical_json;
+
+/// Snapshots without a `schemaVersion` predate the field and are version 1.
+pub const DEFAULT_SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+
+/// The wire format this build speaks, sent as `protocolVersion` both ways.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Peers that don't send `protocolVersion` predate negotiation.
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+fn legacy_protocol_version() -> u32 {
+    LEGACY_PROTOCOL_VERSION
+}
+
+/// The version both sides understand: the lower of the two, and never below 1.
+pub fn negotiate(peer_version: u32) -> u32 {
+    peer_version.clamp(LEGACY_PROTOCOL_VERSION, PROTOCOL_VERSION)
+}
+
+/// Parts of the protocol that a peer only handles from some version on.
+/// Version 1 is the original exchange of snapshots and admin configs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolFeature {
+    /// `snapshotUnchanged` requests and `snapshotRequired` responses.
+    Heartbeats,
+    ServerDirectives,
+    DeviceCommands,
+    FeatureFlags,
+}
+
+impl ProtocolFeature {
+    pub const ALL: [Self; 4] = [
+        Self::Heartbeats,
+        Self::ServerDirectives,
+        Self::DeviceCommands,
+        Self::FeatureFlags,
+    ];
+
+    /// The first protocol version with this feature.
+    pub fn since(self) -> u32 {
+        match self {
+            Self::Heartbeats
+            | Self::ServerDirectives
+            | Self::DeviceCommands
+            | Self::FeatureFlags => 2,
+        }
+    }
+
+    pub fn supported_by(self, version: u32) -> bool {
+        version >= self.since()
+    }
+}
+
+/// Which compiled-in server the client talks to; servers use it to keep
+/// staging data apart from production. Older clients don't send it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManagementEnvironment {
+    #[default]
+    Production,
+    Staging,
+}
+
+/// How an admin config is applied to the client's local providers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyMode {
+    /// Local providers of the app are cleared before the admin config is written.
+    #[default]
+    Replace,
+    /// Only providers in the admin config are added or updated.
+    Merge,
+}
+
+impl ApplyMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Replace => "replace",
+            Self::Merge => "merge",
+        }
+    }
+}
+
+/// How secrets in an uploaded snapshot were treated by the client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotPrivacy {
+    /// Uploaded as is.
+    #[default]
+    Full,
+    /// Only the last few characters are kept.
+    Redacted,
+    /// Encrypted to the public key compiled into the client.
+    Encrypted,
+}
+
+impl SnapshotPrivacy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Redacted => "redacted",
+            Self::Encrypted => "encrypted",
+        }
+    }
+}
+
+/// Providers of one app. The server keeps providers as raw JSON (`P` defaults
+/// to [`serde_json::Value`]); the client uses its own provider type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppProviderSnapshot<P = serde_json::Value> {
+    pub current_id: Option<String>,
+    pub providers: IndexMap<String, P>,
+}
+
+/// A device's provider configuration; also the shape of admin configs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceConfigSnapshot<P = serde_json::Value> {
+    #[serde(default = "default_snapshot_schema_version")]
+    pub schema_version: u32,
+    pub claude: Option<AppProviderSnapshot<P>>,
+    pub codex: Option<AppProviderSnapshot<P>>,
+    pub gemini: Option<AppProviderSnapshot<P>>,
+    /// Set by admin configs to override the client's local apply mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<ApplyMode>,
+    /// Omitted when secrets were uploaded as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<SnapshotPrivacy>,
+    /// Configuration beyond providers; clients only upload it when enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<ConfigExtras>,
+    /// Sections for apps this build doesn't know, kept verbatim.
+    #[serde(flatten)]
+    pub other_apps: IndexMap<String, serde_json::Value>,
+}
+
+/// MCP servers and common config snippets. A missing field leaves that part of
+/// the device untouched; a present one is the complete set when applied with
+/// [`ApplyMode::Replace`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigExtras {
+    /// Server definitions keyed by id, in the client's unified MCP format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<IndexMap<String, serde_json::Value>>,
+    /// Snippet text keyed by app (`claude`, `codex`, `gemini`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub common_config_snippets: Option<IndexMap<String, String>>,
+}
+
+fn default_snapshot_schema_version() -> u32 {
+    DEFAULT_SNAPSHOT_SCHEMA_VERSION
+}
+
+/// Admin config versions each app has applied; apps fail independently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppliedVersions {
+    pub claude: Option<i64>,
+    pub codex: Option<i64>,
+    pub gemini: Option<i64>,
+    /// Version of the [`ConfigExtras`] section, tracked like an app.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extras: Option<i64>,
+}
+
+impl AppliedVersions {
+    /// The oldest applied version, for servers that only track one: they
+    /// resend that version so apps that failed to apply it get another try.
+    pub fn oldest(&self) -> Option<i64> {
+        [self.claude, self.codex, self.gemini, self.extras]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRequest<P = serde_json::Value> {
+    #[serde(default = "legacy_protocol_version")]
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub environment: ManagementEnvironment,
+    pub device_id: String,
+    pub fingerprint_hash: Option<String>,
+    pub app_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub arch: Option<String>,
+    /// Left out when the user turned hostname reporting off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// [`AppliedVersions::oldest`], for servers that predate per-app versions.
+    pub applied_admin_version: Option<i64>,
+    #[serde(default)]
+    pub applied_admin_versions: AppliedVersions,
+    /// Why applying the admin config last failed; cleared after a success.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_admin_version: Option<i64>,
+    /// The failed apply stopped halfway, leaving local config partly applied.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub last_error_partial: bool,
+    /// The user declined this admin config version; cleared by a newer one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected_admin_version: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<DeviceConfigSnapshot<P>>,
+    /// Some provider fields were cut to keep the snapshot under the size limit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub snapshot_truncated: bool,
+    /// Heartbeat: the snapshot matches the last one uploaded and is omitted.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub snapshot_unchanged: bool,
+    /// When the client collected the snapshot; differs from receipt time for
+    /// snapshots replayed from the client's offline queue.
+    pub client_time: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResponse {
+    /// The version the server answered in, see [`SyncResponse::for_client`].
+    #[serde(default = "legacy_protocol_version")]
+    pub protocol_version: u32,
+    pub ok: bool,
+    /// Why the server refused the sync when `ok` is false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Lets clients estimate their clock skew; older servers don't send it.
+    #[serde(default)]
+    pub server_time: Option<String>,
+    /// Kept as raw JSON: the signature covers the stored text, so clients
+    /// verify it before parsing into a [`DeviceConfigSnapshot`].
+    #[serde(default)]
+    pub admin_config: Option<serde_json::Value>,
+    #[serde(default)]
+    pub admin_version: Option<i64>,
+    /// Base64 Ed25519 signature over the canonical JSON of `admin_config`.
+    #[serde(default)]
+    pub admin_config_signature: Option<String>,
+    /// Queued by admins; clients acknowledge each one after handling it.
+    #[serde(default)]
+    pub commands: Vec<DeviceCommand>,
+    /// The snapshot was not stored because the storage quota is used up.
+    #[serde(default)]
+    pub quota_exceeded: bool,
+    /// The server has no snapshot to compare a heartbeat against.
+    #[serde(default)]
+    pub snapshot_required: bool,
+    #[serde(default)]
+    pub server_directives: Option<ServerDirectives>,
+    /// The complete set of feature flags; flags left out have been removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_flags: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl SyncResponse {
+    /// Tailors the response to a client speaking `client_version`: answers in
+    /// the negotiated version and drops what the client doesn't understand.
+    pub fn for_client(mut self, client_version: u32) -> Self {
+        let version = negotiate(client_version);
+        self.protocol_version = version;
+        if !ProtocolFeature::Heartbeats.supported_by(version) {
+            self.snapshot_required = false;
+        }
+        if !ProtocolFeature::ServerDirectives.supported_by(version) {
+            self.server_directives = None;
+        }
+        if !ProtocolFeature::DeviceCommands.supported_by(version) {
+            self.commands.clear();
+        }
+        if !ProtocolFeature::FeatureFlags.supported_by(version) {
+            self.feature_flags = None;
+        }
+        self
+    }
+}
+
+/// Commands admins can queue for a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceCommandKind {
+    Resync,
+    CollectLogs,
+    Rollback,
+}
+
+impl DeviceCommandKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Resync => "resync",
+            Self::CollectLogs => "collect-logs",
+            Self::Rollback => "rollback",
+        }
+    }
+
+    pub fn parse(command: &str) -> Option<Self> {
+        [Self::Resync, Self::CollectLogs, Self::Rollback]
+            .into_iter()
+            .find(|kind| kind.as_str() == command)
+    }
+}
+
+/// One queued command. `command` stays a string so clients can acknowledge
+/// commands added after they were built; see [`DeviceCommand::kind`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCommand {
+    pub id: i64,
+    pub command: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl DeviceCommand {
+    /// `None` for commands this build doesn't know.
+    pub fn kind(&self) -> Option<DeviceCommandKind> {
+        DeviceCommandKind::parse(&self.command)
+    }
+}
+
+/// Instructions for the client beyond the config itself. Missing fields take
+/// their defaults so clients can ignore directives they don't understand.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ServerDirectives {
+    pub blocked: bool,
+    /// The device is waiting for an admin to approve it and gets no config
+    /// until then.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pending_approval: bool,
+    pub upgrade_required: Option<String>,
+    pub message: Option<String>,
+    /// Stable ID of `message`; clients show each message once. Without one
+    /// they go by the message text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    pub retry_after_secs: Option<u64>,
+}
+
+impl ServerDirectives {
+    pub fn is_empty(&self) -> bool {
+        !self.blocked
+            && !self.pending_approval
+            && self.upgrade_required.is_none()
+            && self.message.is_none()
+            && self.retry_after_secs.is_none()
+    }
+}
+
+/// Machine-readable class of a failed request, derived from the HTTP status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    InvalidRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PayloadTooLarge,
+    RateLimited,
+    Unavailable,
+    Internal,
+    /// A code added after this build.
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => Self::Unauthorized,
+            403 => Self::Forbidden,
+            404 => Self::NotFound,
+            409 => Self::Conflict,
+            413 => Self::PayloadTooLarge,
+            429 => Self::RateLimited,
+            503 => Self::Unavailable,
+            400..=499 => Self::InvalidRequest,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// Body of every non-success response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorBody {
+    pub ok: bool,
+    pub error: String,
+    /// Older servers only send the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+}
+
+impl ErrorBody {
+    pub fn new(status: u16, error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: error.into(),
+            code: Some(ErrorCode::from_status(status)),
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! Locks down the JSON both sides put on the wire. A failing assertion here
+//! means a change would break clients or servers that are already deployed.
+
+use cc_switch_protocol::{
+    canonical_json, negotiate, AppProviderSnapshot, AppliedVersions, ApplyMode, ConfigExtras,
+    DeviceCommand, DeviceCommandKind, DeviceConfigSnapshot, ErrorBody, ErrorCode,
+    ManagementEnvironment, ProtocolFeature, ServerDirectives, SnapshotPrivacy, SyncRequest,
+    SyncResponse, LEGACY_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use chrono::{TimeZone, Utc};
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+
+fn snapshot() -> DeviceConfigSnapshot {
+    DeviceConfigSnapshot {
+        schema_version: 2,
+        claude: Some(AppProviderSnapshot {
+            current_id: Some("p1".to_string()),
+            providers: IndexMap::from([("p1".to_string(), json!({ "id": "p1", "name": "Main" }))]),
+        }),
+        codex: None,
+        gemini: None,
+        mode: None,
+        privacy: Some(SnapshotPrivacy::Redacted),
+        extras: None,
+        other_apps: IndexMap::new(),
+    }
+}
+
+#[test]
+fn sync_request_serializes_to_the_documented_shape() {
+    let request = SyncRequest {
+        protocol_version: PROTOCOL_VERSION,
+        environment: ManagementEnvironment::Staging,
+        device_id: "device-1".to_string(),
+        fingerprint_hash: Some("fp".to_string()),
+        app_version: Some("1.0.22".to_string()),
+        os: Some("macos".to_string()),
+        os_version: None,
+        arch: Some("aarch64".to_string()),
+        hostname: None,
+        applied_admin_version: Some(3),
+        applied_admin_versions: AppliedVersions {
+            claude: Some(3),
+            codex: Some(4),
+            gemini: None,
+            extras: None,
+        },
+        last_error: None,
+        last_error_at: None,
+        last_error_admin_version: None,
+        last_error_partial: false,
+        rejected_admin_version: None,
+        rejected_at: None,
+        snapshot: Some(snapshot()),
+        snapshot_truncated: false,
+        snapshot_unchanged: false,
+        client_time: Some("2026-01-02T03:04:05+00:00".to_string()),
+    };
+
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({
+            "protocolVersion": 2,
+            "environment": "staging",
+            "deviceId": "device-1",
+            "fingerprintHash": "fp",
+            "appVersion": "1.0.22",
+            "os": "macos",
+            "osVersion": null,
+            "arch": "aarch64",
+            "appliedAdminVersion": 3,
+            "appliedAdminVersions": { "claude": 3, "codex": 4, "gemini": null },
+            "snapshot": {
+                "schemaVersion": 2,
+                "claude": {
+                    "currentId": "p1",
+                    "providers": { "p1": { "id": "p1", "name": "Main" } },
+                },
+                "codex": null,
+                "gemini": null,
+                "privacy": "redacted",
+            },
+            "clientTime": "2026-01-02T03:04:05+00:00",
+        })
+    );
+}
+
+#[test]
+fn minimal_requests_from_old_clients_still_parse() {
+    let request: SyncRequest = serde_json::from_value(json!({
+        "deviceId": "device-1",
+        "appliedAdminVersion": null,
+        "snapshot": { "claude": null, "codex": null, "gemini": null },
+        "somethingNew": true,
+    }))
+    .unwrap();
+
+    assert_eq!(request.protocol_version, LEGACY_PROTOCOL_VERSION);
+    assert_eq!(request.environment, ManagementEnvironment::Production);
+    assert_eq!(request.app_version, None);
+    assert_eq!(request.applied_admin_versions, AppliedVersions::default());
+    assert!(!request.snapshot_unchanged);
+    let snapshot = request.snapshot.unwrap();
+    assert_eq!(snapshot.schema_version, 1);
+    assert!(snapshot.other_apps.is_empty());
+}
+
+#[test]
+fn unknown_snapshot_sections_survive_a_round_trip() {
+    let raw = json!({
+        "schemaVersion": 3,
+        "claude": null,
+        "codex": null,
+        "gemini": null,
+        "mode": "merge",
+        "opencode": { "currentId": null, "providers": {} },
+    });
+    let snapshot: DeviceConfigSnapshot = serde_json::from_value(raw.clone()).unwrap();
+    assert_eq!(snapshot.mode, Some(ApplyMode::Merge));
+    assert_eq!(
+        snapshot.other_apps.get("opencode"),
+        Some(&json!({ "currentId": null, "providers": {} }))
+    );
+    assert_eq!(serde_json::to_value(&snapshot).unwrap(), raw);
+}
+
+#[test]
+fn config_extras_are_optional_and_keep_their_shape() {
+    let raw = json!({
+        "schemaVersion": 1,
+        "claude": null,
+        "codex": null,
+        "gemini": null,
+        "extras": {
+            "mcpServers": {
+                "fetch": {
+                    "id": "fetch",
+                    "name": "Fetch",
+                    "server": { "type": "stdio", "command": "uvx", "args": ["mcp-server-fetch"] },
+                    "apps": { "claude": true, "codex": false, "gemini": false },
+                },
+            },
+            "commonConfigSnippets": { "claude": "{\"includeCoAuthoredBy\":false}" },
+        },
+    });
+    let snapshot: DeviceConfigSnapshot = serde_json::from_value(raw.clone()).unwrap();
+    let extras = snapshot.extras.clone().unwrap();
+    assert_eq!(
+        extras.mcp_servers.as_ref().map(|servers| servers.len()),
+        Some(1)
+    );
+    assert!(snapshot.other_apps.is_empty());
+    assert_eq!(serde_json::to_value(&snapshot).unwrap(), raw);
+
+    // An empty section serializes to an empty object, not nulls.
+    assert_eq!(
+        serde_json::to_value(ConfigExtras::default()).unwrap(),
+        json!({})
+    );
+
+    let versions: AppliedVersions =
+        serde_json::from_value(json!({ "claude": 4, "extras": 2 })).unwrap();
+    assert_eq!(versions.extras, Some(2));
+    assert_eq!(versions.oldest(), Some(2));
+}
+
+fn full_response() -> SyncResponse {
+    SyncResponse {
+        protocol_version: PROTOCOL_VERSION,
+        ok: true,
+        error: None,
+        server_time: Some("2026-01-02T03:04:05+00:00".to_string()),
+        admin_config: Some(json!({ "claude": null })),
+        admin_version: Some(5),
+        admin_config_signature: None,
+        commands: vec![DeviceCommand {
+            id: 9,
+            command: DeviceCommandKind::CollectLogs.as_str().to_string(),
+            payload: Value::Null,
+            created_at: Some(Utc.with_ymd_and_hms(2026, 1, 2, 3, 0, 0).unwrap()),
+        }],
+        quota_exceeded: false,
+        snapshot_required: true,
+        server_directives: Some(ServerDirectives {
+            retry_after_secs: Some(60),
+            ..ServerDirectives::default()
+        }),
+        feature_flags: None,
+    }
+}
+
+#[test]
+fn sync_response_serializes_to_the_documented_shape() {
+    assert_eq!(
+        serde_json::to_value(full_response()).unwrap(),
+        json!({
+            "protocolVersion": 2,
+            "ok": true,
+            "serverTime": "2026-01-02T03:04:05+00:00",
+            "adminConfig": { "claude": null },
+            "adminVersion": 5,
+            "adminConfigSignature": null,
+            "commands": [{
+                "id": 9,
+                "command": "collect-logs",
+                "payload": null,
+                "createdAt": "2026-01-02T03:00:00Z",
+            }],
+            "quotaExceeded": false,
+            "snapshotRequired": true,
+            "serverDirectives": {
+                "blocked": false,
+                "upgradeRequired": null,
+                "message": null,
+                "retryAfterSecs": 60,
+            },
+        })
+    );
+}
+
+#[test]
+fn responses_from_old_or_newer_servers_parse() {
+    let response: SyncResponse = serde_json::from_value(json!({
+        "ok": true,
+        "adminConfig": null,
+        "adminVersion": null,
+        "commands": [{ "id": 1, "command": "reboot-into-safe-mode" }],
+        "serverDirectives": { "blocked": true, "newDirective": 1 },
+        "featureFlags": { "push_sync_enabled": false },
+    }))
+    .unwrap();
+
+    assert_eq!(response.protocol_version, LEGACY_PROTOCOL_VERSION);
+    assert_eq!(response.server_time, None);
+    assert_eq!(response.error, None);
+    assert_eq!(response.commands[0].kind(), None);
+    assert_eq!(response.commands[0].payload, Value::Null);
+    let directives = response.server_directives.unwrap();
+    assert!(directives.blocked && !directives.is_empty());
+    assert_eq!(
+        response.feature_flags.unwrap().get("push_sync_enabled"),
+        Some(&json!(false))
+    );
+}
+
+#[test]
+fn rejected_responses_carry_the_reason() {
+    let response: SyncResponse = serde_json::from_value(json!({
+        "ok": false,
+        "error": "device limit reached",
+    }))
+    .unwrap();
+    assert!(!response.ok);
+    assert_eq!(response.error.as_deref(), Some("device limit reached"));
+}
+
+#[test]
+fn command_kinds_use_kebab_case_names() {
+    for kind in [
+        DeviceCommandKind::Resync,
+        DeviceCommandKind::CollectLogs,
+        DeviceCommandKind::Rollback,
+    ] {
+        assert_eq!(serde_json::to_value(kind).unwrap(), json!(kind.as_str()));
+        assert_eq!(DeviceCommandKind::parse(kind.as_str()), Some(kind));
+    }
+}
+
+#[test]
+fn error_bodies_carry_a_code_derived_from_the_status() {
+    assert_eq!(
+        serde_json::to_value(ErrorBody::new(429, "slow down")).unwrap(),
+        json!({ "ok": false, "error": "slow down", "code": "rateLimited" })
+    );
+    assert_eq!(ErrorCode::from_status(400), ErrorCode::InvalidRequest);
+    assert_eq!(ErrorCode::from_status(422), ErrorCode::InvalidRequest);
+    assert_eq!(ErrorCode::from_status(500), ErrorCode::Internal);
+
+    let old: ErrorBody = serde_json::from_value(json!({ "ok": false, "error": "x" })).unwrap();
+    assert_eq!(old.code, None);
+    let newer: ErrorBody =
+        serde_json::from_value(json!({ "ok": false, "error": "x", "code": "teapot" })).unwrap();
+    assert_eq!(newer.code, Some(ErrorCode::Unknown));
+}
+
+#[test]
+fn versions_negotiate_down_to_the_older_peer() {
+    assert_eq!(negotiate(LEGACY_PROTOCOL_VERSION), LEGACY_PROTOCOL_VERSION);
+    assert_eq!(negotiate(PROTOCOL_VERSION), PROTOCOL_VERSION);
+    // A newer peer gets our version; a bogus 0 is read as the legacy protocol
+    assert_eq!(negotiate(PROTOCOL_VERSION + 1), PROTOCOL_VERSION);
+    assert_eq!(negotiate(0), LEGACY_PROTOCOL_VERSION);
+}
+
+#[test]
+fn responses_keep_only_what_the_client_version_understands() {
+    let response = SyncResponse {
+        feature_flags: Some(serde_json::Map::from_iter([(
+            "push_sync_enabled".to_string(),
+            json!(true),
+        )])),
+        ..full_response()
+    };
+
+    // (client version, features it should receive)
+    let matrix: [(u32, &[ProtocolFeature]); 3] = [
+        (LEGACY_PROTOCOL_VERSION, &[]),
+        (PROTOCOL_VERSION, &ProtocolFeature::ALL),
+        (PROTOCOL_VERSION + 1, &ProtocolFeature::ALL),
+    ];
+    for (client_version, expected) in matrix {
+        let tailored = response.clone().for_client(client_version);
+        assert_eq!(tailored.protocol_version, negotiate(client_version));
+        let present = |feature: ProtocolFeature| match feature {
+            ProtocolFeature::Heartbeats => tailored.snapshot_required,
+            ProtocolFeature::ServerDirectives => tailored.server_directives.is_some(),
+            ProtocolFeature::DeviceCommands => !tailored.commands.is_empty(),
+            ProtocolFeature::FeatureFlags => tailored.feature_flags.is_some(),
+        };
+        for feature in ProtocolFeature::ALL {
+            assert_eq!(
+                present(feature),
+                expected.contains(&feature),
+                "{feature:?} for a v{client_version} client"
+            );
+            assert_eq!(
+                feature.supported_by(client_version),
+                expected.contains(&feature)
+            );
+        }
+        // The config itself is part of every version
+        assert_eq!(tailored.admin_config, response.admin_config);
+        assert_eq!(tailored.admin_version, response.admin_version);
+    }
+}
+
+/// Admin config signatures cover these exact bytes; changing them invalidates
+/// every stored signature.
+#[test]
+fn canonical_json_is_stable() {
+    let config: Value = serde_json::from_str(
+        r#"{
+            "codex": null,
+            "claude": {
+                "providers": { "b": { "name": "Zürich \"B\"", "weight": 1.5 }, "a": [3, {"z": true, "y": false}] },
+                "currentId": "a"
+            },
+            "Claude": ""
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        canonical_json(&config),
+        r#"{"Claude":"","claude":{"currentId":"a","providers":{"a":[3,{"y":false,"z":true}],"b":{"name":"Zürich \"B\"","weight":1.5}}},"codex":null}"#
+    );
+
+    // Snapshot hashes are taken over the canonical form of the serialized snapshot
+    assert_eq!(
+        canonical_json(&serde_json::to_value(snapshot()).unwrap()),
+        r#"{"claude":{"currentId":"p1","providers":{"p1":{"id":"p1","name":"Main"}}},"codex":null,"gemini":null,"privacy":"redacted","schemaVersion":2}"#
+    );
+}
@@ -0,0 +1,2 @@
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS sync_secret TEXT;
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS last_sync_signed BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS device_commands (
+  id BIGSERIAL PRIMARY KEY,
+  device_id TEXT NOT NULL REFERENCES devices(device_id) ON DELETE CASCADE,
+  command TEXT NOT NULL CHECK (command IN ('resync', 'collect-logs', 'rollback')),
+  payload JSONB NOT NULL DEFAULT 'null'::jsonb,
+  created_at TIMESTAMPTZ NOT NULL,
+  acknowledged_at TIMESTAMPTZ,
+  result TEXT
+);
+
+CREATE INDEX IF NOT EXISTS device_commands_pending_idx
+  ON device_commands (device_id, created_at)
+  WHERE acknowledged_at IS NULL;
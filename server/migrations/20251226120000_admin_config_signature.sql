@@ -0,0 +1 @@
+ALTER TABLE admin_configs ADD COLUMN IF NOT EXISTS signature TEXT;
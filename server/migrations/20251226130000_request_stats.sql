@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS request_stats (
+  id BIGSERIAL PRIMARY KEY,
+  bucket_start TIMESTAMPTZ NOT NULL,
+  route TEXT NOT NULL,
+  status_class TEXT NOT NULL,
+  request_count BIGINT NOT NULL,
+  total_duration_ms DOUBLE PRECISION NOT NULL,
+  max_duration_ms DOUBLE PRECISION NOT NULL,
+  latency_buckets BIGINT[] NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS request_stats_bucket_idx ON request_stats (bucket_start);
@@ -0,0 +1,10 @@
+ALTER TABLE config_snapshots ADD COLUMN IF NOT EXISTS payload_bytes BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS snapshot_bytes BIGINT NOT NULL DEFAULT 0;
+
+UPDATE config_snapshots SET payload_bytes = octet_length(snapshot::text) WHERE payload_bytes = 0;
+
+UPDATE devices d
+SET snapshot_bytes = COALESCE(
+  (SELECT SUM(s.payload_bytes) FROM config_snapshots s WHERE s.device_id = d.device_id),
+  0
+);
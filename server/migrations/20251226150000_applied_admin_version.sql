@@ -0,0 +1 @@
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS applied_admin_version BIGINT;
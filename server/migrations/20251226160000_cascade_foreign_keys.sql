@@ -0,0 +1,43 @@
+-- The init migration already declares both foreign keys with ON DELETE
+-- CASCADE, so on a database it created this only reports zero orphans. It
+-- guards databases whose constraints were dropped by hand: remove the rows
+-- they left behind and add each key back only when it is missing.
+DO $$
+DECLARE
+  removed BIGINT;
+BEGIN
+  DELETE FROM config_snapshots s
+  WHERE NOT EXISTS (SELECT 1 FROM devices d WHERE d.device_id = s.device_id);
+  GET DIAGNOSTICS removed = ROW_COUNT;
+  RAISE NOTICE 'removed % orphaned config_snapshots rows', removed;
+
+  DELETE FROM admin_configs a
+  WHERE NOT EXISTS (SELECT 1 FROM devices d WHERE d.device_id = a.device_id);
+  GET DIAGNOSTICS removed = ROW_COUNT;
+  RAISE NOTICE 'removed % orphaned admin_configs rows', removed;
+
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint
+    WHERE conname = 'config_snapshots_device_id_fkey'
+      AND conrelid = 'config_snapshots'::regclass
+  ) THEN
+    ALTER TABLE config_snapshots
+      ADD CONSTRAINT config_snapshots_device_id_fkey
+      FOREIGN KEY (device_id) REFERENCES devices(device_id) ON DELETE CASCADE;
+  END IF;
+
+  IF NOT EXISTS (
+    SELECT 1 FROM pg_constraint
+    WHERE conname = 'admin_configs_device_id_fkey'
+      AND conrelid = 'admin_configs'::regclass
+  ) THEN
+    ALTER TABLE admin_configs
+      ADD CONSTRAINT admin_configs_device_id_fkey
+      FOREIGN KEY (device_id) REFERENCES devices(device_id) ON DELETE CASCADE;
+  END IF;
+END $$;
+
+-- Postgres does not index referencing columns, so without this every cascaded
+-- device delete scans config_snapshots. admin_configs is keyed by device_id.
+CREATE INDEX IF NOT EXISTS config_snapshots_device_created_idx
+  ON config_snapshots (device_id, created_at DESC);
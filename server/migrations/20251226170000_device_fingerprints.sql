@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS device_fingerprints (
+  device_id TEXT NOT NULL REFERENCES devices(device_id) ON DELETE CASCADE,
+  fingerprint_hash TEXT NOT NULL,
+  first_seen TIMESTAMPTZ NOT NULL,
+  last_seen TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (device_id, fingerprint_hash)
+);
+
+CREATE INDEX IF NOT EXISTS devices_fingerprint_hash_idx ON devices (fingerprint_hash);
@@ -0,0 +1,2 @@
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS blocked BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS blocked_reason TEXT;
@@ -0,0 +1 @@
+ALTER TABLE config_snapshots ADD COLUMN IF NOT EXISTS client_time TIMESTAMPTZ;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS device_logs (
+  id BIGSERIAL PRIMARY KEY,
+  device_id TEXT NOT NULL REFERENCES devices(device_id) ON DELETE CASCADE,
+  command_id BIGINT REFERENCES device_commands(id) ON DELETE SET NULL,
+  content TEXT NOT NULL,
+  truncated BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS device_logs_device_idx
+  ON device_logs (device_id, created_at DESC);
@@ -0,0 +1 @@
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS protocol_version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,3 @@
+-- Release channel derived from app_version; existing rows are filled in on their next sync.
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS channel TEXT NOT NULL DEFAULT 'unknown';
+CREATE INDEX IF NOT EXISTS devices_channel_idx ON devices (channel);
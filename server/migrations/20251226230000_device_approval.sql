@@ -0,0 +1,3 @@
+-- Devices synced before approval mode existed stay active.
+ALTER TABLE devices ADD COLUMN IF NOT EXISTS approval TEXT NOT NULL DEFAULT 'active';
+CREATE INDEX IF NOT EXISTS devices_approval_idx ON devices (approval);
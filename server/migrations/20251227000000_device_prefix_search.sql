@@ -0,0 +1,3 @@
+-- Prefix search on device IDs and fingerprints (`?q=`) without a full scan.
+CREATE INDEX IF NOT EXISTS devices_device_id_prefix_idx ON devices (LOWER(device_id) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS devices_fingerprint_prefix_idx ON devices (LOWER(fingerprint_hash) text_pattern_ops);
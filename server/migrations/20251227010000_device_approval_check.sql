@@ -0,0 +1,6 @@
+-- Unknown approval values are held back rather than treated as active.
+UPDATE devices SET approval = 'pending'
+WHERE approval NOT IN ('pending', 'active', 'rejected');
+ALTER TABLE devices
+  ADD CONSTRAINT devices_approval_check
+  CHECK (approval IN ('pending', 'active', 'rejected'));
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use tower_http::services::{ServeDir, ServeFile};
+
+use crate::{authorize_admin, AppState};
+
+/// Vite emits content-hashed bundles under `assets/`, so they never change in place.
+const HASHED_ASSETS_PREFIX: &str = "/admin/assets/";
+
+/// Serves the SPA under `/admin`, redirects `/` there and falls back to
+/// `index.html` for client-side routes.
+pub fn router<S: Clone + Send + Sync + 'static>(state: &AppState) -> Router<S> {
+    if !state.ui_dir.exists() {
+        return Router::new();
+    }
+
+    let index_path = state.ui_dir.join("index.html");
+    Router::new()
+        .route("/", get(|| async { Redirect::temporary("/admin/") }))
+        .nest_service(
+            "/admin",
+            ServeDir::new(&state.ui_dir).fallback(ServeFile::new(index_path)),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), ui_gate))
+}
+
+async fn ui_gate(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if state.admin_ui_require_auth && authorize_admin(request.headers(), &state).is_err() {
+        let mut response = (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
+        if state.admin_basic_user.is_some() {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Basic realm=\"admin\", charset=\"UTF-8\""),
+            );
+        }
+        return response;
+    }
+
+    let hashed_asset = request.uri().path().starts_with(HASHED_ASSETS_PREFIX);
+    let mut response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    let cache_control = if hashed_asset && !is_html {
+        "public, max-age=31536000, immutable"
+    } else {
+        // index.html (including history-mode fallbacks) and unhashed files
+        // must be revalidated so a deploy is picked up immediately.
+        "no-cache"
+    };
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+    response
+}
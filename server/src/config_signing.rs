@@ -0,0 +1,89 @@
+use base64::{engine::general_purpose, Engine as _};
+use cc_switch_protocol::canonical_json;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+
+/// Ed25519 key used to sign admin configs before they are stored.
+pub struct ConfigSigner {
+    key_pair: Ed25519KeyPair,
+}
+
+impl ConfigSigner {
+    /// Accepts a base64 encoded 32-byte seed or PKCS#8 document.
+    pub fn from_base64(encoded: &str) -> Result<Self, String> {
+        let bytes = general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|err| format!("invalid base64: {err}"))?;
+        let key_pair = if bytes.len() == 32 {
+            Ed25519KeyPair::from_seed_unchecked(&bytes)
+        } else {
+            Ed25519KeyPair::from_pkcs8_maybe_unchecked(&bytes)
+        }
+        .map_err(|err| format!("invalid ed25519 key: {err}"))?;
+
+        Ok(Self { key_pair })
+    }
+
+    pub fn public_key_base64(&self) -> String {
+        general_purpose::STANDARD.encode(self.key_pair.public_key().as_ref())
+    }
+
+    /// Base64 signature over the canonical JSON form of `config`.
+    pub fn sign(&self, config: &serde_json::Value) -> String {
+        let message = canonical_json(config);
+        general_purpose::STANDARD.encode(self.key_pair.sign(message.as_bytes()).as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    use super::*;
+
+    fn signer() -> ConfigSigner {
+        ConfigSigner::from_base64(&general_purpose::STANDARD.encode([7u8; 32])).unwrap()
+    }
+
+    fn verifies(signer: &ConfigSigner, config: &serde_json::Value, signature: &str) -> bool {
+        let public_key = general_purpose::STANDARD
+            .decode(signer.public_key_base64())
+            .unwrap();
+        let signature = general_purpose::STANDARD.decode(signature).unwrap();
+        UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(canonical_json(config).as_bytes(), &signature)
+            .is_ok()
+    }
+
+    #[test]
+    fn signatures_verify_with_the_public_key() {
+        let signer = signer();
+        let config = serde_json::json!({
+            "claude": { "currentId": "managed", "providers": { "managed": { "name": "A" } } },
+        });
+        let signature = signer.sign(&config);
+        assert!(verifies(&signer, &config, &signature));
+
+        let mut tampered = config.clone();
+        tampered["claude"]["currentId"] = serde_json::json!("other");
+        assert!(!verifies(&signer, &tampered, &signature));
+
+        // Ed25519 is deterministic and the key order does not matter
+        let reordered: serde_json::Value = serde_json::from_str(
+            r#"{"claude":{"providers":{"managed":{"name":"A"}},"currentId":"managed"}}"#,
+        )
+        .unwrap();
+        assert_eq!(signer.sign(&reordered), signature);
+    }
+
+    #[test]
+    fn keys_are_read_from_seeds_or_pkcs8() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+        let from_pkcs8 =
+            ConfigSigner::from_base64(&general_purpose::STANDARD.encode(pkcs8.as_ref())).unwrap();
+        let config = serde_json::json!({ "codex": null });
+        assert!(verifies(&from_pkcs8, &config, &from_pkcs8.sign(&config)));
+
+        assert!(ConfigSigner::from_base64("not base64!").is_err());
+        assert!(ConfigSigner::from_base64(&general_purpose::STANDARD.encode([1u8; 16])).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::IpAddr,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use maxminddb::Reader;
+use serde::Serialize;
+
+/// Enough for every address of a large fleet syncing within the same hour.
+const CACHE_CAPACITY: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoResult {
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+}
+
+/// A GeoIP database with a bounded LRU cache of its lookups, negative results
+/// included. The cache belongs to the loaded database, so loading another one
+/// means a new `GeoIp` and starts from an empty cache.
+pub struct GeoIp {
+    reader: Reader<Vec<u8>>,
+    cache: Mutex<GeoCache>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl GeoIp {
+    pub fn open(path: impl AsRef<Path>) -> Option<Self> {
+        let reader = Reader::open_readfile(path).ok()?;
+        Some(Self {
+            reader,
+            cache: Mutex::new(GeoCache::new(CACHE_CAPACITY)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> Option<GeoResult> {
+        if let Some(cached) = self.cache().get(ip) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return cached;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let result = self.resolve(ip);
+        self.cache().insert(ip, result.clone());
+        result
+    }
+
+    pub fn cache_stats(&self) -> GeoCacheStats {
+        GeoCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.cache().len(),
+            capacity: CACHE_CAPACITY,
+        }
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, GeoCache> {
+        self.cache.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn resolve(&self, ip: IpAddr) -> Option<GeoResult> {
+        let result = self.reader.lookup::<maxminddb::geoip2::City>(ip).ok()?;
+
+        let country = result
+            .country
+            .and_then(|c| c.iso_code.map(|code| code.to_string()));
+        let region = result.subdivisions.and_then(|subs| {
+            subs.first()
+                .and_then(|sub| sub.iso_code.map(|code| code.to_string()))
+        });
+        let city = result.city.and_then(|city| {
+            city.names
+                .and_then(|names| names.get("en").map(|value| value.to_string()))
+        });
+
+        Some(GeoResult {
+            country,
+            region,
+            city,
+        })
+    }
+}
+
+/// Least recently used entries are evicted first; `order` maps each entry's
+/// last use to its address.
+struct GeoCache {
+    capacity: usize,
+    entries: HashMap<IpAddr, (u64, Option<GeoResult>)>,
+    order: BTreeMap<u64, IpAddr>,
+    tick: u64,
+}
+
+impl GeoCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// `Some(None)` is a cached negative result.
+    fn get(&mut self, ip: IpAddr) -> Option<Option<GeoResult>> {
+        self.tick += 1;
+        let (used, result) = self.entries.get_mut(&ip)?;
+        self.order.remove(used);
+        self.order.insert(self.tick, ip);
+        *used = self.tick;
+        Some(result.clone())
+    }
+
+    fn insert(&mut self, ip: IpAddr, result: Option<GeoResult>) {
+        self.tick += 1;
+        if let Some((used, _)) = self.entries.insert(ip, (self.tick, result)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, ip);
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([10, 0, 0, last])
+    }
+
+    fn geo(country: &str) -> Option<GeoResult> {
+        Some(GeoResult {
+            country: Some(country.to_string()),
+            region: None,
+            city: None,
+        })
+    }
+
+    #[test]
+    fn caches_negative_results() {
+        let mut cache = GeoCache::new(2);
+        assert_eq!(cache.get(ip(1)), None);
+
+        cache.insert(ip(1), None);
+        assert_eq!(cache.get(ip(1)), Some(None));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let mut cache = GeoCache::new(2);
+        cache.insert(ip(1), geo("CN"));
+        cache.insert(ip(2), geo("US"));
+        // Reading ip(1) makes ip(2) the oldest.
+        assert_eq!(cache.get(ip(1)), Some(geo("CN")));
+
+        cache.insert(ip(3), geo("JP"));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(ip(2)), None);
+        assert_eq!(cache.get(ip(1)), Some(geo("CN")));
+        assert_eq!(cache.get(ip(3)), Some(geo("JP")));
+    }
+
+    #[test]
+    fn reinserting_an_entry_does_not_grow_the_cache() {
+        let mut cache = GeoCache::new(2);
+        cache.insert(ip(1), geo("CN"));
+        cache.insert(ip(1), geo("HK"));
+        cache.insert(ip(2), geo("US"));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.order.len(), 2);
+        assert_eq!(cache.get(ip(1)), Some(geo("HK")));
+    }
+}
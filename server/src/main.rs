@@ -1,36 +1,90 @@
+mod admin_ui;
+mod config_signing;
+mod geoip;
+mod online_status;
+mod release_channel;
+mod request_stats;
+mod snapshot_diff;
+mod snapshot_redaction;
+mod snapshot_summary;
+mod sync_encoding;
+mod sync_rejections;
+mod sync_signature;
+mod tasks;
+mod telemetry;
+
+#[cfg(test)]
+mod integration_tests;
+
 use axum::{
-    extract::{ConnectInfo, Path, State},
+    body::Bytes,
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode},
+    middleware,
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use base64::{engine::general_purpose, Engine as _};
+use cc_switch_protocol::{
+    DeviceCommand, DeviceCommandKind, ErrorBody, ServerDirectives, SyncRequest, SyncResponse,
+    PROTOCOL_VERSION,
+};
 use chrono::{DateTime, Utc};
-use maxminddb::Reader;
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, types::Json as SqlxJson, PgPool, Row};
+use sqlx::{
+    postgres::PgPoolOptions, types::Json as SqlxJson, PgPool, Postgres, QueryBuilder, Row,
+};
 use std::{
+    collections::HashMap,
     env,
     net::{IpAddr, SocketAddr},
     path::PathBuf,
     sync::Arc,
+    time::{Duration, Instant},
 };
-use tower_http::{
-    services::{ServeDir, ServeFile},
-    trace::TraceLayer,
-};
+use tower_http::trace::TraceLayer;
+
+use config_signing::ConfigSigner;
+use geoip::{GeoCacheStats, GeoIp, GeoResult};
+use online_status::{OnlineCutoffs, OnlineStatus};
+use release_channel::ReleaseChannel;
+use request_stats::{RequestStats, RequestStatsHour};
+use sync_rejections::{SyncRejectionSummary, SyncRejections};
+use sync_signature::{extract_signature, verify_signature, ReplayCache};
+use tasks::{TaskRegistry, TaskStatus};
+use telemetry::{db_span, record_device_id};
+use tracing::Instrument;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 #[derive(Clone)]
 struct AppState {
     pool: PgPool,
-    geoip: Option<Arc<Reader<Vec<u8>>>>,
+    geoip: Option<Arc<GeoIp>>,
     sync_token: String,
     admin_token: String,
     admin_basic_user: Option<String>,
     admin_basic_password: Option<String>,
     trust_proxy: bool,
     ui_dir: PathBuf,
+    admin_ui_require_auth: bool,
+    sync_signing_secret: Option<String>,
+    require_signed_sync: bool,
+    replay_cache: Arc<ReplayCache>,
+    command_ttl_secs: i64,
+    config_signer: Option<Arc<ConfigSigner>>,
+    snapshot_quota_bytes: Option<i64>,
+    /// Most devices a list may return with `includeSnapshots=true`.
+    list_snapshot_cap: i64,
+    min_client_version: Option<String>,
+    maintenance: Option<Maintenance>,
+    /// New devices wait in `pending` until an admin approves them.
+    require_device_approval: bool,
+    /// Devices seen within this are `online` in device lists.
+    device_online_threshold_secs: i64,
+    request_stats: Arc<RequestStats>,
+    tasks: Arc<TaskRegistry>,
+    sync_rejections: Arc<SyncRejections>,
 }
 
 #[derive(Debug)]
@@ -50,31 +104,103 @@ impl ApiError {
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let body = Json(serde_json::json!({
-            "ok": false,
-            "error": self.message,
-        }));
+        let body = Json(ErrorBody::new(self.status.as_u16(), self.message));
         (self.status, body).into_response()
     }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PingResponse {
+    ok: bool,
+    server_time: String,
+}
+
+#[derive(Clone)]
+struct Maintenance {
+    message: String,
+    retry_after_secs: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnqueueCommandRequest {
+    command: DeviceCommandKind,
+    #[serde(default)]
+    payload: serde_json::Value,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnqueueCommandResponse {
+    ok: bool,
+    id: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceCommandRecord {
+    id: i64,
+    command: String,
+    payload: serde_json::Value,
+    created_at: DateTime<Utc>,
+    acknowledged_at: Option<DateTime<Utc>>,
+    result: Option<String>,
+    status: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceCommandListResponse {
+    commands: Vec<DeviceCommandRecord>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct SyncRequest {
+struct AckCommandRequest {
     device_id: String,
-    app_version: Option<String>,
-    applied_admin_version: Option<i64>,
-    snapshot: serde_json::Value,
-    client_time: Option<String>,
+    command_id: i64,
+    result: Option<String>,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct SyncResponse {
+struct AckCommandResponse {
     ok: bool,
-    server_time: String,
-    admin_config: Option<serde_json::Value>,
-    admin_version: Option<i64>,
+    acknowledged: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadLogsRequest {
+    device_id: String,
+    command_id: i64,
+    content: String,
+    #[serde(default)]
+    truncated: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadLogsResponse {
+    ok: bool,
+    id: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceLogRecord {
+    id: i64,
+    command_id: Option<i64>,
+    content: String,
+    truncated: bool,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceLogListResponse {
+    logs: Vec<DeviceLogRecord>,
 }
 
 #[derive(Deserialize)]
@@ -104,6 +230,98 @@ struct BatchConfigResponse {
     updated: i64,
 }
 
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum PreferConfig {
+    #[default]
+    Newer,
+    Target,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeDeviceRequest {
+    source_device_id: String,
+    #[serde(default)]
+    prefer_config: PreferConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockDeviceRequest {
+    blocked: bool,
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockDeviceResponse {
+    ok: bool,
+    device_id: String,
+    blocked: bool,
+}
+
+/// Whether a device may sync. With `REQUIRE_DEVICE_APPROVAL` new devices start
+/// out `pending`: their snapshots are stored but they get no admin config or
+/// commands. `rejected` devices are refused with 403.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum DeviceApproval {
+    Pending,
+    Active,
+    Rejected,
+}
+
+impl DeviceApproval {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Active => "active",
+            Self::Rejected => "rejected",
+        }
+    }
+
+    /// Fails closed: a value this build does not know holds the device back.
+    fn parse(value: &str) -> Self {
+        match value {
+            "active" => Self::Active,
+            "rejected" => Self::Rejected,
+            _ => Self::Pending,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceApprovalResponse {
+    ok: bool,
+    device_id: String,
+    approval: DeviceApproval,
+}
+
+/// What the sync handler needs to know about the device it just recorded.
+struct DeviceAccess {
+    blocked: bool,
+    blocked_reason: Option<String>,
+    approval: DeviceApproval,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteDeviceResponse {
+    ok: bool,
+    device_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeDeviceResponse {
+    ok: bool,
+    device_id: String,
+    moved_snapshots: u64,
+    admin_version: Option<i64>,
+}
+
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct DeviceSummary {
@@ -115,17 +333,167 @@ struct DeviceSummary {
     geo_region: Option<String>,
     geo_city: Option<String>,
     app_version: Option<String>,
+    /// Derived from `app_version`: `stable`, `beta`, `dev` or `unknown`.
+    channel: String,
+    approval: DeviceApproval,
+    /// Derived from `last_seen` and `DEVICE_ONLINE_THRESHOLD_SECS`.
+    online_status: OnlineStatus,
+    /// The protocol version the device last synced with.
+    protocol_version: i32,
     created_at: Option<DateTime<Utc>>,
     snapshot_count: i64,
     last_snapshot_at: Option<DateTime<Utc>>,
     admin_version: Option<i64>,
     admin_updated_at: Option<DateTime<Utc>>,
+    last_sync_signed: bool,
+    blocked: bool,
+    /// Another device ID has reported one of this device's fingerprints.
+    shared_fingerprint: bool,
+    /// This device ID has reported more than one fingerprint.
+    multiple_fingerprints: bool,
+    /// Latest snapshot with secrets masked; only with `includeSnapshots=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latest_snapshot: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SharedFingerprintGroup {
+    fingerprint_hash: String,
+    device_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MultipleFingerprintGroup {
+    device_id: String,
+    fingerprint_hashes: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DuplicateDevicesResponse {
+    shared_fingerprints: Vec<SharedFingerprintGroup>,
+    multiple_fingerprints: Vec<MultipleFingerprintGroup>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceListQuery {
+    /// Case-insensitive prefix of the device ID or fingerprint hash, at least
+    /// [`MIN_DEVICE_PREFIX_LEN`] characters.
+    q: Option<String>,
+    channel: Option<ReleaseChannel>,
+    approval: Option<DeviceApproval>,
+    status: Option<OnlineStatus>,
+    limit: Option<i64>,
+    /// Matching devices to skip, for paging through the list `limit` at a time.
+    offset: Option<i64>,
+    #[serde(default)]
+    sort_by: DeviceSort,
+    #[serde(default)]
+    sort_dir: SortDir,
+    #[serde(default)]
+    include_snapshots: bool,
+    /// Only count the matching devices; `devices` is left empty.
+    #[serde(default)]
+    count_only: bool,
+}
+
+/// Device list order; devices without a value sort last either way and ties
+/// fall back to the device ID, so pages stay deterministic.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+enum DeviceSort {
+    #[default]
+    LastSeen,
+    CreatedAt,
+    SnapshotCount,
+    AdminUpdatedAt,
+}
+
+impl DeviceSort {
+    fn column(self) -> &'static str {
+        match self {
+            Self::LastSeen => "d.last_seen",
+            Self::CreatedAt => "d.created_at",
+            Self::SnapshotCount => "snapshot_count",
+            Self::AdminUpdatedAt => "a.updated_at",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum SortDir {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortDir {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct DeviceListResponse {
     devices: Vec<DeviceSummary>,
+    /// Devices matching the filters, including those outside this page.
+    total: i64,
+    /// Whether devices past this page match the filters.
+    has_more: bool,
+    meta: DeviceListMeta,
+}
+
+/// Headline numbers over every device matching the filters, not just this
+/// page.
+#[derive(Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+struct DeviceListMeta {
+    /// Same as `total`.
+    total_devices: i64,
+    active_last_24h: i64,
+    with_admin_config: i64,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum ConfigListFilter {
+    Drifted,
+    Pending,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigListQuery {
+    filter: Option<ConfigListFilter>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManagedConfigSummary {
+    device_id: String,
+    version: i64,
+    updated_at: DateTime<Utc>,
+    applied_version: Option<i64>,
+    last_seen: Option<DateTime<Utc>>,
+    last_snapshot_at: Option<DateTime<Utc>>,
+    pending: bool,
+    /// `None` when there is no comparable snapshot yet.
+    drift: Option<bool>,
+    drifted_apps: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManagedConfigListResponse {
+    configs: Vec<ManagedConfigSummary>,
 }
 
 #[derive(Serialize)]
@@ -133,6 +501,7 @@ struct DeviceListResponse {
 struct SnapshotItem {
     id: i64,
     created_at: DateTime<Utc>,
+    client_time: Option<DateTime<Utc>>,
     snapshot: serde_json::Value,
 }
 
@@ -142,6 +511,72 @@ struct AdminConfigItem {
     version: i64,
     updated_at: DateTime<Utc>,
     config: serde_json::Value,
+    signature: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestStatsQuery {
+    hours: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncRejectionsQuery {
+    hours: Option<i64>,
+}
+
+/// Just the device ID of a sync body, read from rejected requests.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClaimedDevice {
+    device_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestStatsResponse {
+    since: DateTime<Utc>,
+    hours: Vec<RequestStatsHour>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskListResponse {
+    tasks: Vec<TaskStatus>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunTaskResponse {
+    ok: bool,
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeoIpStatsResponse {
+    enabled: bool,
+    /// Lookups since startup; `None` without a GeoIP database.
+    cache: Option<GeoCacheStats>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SigningPublicKeyResponse {
+    enabled: bool,
+    algorithm: &'static str,
+    public_key: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageUsage {
+    used_bytes: i64,
+    quota_bytes: Option<i64>,
+    /// Whether the latest stored snapshot is a quota marker, i.e. the last
+    /// full sync got `quotaExceeded`; the same check `sync_device` makes.
+    quota_exceeded: bool,
 }
 
 #[derive(Serialize)]
@@ -150,13 +585,74 @@ struct DeviceDetailResponse {
     device: DeviceSummary,
     snapshots: Vec<SnapshotItem>,
     admin_config: Option<AdminConfigItem>,
+    storage: StorageUsage,
+    /// Provider overview of the latest snapshot; `None` without provider data.
+    parsed_summary: Option<Vec<snapshot_summary::AppSummary>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageStatsQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageConsumer {
+    device_id: String,
+    used_bytes: i64,
+    snapshot_count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageStatsResponse {
+    total_bytes: i64,
+    quota_bytes: Option<i64>,
+    top_consumers: Vec<StorageConsumer>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChannelCount {
+    channel: String,
+    devices: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppVersionCount {
+    app_version: Option<String>,
+    channel: String,
+    devices: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceStatsResponse {
+    total: i64,
+    channels: Vec<ChannelCount>,
+    app_versions: Vec<AppVersionCount>,
 }
 
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_filter(tracing_subscriber::EnvFilter::from_default_env());
+    #[cfg(feature = "otel")]
+    let (otel_layer, tracer_provider) = match telemetry::otlp::layer_from_env() {
+        Some((layer, provider)) => (
+            Some(layer.with_filter(tracing_subscriber::filter::LevelFilter::INFO)),
+            Some(provider),
+        ),
+        None => (None, None),
+    };
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<tracing_subscriber::layer::Identity> = None;
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(otel_layer)
         .init();
 
     let database_url = require_env("DATABASE_URL");
@@ -166,9 +662,32 @@ async fn main() {
     let trust_proxy = env::var("TRUST_PROXY")
         .map(|value| value == "true")
         .unwrap_or(false);
+    let sync_signing_secret = env::var("SYNC_SIGNING_SECRET")
+        .ok()
+        .filter(|value| !value.trim().is_empty());
+    let require_signed_sync = env::var("REQUIRE_SIGNED_SYNC")
+        .map(|value| value == "true")
+        .unwrap_or(false);
+    let require_device_approval = env::var("REQUIRE_DEVICE_APPROVAL")
+        .map(|value| value == "true")
+        .unwrap_or(false);
+    // Clients sync daily, so a little over a day.
+    let device_online_threshold_secs = env::var("DEVICE_ONLINE_THRESHOLD_SECS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(26 * 3600);
+    let command_ttl_secs = env::var("DEVICE_COMMAND_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(7 * 24 * 3600);
     let ui_dir = env::var("UI_DIST_DIR")
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("ui/dist"));
+    let admin_ui_require_auth = env::var("ADMIN_UI_REQUIRE_AUTH")
+        .map(|value| value == "true")
+        .unwrap_or(false);
 
     let (admin_basic_user, admin_basic_password) = match (
         env::var("ADMIN_BASIC_USER").ok(),
@@ -180,44 +699,142 @@ async fn main() {
         _ => (None, None),
     };
 
+    let config_signer = env::var("CONFIG_SIGNING_KEY")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| {
+            ConfigSigner::from_base64(&value)
+                .unwrap_or_else(|err| panic!("invalid CONFIG_SIGNING_KEY: {}", err))
+        })
+        .map(Arc::new);
+
+    // 0 disables the quota.
+    let snapshot_quota_bytes = env::var("DEVICE_SNAPSHOT_QUOTA_BYTES")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(50 * 1024 * 1024);
+    let snapshot_quota_bytes = (snapshot_quota_bytes > 0).then_some(snapshot_quota_bytes);
+    let list_snapshot_cap = env::var("DEVICE_LIST_SNAPSHOT_CAP")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(100);
+
+    let min_client_version = env::var("MIN_CLIENT_VERSION")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    let maintenance = env::var("SYNC_MAINTENANCE_MESSAGE")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(|message| Maintenance {
+            message,
+            retry_after_secs: env::var("SYNC_MAINTENANCE_RETRY_AFTER_SECS")
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok()),
+        });
+
     let geoip = env::var("GEOIP_DB_PATH")
         .ok()
-        .and_then(|path| Reader::open_readfile(path).ok())
+        .and_then(GeoIp::open)
         .map(Arc::new);
 
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-        .expect("failed to connect to database");
+    // 0 gives up on the first failed connection attempt.
+    let db_startup_wait = env::var("DB_STARTUP_WAIT_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(0);
+    let pool = connect_database(&database_url, Duration::from_secs(db_startup_wait)).await;
+
+    let request_stats = Arc::new(RequestStats::default());
+    let tasks = Arc::new(TaskRegistry::default());
+    request_stats::spawn_flush_loop(&tasks, request_stats.clone(), pool.clone());
 
     let state = AppState {
-        pool,
+        pool: pool.clone(),
         geoip,
         sync_token,
         admin_token,
         admin_basic_user,
         admin_basic_password,
         trust_proxy,
-        ui_dir: ui_dir.clone(),
+        ui_dir,
+        admin_ui_require_auth,
+        sync_signing_secret,
+        require_signed_sync,
+        replay_cache: Arc::new(ReplayCache::new()),
+        command_ttl_secs,
+        config_signer,
+        snapshot_quota_bytes,
+        list_snapshot_cap,
+        min_client_version,
+        require_device_approval,
+        device_online_threshold_secs,
+        maintenance,
+        request_stats: request_stats.clone(),
+        tasks,
+        sync_rejections: Arc::new(SyncRejections::default()),
     };
 
-    let ui_router = if ui_dir.exists() {
-        let index_path = ui_dir.join("index.html");
-        Router::new().nest_service(
-            "/admin",
-            ServeDir::new(ui_dir).not_found_service(ServeFile::new(index_path)),
-        )
-    } else {
-        Router::new()
-    };
+    let addr: SocketAddr = bind_addr.parse().expect("invalid BIND_ADDR");
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .expect("failed to bind address");
 
-    let app = Router::new()
-        .merge(ui_router)
+    axum::serve(
+        listener,
+        build_app(state).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .expect("server error");
+
+    // Failures were already logged per bucket; there is no next flush to keep them for.
+    let _ = request_stats.flush(&pool, true).await;
+    #[cfg(feature = "otel")]
+    if let Some(provider) = tracer_provider {
+        let _ = provider.shutdown();
+    }
+}
+
+/// Retries with backoff for up to `wait`, for deployments (docker-compose) where
+/// Postgres comes up after the server.
+async fn connect_database(database_url: &str, wait: Duration) -> PgPool {
+    let deadline = Instant::now() + wait;
+    let mut delay = Duration::from_millis(500);
+    loop {
+        match PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await
+        {
+            Ok(pool) => return pool,
+            Err(err) if Instant::now() + delay < deadline => {
+                tracing::warn!("database not reachable, retrying in {:?}: {}", delay, err);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(Duration::from_secs(10));
+            }
+            Err(err) => panic!("failed to connect to database: {err}"),
+        }
+    }
+}
+
+/// All routes and middleware; callers serve it with `ConnectInfo<SocketAddr>`.
+fn build_app(state: AppState) -> Router {
+    let request_stats = state.request_stats.clone();
+    Router::new()
+        .merge(admin_ui::router(&state))
         .route("/healthz", get(healthz))
         .route("/api/v1/devices/sync", post(sync_device))
+        .route("/api/v1/devices/ping", get(ping_device))
+        .route("/api/v1/devices/commands/ack", post(ack_device_command))
+        .route("/api/v1/devices/logs", post(upload_device_logs))
         .route("/api/v1/admin/devices", get(list_devices))
-        .route("/api/v1/admin/devices/:device_id", get(get_device_detail))
+        .route("/api/v1/admin/devices/duplicates", get(list_duplicate_devices))
+        .route("/api/v1/admin/configs", get(list_managed_configs))
+        .route(
+            "/api/v1/admin/devices/:device_id",
+            get(get_device_detail).delete(delete_device),
+        )
         .route(
             "/api/v1/admin/devices/:device_id/config",
             post(upsert_admin_config),
@@ -226,77 +843,508 @@ async fn main() {
             "/api/v1/admin/devices/config/batch",
             post(batch_admin_config),
         )
+        .route(
+            "/api/v1/admin/devices/:device_id/approve",
+            post(approve_device),
+        )
+        .route(
+            "/api/v1/admin/devices/:device_id/reject",
+            post(reject_device),
+        )
+        .route(
+            "/api/v1/admin/devices/:device_id/block",
+            post(block_device),
+        )
+        .route(
+            "/api/v1/admin/devices/:device_id/merge",
+            post(merge_devices),
+        )
+        .route(
+            "/api/v1/admin/devices/:device_id/commands",
+            get(list_device_commands).post(enqueue_device_command),
+        )
+        .route(
+            "/api/v1/admin/devices/:device_id/logs",
+            get(list_device_logs),
+        )
+        .route("/api/v1/admin/signing/public-key", get(signing_public_key))
+        .route("/api/v1/admin/stats/requests", get(get_request_stats))
+        .route("/api/v1/admin/stats/storage", get(get_storage_stats))
+        .route("/api/v1/admin/stats/devices", get(get_device_stats))
+        .route(
+            "/api/v1/admin/security/sync-rejections",
+            get(get_sync_rejections),
+        )
+        .route("/api/v1/admin/stats/geoip", get(get_geoip_stats))
+        .route("/api/v1/admin/tasks", get(list_tasks))
+        .route("/api/v1/admin/tasks/:name/run", post(run_task))
         .with_state(state)
-        .layer(TraceLayer::new_for_http());
+        .layer(middleware::from_fn_with_state(
+            request_stats,
+            request_stats::track_requests,
+        ))
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::make_request_span))
+}
 
-    let addr: SocketAddr = bind_addr.parse().expect("invalid BIND_ADDR");
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .expect("failed to bind address");
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
 
-    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
-        .await
-        .expect("server error");
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
 async fn healthz() -> &'static str {
     "ok"
 }
 
+/// Authenticated no-op: lets clients check their sync token before enabling
+/// sync, without registering the device or touching its state.
+async fn ping_device(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<PingResponse>, ApiError> {
+    authorize_sync(&state, &headers, addr, || None)?;
+    Ok(Json(PingResponse {
+        ok: true,
+        server_time: Utc::now().to_rfc3339(),
+    }))
+}
+
 async fn sync_device(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Json(payload): Json<SyncRequest>,
+    body: Bytes,
 ) -> Result<Json<SyncResponse>, ApiError> {
-    authorize_bearer(&headers, &state.sync_token)?;
+    authorize_sync(&state, &headers, addr, || {
+        let body = sync_encoding::decode_body(&headers, body.clone()).ok()?;
+        serde_json::from_slice::<ClaimedDevice>(&body)
+            .ok()?
+            .device_id
+    })?;
+    // Signatures cover the uncompressed JSON, so decode before verifying.
+    let body = sync_encoding::decode_body(&headers, body)?;
+
+    let signature = extract_signature(&headers)?;
+    let payload: SyncRequest = serde_json::from_slice(&body)
+        .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, err.to_string()))?;
 
     if payload.device_id.trim().is_empty() {
         return Err(ApiError::new(StatusCode::BAD_REQUEST, "device_id is required"));
     }
+    record_device_id(&payload.device_id);
 
     let now = Utc::now();
+    let signed = match signature {
+        Some(signature) => {
+            match fetch_sync_secret(&state, &payload.device_id).await? {
+                Some(secret) => {
+                    verify_signature(&secret, &signature, &body, now.timestamp())?;
+                    if !state.replay_cache.check_and_insert(&signature, now.timestamp()) {
+                        return Err(ApiError::new(StatusCode::UNAUTHORIZED, "replayed request"));
+                    }
+                    true
+                }
+                None => false,
+            }
+        }
+        None => false,
+    };
+
+    if state.require_signed_sync && !signed {
+        return Err(ApiError::new(StatusCode::UNAUTHORIZED, "signed sync required"));
+    }
+
     let ip = extract_ip(&headers, addr, state.trust_proxy);
-    let geo = ip.and_then(|ip| lookup_geo(&state.geoip, ip));
+    let geo = ip.and_then(|ip| state.geoip.as_ref()?.lookup(ip));
+
+    // Only devices seen for the first time start out pending.
+    let new_approval = if state.require_device_approval {
+        DeviceApproval::Pending
+    } else {
+        DeviceApproval::Active
+    };
+    let access = upsert_device(
+        &state.pool,
+        &payload,
+        now,
+        ip,
+        geo.as_ref(),
+        signed,
+        new_approval,
+    )
+    .await?;
+    if access.approval == DeviceApproval::Rejected {
+        return Err(ApiError::new(StatusCode::FORBIDDEN, "device was rejected"));
+    }
+    // Pending devices left over from approval mode sync normally once it is off.
+    let pending = state.require_device_approval && access.approval == DeviceApproval::Pending;
+
+    let mut directives = ServerDirectives {
+        upgrade_required: state
+            .min_client_version
+            .as_ref()
+            .filter(|min| {
+                payload
+                    .app_version
+                    .as_deref()
+                    .is_none_or(|version| version_older_than(version, min))
+            })
+            .cloned(),
+        ..ServerDirectives::default()
+    };
+    if let Some(maintenance) = &state.maintenance {
+        directives.message = Some(maintenance.message.clone());
+        directives.retry_after_secs = maintenance.retry_after_secs;
+    }
+
+    // Blocked devices are still recorded as seen, but get nothing back and
+    // their snapshot is not stored.
+    if access.blocked {
+        directives.blocked = true;
+        directives.message = access.blocked_reason.or(directives.message);
+        return Ok(Json(
+            SyncResponse {
+                protocol_version: PROTOCOL_VERSION,
+                ok: true,
+                error: None,
+                server_time: Some(now.to_rfc3339()),
+                admin_config: None,
+                admin_version: None,
+                admin_config_signature: None,
+                commands: Vec::new(),
+                quota_exceeded: false,
+                snapshot_required: false,
+                server_directives: Some(directives),
+                feature_flags: None,
+            }
+            .for_client(payload.protocol_version),
+        ));
+    }
+
+    // Heartbeats skip the upload; if the server has nothing to compare against
+    // (e.g. the device was deleted) the client is asked for a full snapshot.
+    let (quota_exceeded, snapshot_required) = if payload.snapshot_unchanged {
+        let has_snapshot = fetch_snapshot_bytes(&state.pool, &payload.device_id).await? > 0;
+        (false, !has_snapshot)
+    } else {
+        // Stored as JSON; a missing snapshot is stored as null, as before.
+        let snapshot = serde_json::to_value(&payload.snapshot)
+            .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        let snapshot_bytes = serde_json::to_vec(&snapshot)
+            .map(|bytes| bytes.len() as i64)
+            .unwrap_or_default();
+        let used_bytes = fetch_snapshot_bytes(&state.pool, &payload.device_id).await?;
+        let quota_exceeded =
+            exceeds_snapshot_quota(state.snapshot_quota_bytes, used_bytes, snapshot_bytes);
+
+        let client_time = payload
+            .client_time
+            .as_deref()
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|value| value.with_timezone(&Utc));
+
+        if quota_exceeded {
+            let marker = quota_marker(snapshot_bytes);
+            insert_snapshot(&state.pool, &payload.device_id, &marker, now, client_time).await?;
+            directives
+                .message
+                .get_or_insert_with(|| "snapshot storage quota exceeded".to_string());
+        } else {
+            insert_snapshot(&state.pool, &payload.device_id, &snapshot, now, client_time).await?;
+        }
+
+        (quota_exceeded, false)
+    };
+
+    // Pending devices only report in until an admin approves them.
+    let (admin, commands) = if pending {
+        directives.pending_approval = true;
+        (None, Vec::new())
+    } else {
+        let admin = fetch_admin_config(&state.pool, &payload.device_id).await?;
+        let commands =
+            fetch_pending_commands(&state.pool, &payload.device_id, now, state.command_ttl_secs)
+                .await?;
+        (admin, commands)
+    };
+
+    // Clients that predate a feature don't get it; see `SyncResponse::for_client`.
+    Ok(Json(
+        SyncResponse {
+            protocol_version: PROTOCOL_VERSION,
+            ok: true,
+            error: None,
+            server_time: Some(now.to_rfc3339()),
+            admin_config: admin.as_ref().map(|item| item.config.clone()),
+            admin_version: admin.as_ref().map(|item| item.version),
+            admin_config_signature: admin.and_then(|item| item.signature),
+            commands,
+            quota_exceeded,
+            snapshot_required,
+            server_directives: (!directives.is_empty()).then_some(directives),
+            feature_flags: None,
+        }
+        .for_client(payload.protocol_version),
+    ))
+}
+
+/// Compares dotted numeric versions; pre-release and build suffixes are ignored.
+fn version_older_than(version: &str, min: &str) -> bool {
+    let parse = |text: &str| -> Vec<u64> {
+        text.trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse::<u64>().unwrap_or(0))
+            .collect()
+    };
+    let (mut version, mut min) = (parse(version), parse(min));
+    let len = version.len().max(min.len());
+    version.resize(len, 0);
+    min.resize(len, 0);
+    version < min
+}
 
-    upsert_device(&state.pool, &payload, now, ip, geo.as_ref()).await?;
-    insert_snapshot(&state.pool, &payload.device_id, &payload.snapshot, now).await?;
+async fn ack_device_command(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<AckCommandRequest>,
+) -> Result<Json<AckCommandResponse>, ApiError> {
+    authorize_sync(&state, &headers, addr, || Some(payload.device_id.clone()))?;
 
-    let admin = fetch_admin_config(&state.pool, &payload.device_id).await?;
+    if payload.device_id.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "device_id is required"));
+    }
 
-    Ok(Json(SyncResponse {
+    let result = sqlx::query(
+        "UPDATE device_commands SET acknowledged_at = $1, result = $2
+         WHERE id = $3 AND device_id = $4 AND acknowledged_at IS NULL",
+    )
+    .bind(Utc::now())
+    .bind(payload.result)
+    .bind(payload.command_id)
+    .bind(&payload.device_id)
+    .execute(&state.pool)
+    .instrument(db_span("ack_device_command"))
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(AckCommandResponse {
         ok: true,
-        server_time: now.to_rfc3339(),
-        admin_config: admin.as_ref().map(|item| item.config.clone()),
-        admin_version: admin.map(|item| item.version),
+        acknowledged: result.rows_affected() > 0,
     }))
 }
 
+/// Upper bound for one uploaded log excerpt; clients send at most a few hundred KB.
+const MAX_LOG_UPLOAD_BYTES: usize = 1024 * 1024;
+
+/// Logs are only accepted in reply to a `collect-logs` command addressed to
+/// the same device, so a sync token alone cannot fill the table.
+async fn upload_device_logs(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<UploadLogsRequest>,
+) -> Result<Json<UploadLogsResponse>, ApiError> {
+    authorize_sync(&state, &headers, addr, || Some(payload.device_id.clone()))?;
+
+    if payload.device_id.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "device_id is required"));
+    }
+    if payload.content.len() > MAX_LOG_UPLOAD_BYTES {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "log upload is too large",
+        ));
+    }
+
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO device_logs (device_id, command_id, content, truncated, created_at)
+         SELECT device_id, id, $3, $4, $5 FROM device_commands
+         WHERE id = $1 AND device_id = $2 AND command = 'collect-logs'
+         RETURNING id",
+    )
+    .bind(payload.command_id)
+    .bind(&payload.device_id)
+    .bind(&payload.content)
+    .bind(payload.truncated)
+    .bind(Utc::now())
+    .fetch_optional(&state.pool)
+    .instrument(db_span("upload_device_logs"))
+    .await
+    .map_err(db_error)?;
+
+    let Some(id) = id else {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "collect-logs command not found",
+        ));
+    };
+
+    Ok(Json(UploadLogsResponse { ok: true, id }))
+}
+
+async fn list_device_logs(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceLogListResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
+
+    let rows = sqlx::query(
+        "SELECT id, command_id, content, truncated, created_at
+         FROM device_logs
+         WHERE device_id = $1
+         ORDER BY created_at DESC
+         LIMIT 20",
+    )
+    .bind(&device_id)
+    .fetch_all(&state.pool)
+    .instrument(db_span("list_device_logs"))
+    .await
+    .map_err(db_error)?;
+
+    let logs = rows
+        .into_iter()
+        .map(|row| DeviceLogRecord {
+            id: row.get("id"),
+            command_id: row.get("command_id"),
+            content: row.get("content"),
+            truncated: row.get("truncated"),
+            created_at: row.get("created_at"),
+        })
+        .collect();
+
+    Ok(Json(DeviceLogListResponse { logs }))
+}
+
 async fn list_devices(
     State(state): State<AppState>,
+    Query(query): Query<DeviceListQuery>,
     headers: HeaderMap,
 ) -> Result<Json<DeviceListResponse>, ApiError> {
     authorize_admin(&headers, &state)?;
+    if device_prefix(&query).is_some_and(|prefix| prefix.chars().count() < MIN_DEVICE_PREFIX_LEN) {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("q must be at least {MIN_DEVICE_PREFIX_LEN} characters"),
+        ));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_DEVICE_LIST_LIMIT)
+        .clamp(1, MAX_DEVICE_LIST_LIMIT);
+    let offset = query.offset.unwrap_or_default().max(0);
+    let cutoffs = OnlineCutoffs::at(Utc::now(), state.device_online_threshold_secs);
+
+    let mut count = QueryBuilder::<Postgres>::new(
+        "SELECT COUNT(*) AS total_devices,
+                COUNT(*) FILTER (WHERE d.last_seen >= ",
+    );
+    count.push_bind(Utc::now() - chrono::Duration::hours(24));
+    count.push(
+        ") AS active_last_24h,
+                COUNT(*) FILTER (WHERE EXISTS (SELECT 1 FROM admin_configs a
+                                               WHERE a.device_id = d.device_id))
+                    AS with_admin_config
+         FROM devices d",
+    );
+    push_device_filters(&mut count, &query, cutoffs);
+    let meta: DeviceListMeta = count
+        .build_query_as()
+        .fetch_one(&state.pool)
+        .instrument(db_span("list_devices"))
+        .await
+        .map_err(db_error)?;
+    let total = meta.total_devices;
+
+    if query.count_only {
+        return Ok(Json(DeviceListResponse {
+            devices: Vec::new(),
+            total,
+            has_more: total > offset,
+            meta,
+        }));
+    }
 
-    let rows = sqlx::query(
+    if query.include_snapshots && (total - offset).clamp(0, limit) > state.list_snapshot_cap {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "includeSnapshots is limited to {} devices; narrow the list with limit or q, \
+                 or fetch snapshots from the device detail endpoint",
+                state.list_snapshot_cap
+            ),
+        ));
+    }
+
+    let mut list = QueryBuilder::<Postgres>::new(
         "SELECT d.device_id, d.fingerprint_hash, d.last_seen, d.last_ip, d.geo_country, d.geo_region, d.geo_city,
-                d.app_version, d.created_at,
+                d.app_version, d.channel, d.approval, d.protocol_version, d.created_at,
                 COUNT(s.id) AS snapshot_count,
                 MAX(s.created_at) AS last_snapshot_at,
                 a.version AS admin_version,
-                a.updated_at AS admin_updated_at
+                a.updated_at AS admin_updated_at,
+                d.last_sync_signed,
+                d.blocked,
+                EXISTS (SELECT 1 FROM device_fingerprints f
+                        JOIN device_fingerprints o
+                          ON o.fingerprint_hash = f.fingerprint_hash AND o.device_id <> f.device_id
+                        WHERE f.device_id = d.device_id)
+                    AS shared_fingerprint,
+                (SELECT COUNT(*) FROM device_fingerprints f WHERE f.device_id = d.device_id) > 1
+                    AS multiple_fingerprints
          FROM devices d
          LEFT JOIN config_snapshots s ON d.device_id = s.device_id
-         LEFT JOIN admin_configs a ON d.device_id = a.device_id
-         GROUP BY d.device_id, d.fingerprint_hash, d.last_seen, d.last_ip, d.geo_country, d.geo_region, d.geo_city,
-                  d.app_version, d.created_at, a.version, a.updated_at
-         ORDER BY d.last_seen DESC NULLS LAST",
-    )
-    .fetch_all(&state.pool)
-    .await
-    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+         LEFT JOIN admin_configs a ON d.device_id = a.device_id",
+    );
+    push_device_filters(&mut list, &query, cutoffs);
+    list.push(
+        " GROUP BY d.device_id, d.fingerprint_hash, d.last_seen, d.last_ip, d.geo_country, d.geo_region, d.geo_city,
+                  d.app_version, d.channel, d.approval, d.protocol_version, d.created_at, a.version,
+                  a.updated_at,
+                  d.last_sync_signed, d.blocked",
+    );
+    // Only whitelisted column names reach the SQL
+    list.push(format!(
+        " ORDER BY {} {} NULLS LAST, d.device_id LIMIT ",
+        query.sort_by.column(),
+        query.sort_dir.as_sql()
+    ));
+    list.push_bind(limit);
+    list.push(" OFFSET ");
+    list.push_bind(offset);
+    let rows = list
+        .build()
+        .fetch_all(&state.pool)
+        .instrument(db_span("list_devices"))
+        .await
+        .map_err(db_error)?;
 
-    let devices = rows
+    let mut devices = rows
         .into_iter()
         .map(|row| DeviceSummary {
             device_id: row.get("device_id"),
@@ -307,166 +1355,1043 @@ async fn list_devices(
             geo_region: row.get("geo_region"),
             geo_city: row.get("geo_city"),
             app_version: row.get("app_version"),
+            channel: row.get("channel"),
+            approval: DeviceApproval::parse(row.get("approval")),
+            online_status: cutoffs.status(row.get("last_seen")),
+            protocol_version: row.get("protocol_version"),
             created_at: row.get("created_at"),
-            snapshot_count: row
-                .try_get::<i64, _>("snapshot_count")
-                .unwrap_or_default(),
+            snapshot_count: row.try_get::<i64, _>("snapshot_count").unwrap_or_default(),
             last_snapshot_at: row.get("last_snapshot_at"),
             admin_version: row.get("admin_version"),
             admin_updated_at: row.get("admin_updated_at"),
+            last_sync_signed: row.get("last_sync_signed"),
+            blocked: row.get("blocked"),
+            shared_fingerprint: row.get("shared_fingerprint"),
+            multiple_fingerprints: row.get("multiple_fingerprints"),
+            latest_snapshot: None,
         })
+        .collect::<Vec<_>>();
+
+    if query.include_snapshots {
+        attach_latest_snapshots(&state.pool, &mut devices).await?;
+    }
+
+    Ok(Json(DeviceListResponse {
+        has_more: total > offset + devices.len() as i64,
+        devices,
+        total,
+        meta,
+    }))
+}
+
+const DEFAULT_DEVICE_LIST_LIMIT: i64 = 50;
+const MAX_DEVICE_LIST_LIMIT: i64 = 1000;
+/// Shorter prefixes match too much of the fleet to be worth an index lookup.
+const MIN_DEVICE_PREFIX_LEN: usize = 4;
+
+/// Kept out of the list query so the plain list never pays for it.
+async fn attach_latest_snapshots(
+    pool: &PgPool,
+    devices: &mut [DeviceSummary],
+) -> Result<(), ApiError> {
+    let device_ids: Vec<String> = devices
+        .iter()
+        .map(|device| device.device_id.clone())
         .collect();
+    let rows = sqlx::query_as::<_, (String, SqlxJson<serde_json::Value>)>(
+        "SELECT d.device_id, latest.snapshot
+         FROM UNNEST($1::text[]) AS d(device_id)
+         CROSS JOIN LATERAL (
+             SELECT snapshot FROM config_snapshots s
+             WHERE s.device_id = d.device_id
+             ORDER BY s.created_at DESC
+             LIMIT 1
+         ) latest",
+    )
+    .bind(&device_ids)
+    .fetch_all(pool)
+    .instrument(db_span("list_devices_snapshots"))
+    .await
+    .map_err(db_error)?;
+
+    let mut snapshots: HashMap<String, serde_json::Value> = rows
+        .into_iter()
+        .map(|(device_id, snapshot)| (device_id, snapshot.0))
+        .collect();
+    for device in devices {
+        device.latest_snapshot = snapshots.remove(&device.device_id).map(|mut snapshot| {
+            snapshot_redaction::redact(&mut snapshot);
+            snapshot
+        });
+    }
+    Ok(())
+}
+
+/// The trimmed `q` prefix, if one was given.
+fn device_prefix(query: &DeviceListQuery) -> Option<&str> {
+    query
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|prefix| !prefix.is_empty())
+}
+
+/// Escapes LIKE wildcards so `text` matches literally.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// The WHERE clause of the device list; its count uses the same one so the two
+/// always agree. Expects `devices` aliased as `d`. Only narrows `devices` rows,
+/// so it applies before any grouping.
+fn push_device_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    query: &DeviceListQuery,
+    cutoffs: OnlineCutoffs,
+) {
+    let mut keyword = " WHERE ";
+    if let Some(prefix) = device_prefix(query) {
+        // Matches the LOWER(...) text_pattern_ops indexes
+        let pattern = format!("{}%", escape_like(&prefix.to_lowercase()));
+        builder
+            .push(keyword)
+            .push("(LOWER(d.device_id) LIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR LOWER(d.fingerprint_hash) LIKE ")
+            .push_bind(pattern)
+            .push(")");
+        keyword = " AND ";
+    }
+    if let Some(channel) = query.channel {
+        builder.push(keyword).push("d.channel = ");
+        keyword = " AND ";
+        builder.push_bind(channel.as_str());
+    }
+    if let Some(approval) = query.approval {
+        builder.push(keyword).push("d.approval = ");
+        keyword = " AND ";
+        builder.push_bind(approval.as_str());
+    }
+    if let Some(status) = query.status {
+        builder.push(keyword);
+        match status {
+            OnlineStatus::Online => {
+                builder
+                    .push("d.last_seen >= ")
+                    .push_bind(cutoffs.online_since);
+            }
+            OnlineStatus::Stale => {
+                builder
+                    .push("d.last_seen < ")
+                    .push_bind(cutoffs.online_since)
+                    .push(" AND d.last_seen >= ")
+                    .push_bind(cutoffs.stale_since);
+            }
+            OnlineStatus::Offline => {
+                builder
+                    .push("(d.last_seen IS NULL OR d.last_seen < ")
+                    .push_bind(cutoffs.stale_since)
+                    .push(")");
+            }
+        }
+    }
+}
+
+async fn list_duplicate_devices(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<DuplicateDevicesResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    let shared_fingerprints = sqlx::query_as::<_, (String, Vec<String>)>(
+        "SELECT fingerprint_hash, array_agg(device_id ORDER BY last_seen DESC)
+         FROM device_fingerprints
+         GROUP BY fingerprint_hash
+         HAVING COUNT(*) > 1
+         ORDER BY COUNT(*) DESC, fingerprint_hash",
+    )
+    .fetch_all(&state.pool)
+    .instrument(db_span("list_duplicate_devices"))
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .map(|(fingerprint_hash, device_ids)| SharedFingerprintGroup {
+        fingerprint_hash,
+        device_ids,
+    })
+    .collect();
+
+    let multiple_fingerprints = sqlx::query_as::<_, (String, Vec<String>)>(
+        "SELECT device_id, array_agg(fingerprint_hash ORDER BY last_seen DESC)
+         FROM device_fingerprints
+         GROUP BY device_id
+         HAVING COUNT(*) > 1
+         ORDER BY COUNT(*) DESC, device_id",
+    )
+    .fetch_all(&state.pool)
+    .instrument(db_span("list_duplicate_devices"))
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .map(|(device_id, fingerprint_hashes)| MultipleFingerprintGroup {
+        device_id,
+        fingerprint_hashes,
+    })
+    .collect();
+
+    Ok(Json(DuplicateDevicesResponse {
+        shared_fingerprints,
+        multiple_fingerprints,
+    }))
+}
+
+async fn list_managed_configs(
+    State(state): State<AppState>,
+    Query(query): Query<ConfigListQuery>,
+    headers: HeaderMap,
+) -> Result<Json<ManagedConfigListResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    let rows = sqlx::query(
+        "SELECT a.device_id, a.version, a.config, a.updated_at,
+                d.applied_admin_version, d.last_seen,
+                s.snapshot AS latest_snapshot, s.created_at AS last_snapshot_at
+         FROM admin_configs a
+         JOIN devices d ON d.device_id = a.device_id
+         LEFT JOIN LATERAL (
+             SELECT snapshot, created_at FROM config_snapshots
+             WHERE device_id = a.device_id
+             ORDER BY created_at DESC
+             LIMIT 1
+         ) s ON TRUE
+         ORDER BY a.updated_at DESC",
+    )
+    .fetch_all(&state.pool)
+    .instrument(db_span("list_managed_configs"))
+    .await
+    .map_err(db_error)?;
+
+    let configs = rows
+        .into_iter()
+        .map(|row| {
+            let version: i64 = row.get("version");
+            let applied_version: Option<i64> = row.get("applied_admin_version");
+            let config = row
+                .try_get::<SqlxJson<serde_json::Value>, _>("config")
+                .map(|value| value.0)
+                .unwrap_or(serde_json::Value::Null);
+            let snapshot = row
+                .try_get::<Option<SqlxJson<serde_json::Value>>, _>("latest_snapshot")
+                .ok()
+                .flatten()
+                .map(|value| value.0)
+                .filter(snapshot_diff::is_comparable);
+            let drifted_apps: Vec<String> = snapshot
+                .as_ref()
+                .map(|snapshot| {
+                    snapshot_diff::diff_config(&config, snapshot)
+                        .into_iter()
+                        .map(|diff| diff.app)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            ManagedConfigSummary {
+                device_id: row.get("device_id"),
+                version,
+                updated_at: row.get("updated_at"),
+                applied_version,
+                last_seen: row.get("last_seen"),
+                last_snapshot_at: row.get("last_snapshot_at"),
+                pending: applied_version.is_none_or(|applied| applied < version),
+                drift: snapshot.as_ref().map(|_| !drifted_apps.is_empty()),
+                drifted_apps,
+            }
+        })
+        .filter(|item| match query.filter {
+            Some(ConfigListFilter::Drifted) => item.drift == Some(true),
+            Some(ConfigListFilter::Pending) => item.pending,
+            None => true,
+        })
+        .collect();
+
+    Ok(Json(ManagedConfigListResponse { configs }))
+}
+
+async fn get_device_detail(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceDetailResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
+
+    let row = sqlx::query(
+        "SELECT d.device_id, d.fingerprint_hash, d.last_seen, d.last_ip, d.geo_country, d.geo_region,
+                d.geo_city, d.app_version, d.channel, d.approval, d.protocol_version, d.created_at,
+                d.last_sync_signed,
+                d.snapshot_bytes, d.blocked,
+                EXISTS (SELECT 1 FROM device_fingerprints f
+                        JOIN device_fingerprints o
+                          ON o.fingerprint_hash = f.fingerprint_hash AND o.device_id <> f.device_id
+                        WHERE f.device_id = d.device_id)
+                    AS shared_fingerprint,
+                (SELECT COUNT(*) FROM device_fingerprints f WHERE f.device_id = d.device_id) > 1
+                    AS multiple_fingerprints
+         FROM devices d WHERE d.device_id = $1",
+    )
+    .bind(&device_id)
+    .fetch_optional(&state.pool)
+    .instrument(db_span("get_device_detail"))
+    .await
+    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+
+    let Some(row) = row else {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "device not found"));
+    };
+
+    let summary_row = sqlx::query(
+        "SELECT COUNT(id) AS snapshot_count, MAX(created_at) AS last_snapshot_at
+         FROM config_snapshots WHERE device_id = $1",
+    )
+    .bind(&device_id)
+    .fetch_one(&state.pool)
+    .instrument(db_span("get_device_detail"))
+    .await
+    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+
+    let snapshot_rows = sqlx::query(
+        "SELECT id, created_at, client_time, snapshot
+         FROM config_snapshots
+         WHERE device_id = $1
+         ORDER BY created_at DESC
+         LIMIT 20",
+    )
+    .bind(&device_id)
+    .fetch_all(&state.pool)
+    .instrument(db_span("get_device_detail"))
+    .await
+    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+
+    let snapshots: Vec<SnapshotItem> = snapshot_rows
+        .into_iter()
+        .map(|row| SnapshotItem {
+            id: row.get("id"),
+            created_at: row.get("created_at"),
+            client_time: row.get("client_time"),
+            snapshot: row
+                .try_get::<SqlxJson<serde_json::Value>, _>("snapshot")
+                .map(|value| value.0)
+                .unwrap_or(serde_json::Value::Null),
+        })
+        .collect();
+    let parsed_summary = snapshots
+        .first()
+        .and_then(|latest| snapshot_summary::summarize(&latest.snapshot));
+
+    let admin_row = sqlx::query_as::<
+        _,
+        (i64, SqlxJson<serde_json::Value>, DateTime<Utc>, Option<String>),
+    >(
+        "SELECT version, config, updated_at, signature FROM admin_configs WHERE device_id = $1",
+    )
+    .bind(&device_id)
+    .fetch_optional(&state.pool)
+    .instrument(db_span("get_device_detail"))
+    .await
+    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+
+    let admin_config = admin_row.map(|(version, config, updated_at, signature)| AdminConfigItem {
+        version,
+        updated_at,
+        config: config.0,
+        signature,
+    });
+
+    let used_bytes: i64 = row.get("snapshot_bytes");
+    let storage = StorageUsage {
+        used_bytes,
+        quota_bytes: state.snapshot_quota_bytes,
+        quota_exceeded: snapshots
+            .first()
+            .is_some_and(|latest| is_quota_marker(&latest.snapshot)),
+    };
+
+    let (admin_version, admin_updated_at) = admin_config
+        .as_ref()
+        .map(|config| (Some(config.version), Some(config.updated_at)))
+        .unwrap_or((None, None));
+
+    let device = DeviceSummary {
+        device_id: row.get("device_id"),
+        fingerprint_hash: row.get("fingerprint_hash"),
+        last_seen: row.get("last_seen"),
+        last_ip: row.get("last_ip"),
+        geo_country: row.get("geo_country"),
+        geo_region: row.get("geo_region"),
+        geo_city: row.get("geo_city"),
+        app_version: row.get("app_version"),
+        channel: row.get("channel"),
+        approval: DeviceApproval::parse(row.get("approval")),
+        online_status: OnlineCutoffs::at(Utc::now(), state.device_online_threshold_secs)
+            .status(row.get("last_seen")),
+        protocol_version: row.get("protocol_version"),
+        created_at: row.get("created_at"),
+        snapshot_count: summary_row
+            .try_get::<i64, _>("snapshot_count")
+            .unwrap_or_default(),
+        last_snapshot_at: summary_row.get("last_snapshot_at"),
+        admin_version,
+        admin_updated_at,
+        last_sync_signed: row.get("last_sync_signed"),
+        blocked: row.get("blocked"),
+        shared_fingerprint: row.get("shared_fingerprint"),
+        multiple_fingerprints: row.get("multiple_fingerprints"),
+        latest_snapshot: None,
+    };
+
+    Ok(Json(DeviceDetailResponse {
+        device,
+        snapshots,
+        admin_config,
+        storage,
+        parsed_summary,
+    }))
+}
+
+async fn upsert_admin_config(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<AdminConfigRequest>,
+) -> Result<Json<AdminConfigResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
+
+    if device_id.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "device_id is required"));
+    }
+
+    let now = Utc::now();
+    let version = upsert_admin_config_value(&state, &device_id, &payload.config, now).await?;
+
+    Ok(Json(AdminConfigResponse {
+        ok: true,
+        version,
+    }))
+}
+
+async fn batch_admin_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<BatchConfigRequest>,
+) -> Result<Json<BatchConfigResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    if payload.device_ids.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "device_ids is required"));
+    }
+
+    let existing_ids = sqlx::query_scalar::<_, String>(
+        "SELECT device_id FROM devices WHERE device_id = ANY($1)",
+    )
+    .bind(&payload.device_ids)
+    .fetch_all(&state.pool)
+    .instrument(db_span("batch_admin_config"))
+    .await
+    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+
+    let now = Utc::now();
+    let mut updated = 0;
+
+    for device_id in existing_ids {
+        upsert_admin_config_value(&state, &device_id, &payload.config, now).await?;
+        updated += 1;
+    }
+
+    Ok(Json(BatchConfigResponse { ok: true, updated }))
+}
+
+async fn list_tasks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<TaskListResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    Ok(Json(TaskListResponse {
+        tasks: state.tasks.list(),
+    }))
+}
+
+async fn run_task(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<RunTaskResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    if !state.tasks.run_now(&name) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "task not found"));
+    }
+    Ok(Json(RunTaskResponse { ok: true, name }))
+}
+
+async fn get_geoip_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<GeoIpStatsResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    Ok(Json(GeoIpStatsResponse {
+        enabled: state.geoip.is_some(),
+        cache: state.geoip.as_ref().map(|geoip| geoip.cache_stats()),
+    }))
+}
+
+async fn get_request_stats(
+    State(state): State<AppState>,
+    Query(query): Query<RequestStatsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<RequestStatsResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    let hours = query.hours.unwrap_or(24).clamp(1, 24 * 31);
+    let since = Utc::now() - chrono::Duration::hours(hours);
+    let hours = request_stats::load_hourly(&state.pool, since)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(RequestStatsResponse { since, hours }))
+}
+
+async fn get_storage_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StorageStatsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<StorageStatsResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+    let total_bytes = sqlx::query_scalar::<_, Option<i64>>(
+        "SELECT SUM(snapshot_bytes)::BIGINT FROM devices",
+    )
+    .fetch_one(&state.pool)
+    .instrument(db_span("get_storage_stats"))
+    .await
+    .map_err(db_error)?
+    .unwrap_or_default();
+
+    let top_consumers = sqlx::query_as::<_, (String, i64, i64)>(
+        "SELECT d.device_id, d.snapshot_bytes,
+                (SELECT COUNT(*) FROM config_snapshots s WHERE s.device_id = d.device_id)
+         FROM devices d
+         ORDER BY d.snapshot_bytes DESC
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .instrument(db_span("get_storage_stats"))
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .map(|(device_id, used_bytes, snapshot_count)| StorageConsumer {
+        device_id,
+        used_bytes,
+        snapshot_count,
+    })
+    .collect();
+
+    Ok(Json(StorageStatsResponse {
+        total_bytes,
+        quota_bytes: state.snapshot_quota_bytes,
+        top_consumers,
+    }))
+}
+
+async fn get_device_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceStatsResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    let channels: Vec<ChannelCount> = sqlx::query_as::<_, (String, i64)>(
+        "SELECT channel, COUNT(*) FROM devices GROUP BY channel ORDER BY COUNT(*) DESC, channel",
+    )
+    .fetch_all(&state.pool)
+    .instrument(db_span("get_device_stats"))
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .map(|(channel, devices)| ChannelCount { channel, devices })
+    .collect();
+
+    let app_versions = sqlx::query_as::<_, (Option<String>, String, i64)>(
+        "SELECT app_version, channel, COUNT(*) FROM devices
+         GROUP BY app_version, channel
+         ORDER BY COUNT(*) DESC, app_version",
+    )
+    .fetch_all(&state.pool)
+    .instrument(db_span("get_device_stats"))
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .map(|(app_version, channel, devices)| AppVersionCount {
+        app_version,
+        channel,
+        devices,
+    })
+    .collect();
 
-    Ok(Json(DeviceListResponse { devices }))
+    Ok(Json(DeviceStatsResponse {
+        total: channels.iter().map(|count| count.devices).sum(),
+        channels,
+        app_versions,
+    }))
 }
 
-async fn get_device_detail(
+async fn get_sync_rejections(
+    State(state): State<AppState>,
+    Query(query): Query<SyncRejectionsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<SyncRejectionSummary>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    let hours = query.hours.unwrap_or(24).clamp(1, 24 * 31);
+    let since = Utc::now() - chrono::Duration::hours(hours);
+    Ok(Json(state.sync_rejections.summary(since)))
+}
+
+async fn signing_public_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SigningPublicKeyResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+
+    Ok(Json(SigningPublicKeyResponse {
+        enabled: state.config_signer.is_some(),
+        algorithm: "ed25519",
+        public_key: state
+            .config_signer
+            .as_ref()
+            .map(|signer| signer.public_key_base64()),
+    }))
+}
+
+async fn enqueue_device_command(
     State(state): State<AppState>,
     Path(device_id): Path<String>,
     headers: HeaderMap,
-) -> Result<Json<DeviceDetailResponse>, ApiError> {
+    Json(payload): Json<EnqueueCommandRequest>,
+) -> Result<Json<EnqueueCommandResponse>, ApiError> {
     authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
 
-    let row = sqlx::query(
-        "SELECT device_id, fingerprint_hash, last_seen, last_ip, geo_country, geo_region, geo_city,
-                app_version, created_at
-         FROM devices WHERE device_id = $1",
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO device_commands (device_id, command, payload, created_at)
+         SELECT device_id, $2, $3, $4 FROM devices WHERE device_id = $1
+         RETURNING id",
     )
     .bind(&device_id)
+    .bind(payload.command.as_str())
+    .bind(SqlxJson(payload.payload))
+    .bind(Utc::now())
     .fetch_optional(&state.pool)
+    .instrument(db_span("enqueue_device_command"))
     .await
-    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    .map_err(db_error)?;
 
-    let Some(row) = row else {
+    let Some(id) = id else {
         return Err(ApiError::new(StatusCode::NOT_FOUND, "device not found"));
     };
 
-    let summary_row = sqlx::query(
-        "SELECT COUNT(id) AS snapshot_count, MAX(created_at) AS last_snapshot_at
-         FROM config_snapshots WHERE device_id = $1",
-    )
-    .bind(&device_id)
-    .fetch_one(&state.pool)
-    .await
-    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(Json(EnqueueCommandResponse { ok: true, id }))
+}
 
-    let snapshot_rows = sqlx::query(
-        "SELECT id, created_at, snapshot
-         FROM config_snapshots
+async fn list_device_commands(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceCommandListResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
+
+    let rows = sqlx::query(
+        "SELECT id, command, payload, created_at, acknowledged_at, result
+         FROM device_commands
          WHERE device_id = $1
          ORDER BY created_at DESC
-         LIMIT 20",
+         LIMIT 100",
     )
     .bind(&device_id)
     .fetch_all(&state.pool)
+    .instrument(db_span("list_device_commands"))
     .await
-    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    .map_err(db_error)?;
 
-    let snapshots = snapshot_rows
+    let expires_before = Utc::now() - chrono::Duration::seconds(state.command_ttl_secs);
+    let commands = rows
         .into_iter()
-        .map(|row| SnapshotItem {
-            id: row.get("id"),
-            created_at: row.get("created_at"),
-            snapshot: row
-                .try_get::<SqlxJson<serde_json::Value>, _>("snapshot")
-                .map(|value| value.0)
-                .unwrap_or(serde_json::Value::Null),
+        .map(|row| {
+            let created_at: DateTime<Utc> = row.get("created_at");
+            let acknowledged_at: Option<DateTime<Utc>> = row.get("acknowledged_at");
+            let status = match acknowledged_at {
+                Some(_) => "acknowledged",
+                None if created_at <= expires_before => "expired",
+                None => "pending",
+            };
+            DeviceCommandRecord {
+                id: row.get("id"),
+                command: row.get("command"),
+                payload: row
+                    .try_get::<SqlxJson<serde_json::Value>, _>("payload")
+                    .map(|value| value.0)
+                    .unwrap_or(serde_json::Value::Null),
+                created_at,
+                acknowledged_at,
+                result: row.get("result"),
+                status,
+            }
         })
         .collect();
 
-    let admin_row = sqlx::query_as::<_, (i64, SqlxJson<serde_json::Value>, DateTime<Utc>)>(
-        "SELECT version, config, updated_at FROM admin_configs WHERE device_id = $1",
-    )
-    .bind(&device_id)
-    .fetch_optional(&state.pool)
-    .await
-    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(Json(DeviceCommandListResponse { commands }))
+}
 
-    let admin_config = admin_row.map(|(version, config, updated_at)| AdminConfigItem {
-        version,
-        updated_at,
-        config: config.0,
-    });
+/// Snapshots, admin configs and queued commands go with the device row via
+/// `ON DELETE CASCADE`.
+async fn delete_device(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<DeleteDeviceResponse>, ApiError> {
+    authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
 
-    let (admin_version, admin_updated_at) = admin_config
-        .as_ref()
-        .map(|config| (Some(config.version), Some(config.updated_at)))
-        .unwrap_or((None, None));
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
 
-    let device = DeviceSummary {
-        device_id: row.get("device_id"),
-        fingerprint_hash: row.get("fingerprint_hash"),
-        last_seen: row.get("last_seen"),
-        last_ip: row.get("last_ip"),
-        geo_country: row.get("geo_country"),
-        geo_region: row.get("geo_region"),
-        geo_city: row.get("geo_city"),
-        app_version: row.get("app_version"),
-        created_at: row.get("created_at"),
-        snapshot_count: summary_row
-            .try_get::<i64, _>("snapshot_count")
-            .unwrap_or_default(),
-        last_snapshot_at: summary_row.get("last_snapshot_at"),
-        admin_version,
-        admin_updated_at,
-    };
+    let deleted = sqlx::query("DELETE FROM devices WHERE device_id = $1")
+        .bind(&device_id)
+        .execute(&mut *tx)
+        .instrument(db_span("delete_device"))
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "device not found"));
+    }
 
-    Ok(Json(DeviceDetailResponse {
-        device,
-        snapshots,
-        admin_config,
+    insert_audit_log(
+        &mut tx,
+        "device.delete",
+        &device_id,
+        serde_json::json!({}),
+        Utc::now(),
+    )
+    .await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(Json(DeleteDeviceResponse {
+        ok: true,
+        device_id,
     }))
 }
 
-async fn upsert_admin_config(
+async fn block_device(
     State(state): State<AppState>,
     Path(device_id): Path<String>,
     headers: HeaderMap,
-    Json(payload): Json<AdminConfigRequest>,
-) -> Result<Json<AdminConfigResponse>, ApiError> {
+    Json(payload): Json<BlockDeviceRequest>,
+) -> Result<Json<BlockDeviceResponse>, ApiError> {
     authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
 
-    if device_id.trim().is_empty() {
-        return Err(ApiError::new(StatusCode::BAD_REQUEST, "device_id is required"));
+    let reason = payload
+        .reason
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty() && payload.blocked);
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+
+    let updated = sqlx::query(
+        "UPDATE devices SET blocked = $2, blocked_reason = $3 WHERE device_id = $1",
+    )
+    .bind(&device_id)
+    .bind(payload.blocked)
+    .bind(&reason)
+    .execute(&mut *tx)
+    .instrument(db_span("block_device"))
+    .await
+    .map_err(db_error)?
+    .rows_affected();
+    if updated == 0 {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "device not found"));
     }
 
-    let now = Utc::now();
-    let version = upsert_admin_config_value(&state.pool, &device_id, &payload.config, now).await?;
+    insert_audit_log(
+        &mut tx,
+        if payload.blocked {
+            "device.block"
+        } else {
+            "device.unblock"
+        },
+        &device_id,
+        serde_json::json!({ "reason": reason }),
+        Utc::now(),
+    )
+    .await?;
 
-    Ok(Json(AdminConfigResponse {
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(Json(BlockDeviceResponse {
         ok: true,
-        version,
+        device_id,
+        blocked: payload.blocked,
     }))
 }
 
-async fn batch_admin_config(
+async fn approve_device(
     State(state): State<AppState>,
+    Path(device_id): Path<String>,
     headers: HeaderMap,
-    Json(payload): Json<BatchConfigRequest>,
-) -> Result<Json<BatchConfigResponse>, ApiError> {
+) -> Result<Json<DeviceApprovalResponse>, ApiError> {
+    set_device_approval(&state, &headers, device_id, DeviceApproval::Active).await
+}
+
+async fn reject_device(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceApprovalResponse>, ApiError> {
+    set_device_approval(&state, &headers, device_id, DeviceApproval::Rejected).await
+}
+
+async fn set_device_approval(
+    state: &AppState,
+    headers: &HeaderMap,
+    device_id: String,
+    approval: DeviceApproval,
+) -> Result<Json<DeviceApprovalResponse>, ApiError> {
+    authorize_admin(headers, state)?;
+    record_device_id(&device_id);
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+
+    let previous = sqlx::query_scalar::<_, String>(
+        "SELECT approval FROM devices WHERE device_id = $1 FOR UPDATE",
+    )
+    .bind(&device_id)
+    .fetch_optional(&mut *tx)
+    .instrument(db_span("set_device_approval"))
+    .await
+    .map_err(db_error)?;
+    let Some(previous) = previous else {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "device not found"));
+    };
+    sqlx::query("UPDATE devices SET approval = $2 WHERE device_id = $1")
+        .bind(&device_id)
+        .bind(approval.as_str())
+        .execute(&mut *tx)
+        .instrument(db_span("set_device_approval"))
+        .await
+        .map_err(db_error)?;
+
+    insert_audit_log(
+        &mut tx,
+        match approval {
+            DeviceApproval::Rejected => "device.reject",
+            _ => "device.approve",
+        },
+        &device_id,
+        serde_json::json!({ "previous": previous }),
+        Utc::now(),
+    )
+    .await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(Json(DeviceApprovalResponse {
+        ok: true,
+        device_id,
+        approval,
+    }))
+}
+
+async fn merge_devices(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<MergeDeviceRequest>,
+) -> Result<Json<MergeDeviceResponse>, ApiError> {
     authorize_admin(&headers, &state)?;
+    record_device_id(&device_id);
 
-    if payload.device_ids.is_empty() {
-        return Err(ApiError::new(StatusCode::BAD_REQUEST, "device_ids is required"));
+    let source_id = payload.source_device_id.trim();
+    if source_id.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "sourceDeviceId is required"));
+    }
+    if source_id == device_id {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "cannot merge a device into itself",
+        ));
     }
 
-    let existing_ids = sqlx::query_scalar::<_, String>(
-        "SELECT device_id FROM devices WHERE device_id = ANY($1)",
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+
+    let found = sqlx::query_scalar::<_, String>(
+        "SELECT device_id FROM devices WHERE device_id = ANY($1) FOR UPDATE",
     )
-    .bind(&payload.device_ids)
-    .fetch_all(&state.pool)
+    .bind(vec![device_id.clone(), source_id.to_string()])
+    .fetch_all(&mut *tx)
+    .instrument(db_span("merge_devices"))
     .await
-    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    .map_err(db_error)?;
+    if !found.contains(&device_id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "target device not found"));
+    }
+    if !found.iter().any(|id| id == source_id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "source device not found"));
+    }
 
-    let now = Utc::now();
-    let mut updated = 0;
+    let moved_snapshots =
+        sqlx::query("UPDATE config_snapshots SET device_id = $1 WHERE device_id = $2")
+            .bind(&device_id)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .instrument(db_span("merge_devices"))
+            .await
+            .map_err(db_error)?
+            .rows_affected();
+
+    let configs = sqlx::query_as::<_, (String, i64, DateTime<Utc>)>(
+        "SELECT device_id, version, updated_at FROM admin_configs WHERE device_id = ANY($1)",
+    )
+    .bind(vec![device_id.clone(), source_id.to_string()])
+    .fetch_all(&mut *tx)
+    .instrument(db_span("merge_devices"))
+    .await
+    .map_err(db_error)?;
+    let target_config = configs.iter().find(|(id, ..)| *id == device_id);
+    let source_config = configs.iter().find(|(id, ..)| id == source_id);
+
+    let take_source = match (target_config, source_config) {
+        (_, None) => false,
+        (None, Some(_)) => payload.prefer_config == PreferConfig::Newer,
+        (Some((_, _, target_at)), Some((_, _, source_at))) => {
+            payload.prefer_config == PreferConfig::Newer && source_at > target_at
+        }
+    };
 
-    for device_id in existing_ids {
-        upsert_admin_config_value(&state.pool, &device_id, &payload.config, now).await?;
-        updated += 1;
-    }
+    // The moved config gets a version above both so the device re-applies it
+    // regardless of which record it last synced as.
+    let admin_version = if take_source {
+        let next_version = configs.iter().map(|(_, version, _)| *version).max().unwrap_or(0) + 1;
+        sqlx::query("DELETE FROM admin_configs WHERE device_id = $1")
+            .bind(&device_id)
+            .execute(&mut *tx)
+            .instrument(db_span("merge_devices"))
+            .await
+            .map_err(db_error)?;
+        sqlx::query("UPDATE admin_configs SET device_id = $1, version = $2 WHERE device_id = $3")
+            .bind(&device_id)
+            .bind(next_version)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .instrument(db_span("merge_devices"))
+            .await
+            .map_err(db_error)?;
+        Some(next_version)
+    } else {
+        target_config.map(|(_, version, _)| *version)
+    };
 
-    Ok(Json(BatchConfigResponse { ok: true, updated }))
+    // Logs the source uploaded, and commands queued for or answered by it,
+    // belong to the same machine.
+    let moved_logs = sqlx::query("UPDATE device_logs SET device_id = $1 WHERE device_id = $2")
+        .bind(&device_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .instrument(db_span("merge_devices"))
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+    let moved_commands =
+        sqlx::query("UPDATE device_commands SET device_id = $1 WHERE device_id = $2")
+            .bind(&device_id)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .instrument(db_span("merge_devices"))
+            .await
+            .map_err(db_error)?
+            .rows_affected();
+
+    // A fingerprint both reported keeps the widest sighting window.
+    sqlx::query(
+        "INSERT INTO device_fingerprints (device_id, fingerprint_hash, first_seen, last_seen)
+         SELECT $1, fingerprint_hash, first_seen, last_seen
+         FROM device_fingerprints WHERE device_id = $2
+         ON CONFLICT (device_id, fingerprint_hash) DO UPDATE
+         SET first_seen = LEAST(device_fingerprints.first_seen, EXCLUDED.first_seen),
+             last_seen = GREATEST(device_fingerprints.last_seen, EXCLUDED.last_seen)",
+    )
+    .bind(&device_id)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .instrument(db_span("merge_devices"))
+    .await
+    .map_err(db_error)?;
+
+    sqlx::query(
+        "UPDATE devices AS t
+         SET created_at = LEAST(t.created_at, s.created_at),
+             last_seen = GREATEST(t.last_seen, s.last_seen),
+             snapshot_bytes = t.snapshot_bytes + s.snapshot_bytes
+         FROM devices AS s
+         WHERE t.device_id = $1 AND s.device_id = $2",
+    )
+    .bind(&device_id)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .instrument(db_span("merge_devices"))
+    .await
+    .map_err(db_error)?;
+
+    // The source's remaining rows (its admin config if not taken, and the
+    // fingerprint rows copied above) cascade away.
+    sqlx::query("DELETE FROM devices WHERE device_id = $1")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .instrument(db_span("merge_devices"))
+        .await
+        .map_err(db_error)?;
+
+    insert_audit_log(
+        &mut tx,
+        "device.merge",
+        &device_id,
+        serde_json::json!({
+            "sourceDeviceId": source_id,
+            "movedSnapshots": moved_snapshots,
+            "movedLogs": moved_logs,
+            "movedCommands": moved_commands,
+            "configFromSource": take_source,
+            "adminVersion": admin_version,
+        }),
+        Utc::now(),
+    )
+    .await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(Json(MergeDeviceResponse {
+        ok: true,
+        device_id,
+        moved_snapshots,
+        admin_version,
+    }))
 }
 
 fn authorize_bearer(headers: &HeaderMap, expected: &str) -> Result<(), ApiError> {
@@ -482,6 +2407,24 @@ fn authorize_bearer(headers: &HeaderMap, expected: &str) -> Result<(), ApiError>
     Ok(())
 }
 
+/// [`authorize_bearer`] with the sync token; rejections are recorded for
+/// `GET /api/v1/admin/security/sync-rejections`.
+fn authorize_sync(
+    state: &AppState,
+    headers: &HeaderMap,
+    addr: SocketAddr,
+    claimed_device_id: impl FnOnce() -> Option<String>,
+) -> Result<(), ApiError> {
+    authorize_bearer(headers, &state.sync_token).inspect_err(|_| {
+        state.sync_rejections.record(
+            extract_ip(headers, addr, state.trust_proxy),
+            extract_bearer_token(headers).as_deref(),
+            Utc::now(),
+            claimed_device_id,
+        );
+    })
+}
+
 fn authorize_admin(headers: &HeaderMap, state: &AppState) -> Result<(), ApiError> {
     if let Some(token) = extract_bearer_token(headers) {
         if token == state.admin_token {
@@ -534,58 +2477,45 @@ fn extract_ip(headers: &HeaderMap, addr: SocketAddr, trust_proxy: bool) -> Optio
     Some(addr.ip())
 }
 
-fn lookup_geo(geoip: &Option<Arc<Reader<Vec<u8>>>>, ip: IpAddr) -> Option<GeoResult> {
-    let reader = geoip.as_ref()?;
-    let result = reader.lookup::<maxminddb::geoip2::City>(ip).ok()?;
-
-    let country = result
-        .country
-        .and_then(|c| c.iso_code.map(|code| code.to_string()));
-    let region = result
-        .subdivisions
-        .and_then(|subs| subs.first().and_then(|sub| sub.iso_code.map(|code| code.to_string())));
-    let city = result
-        .city
-        .and_then(|city| city.names.and_then(|names| names.get("en").map(|value| value.to_string())));
-
-    Some(GeoResult {
-        country,
-        region,
-        city,
-    })
-}
-
-struct GeoResult {
-    country: Option<String>,
-    region: Option<String>,
-    city: Option<String>,
-}
-
 async fn upsert_device(
     pool: &PgPool,
     payload: &SyncRequest,
     now: DateTime<Utc>,
     ip: Option<IpAddr>,
     geo: Option<&GeoResult>,
-) -> Result<(), ApiError> {
+    signed: bool,
+    new_approval: DeviceApproval,
+) -> Result<DeviceAccess, ApiError> {
     let ip_str = ip.map(|value| value.to_string());
     let geo_country = geo.and_then(|g| g.country.clone());
     let geo_region = geo.and_then(|g| g.region.clone());
     let geo_city = geo.and_then(|g| g.city.clone());
+    // Older clients don't send a fingerprint; their device ID stands in for it.
+    let fingerprint = payload
+        .fingerprint_hash
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
 
-    sqlx::query(
-        "INSERT INTO devices (device_id, fingerprint_hash, last_seen, last_ip, geo_country, geo_region, geo_city, app_version, created_at)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+    let row = sqlx::query(
+        "INSERT INTO devices (device_id, fingerprint_hash, last_seen, last_ip, geo_country, geo_region, geo_city, app_version, created_at, last_sync_signed, applied_admin_version, protocol_version, channel, approval)
+         VALUES ($1, COALESCE($2, $1), $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
          ON CONFLICT (device_id)
          DO UPDATE SET last_seen = EXCLUDED.last_seen,
+                       fingerprint_hash = COALESCE($2, devices.fingerprint_hash),
                        last_ip = EXCLUDED.last_ip,
                        geo_country = EXCLUDED.geo_country,
                        geo_region = EXCLUDED.geo_region,
                        geo_city = EXCLUDED.geo_city,
-                       app_version = EXCLUDED.app_version",
+                       app_version = EXCLUDED.app_version,
+                       last_sync_signed = EXCLUDED.last_sync_signed,
+                       applied_admin_version = EXCLUDED.applied_admin_version,
+                       protocol_version = EXCLUDED.protocol_version,
+                       channel = EXCLUDED.channel
+         RETURNING blocked, blocked_reason, approval",
     )
     .bind(&payload.device_id)
-    .bind(&payload.device_id)
+    .bind(fingerprint)
     .bind(now)
     .bind(ip_str)
     .bind(geo_country)
@@ -593,11 +2523,37 @@ async fn upsert_device(
     .bind(geo_city)
     .bind(payload.app_version.clone())
     .bind(now)
-    .execute(pool)
+    .bind(signed)
+    .bind(payload.applied_admin_version)
+    .bind(i32::try_from(payload.protocol_version).unwrap_or(i32::MAX))
+    .bind(ReleaseChannel::from_app_version(payload.app_version.as_deref()).as_str())
+    .bind(new_approval.as_str())
+    .fetch_one(pool)
+    .instrument(db_span("upsert_device"))
     .await
     .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    let access = DeviceAccess {
+        blocked: row.get("blocked"),
+        blocked_reason: row.get("blocked_reason"),
+        approval: DeviceApproval::parse(row.get("approval")),
+    };
 
-    Ok(())
+    if let Some(fingerprint) = fingerprint {
+        sqlx::query(
+            "INSERT INTO device_fingerprints (device_id, fingerprint_hash, first_seen, last_seen)
+             VALUES ($1, $2, $3, $3)
+             ON CONFLICT (device_id, fingerprint_hash) DO UPDATE SET last_seen = EXCLUDED.last_seen",
+        )
+        .bind(&payload.device_id)
+        .bind(fingerprint)
+        .bind(now)
+        .execute(pool)
+        .instrument(db_span("upsert_device"))
+        .await
+        .map_err(db_error)?;
+    }
+
+    Ok(access)
 }
 
 async fn insert_snapshot(
@@ -605,70 +2561,205 @@ async fn insert_snapshot(
     device_id: &str,
     snapshot: &serde_json::Value,
     now: DateTime<Utc>,
+    client_time: Option<DateTime<Utc>>,
 ) -> Result<(), ApiError> {
+    let payload_bytes = serde_json::to_vec(snapshot)
+        .map(|bytes| bytes.len() as i64)
+        .unwrap_or_default();
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
     sqlx::query(
-        "INSERT INTO config_snapshots (device_id, snapshot, created_at) VALUES ($1, $2, $3)",
+        "INSERT INTO config_snapshots (device_id, snapshot, created_at, payload_bytes, client_time)
+         VALUES ($1, $2, $3, $4, $5)",
     )
     .bind(device_id)
     .bind(SqlxJson(snapshot.clone()))
     .bind(now)
-    .execute(pool)
+    .bind(payload_bytes)
+    .bind(client_time)
+    .execute(&mut *tx)
+    .instrument(db_span("insert_snapshot"))
     .await
-    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    .map_err(db_error)?;
+
+    sqlx::query("UPDATE devices SET snapshot_bytes = snapshot_bytes + $1 WHERE device_id = $2")
+        .bind(payload_bytes)
+        .bind(device_id)
+        .execute(&mut *tx)
+        .instrument(db_span("insert_snapshot"))
+        .await
+        .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
 
     Ok(())
 }
 
+/// Whether storing `incoming_bytes` more would take a device past the quota.
+fn exceeds_snapshot_quota(quota: Option<i64>, used_bytes: i64, incoming_bytes: i64) -> bool {
+    quota.is_some_and(|quota| used_bytes + incoming_bytes > quota)
+}
+
+/// Stored instead of a snapshot that would exceed the quota.
+fn quota_marker(original_bytes: i64) -> serde_json::Value {
+    serde_json::json!({
+        "truncated": true,
+        "reason": "quota_exceeded",
+        "originalBytes": original_bytes,
+    })
+}
+
+fn is_quota_marker(snapshot: &serde_json::Value) -> bool {
+    snapshot.get("reason").and_then(serde_json::Value::as_str) == Some("quota_exceeded")
+}
+
+async fn fetch_snapshot_bytes(pool: &PgPool, device_id: &str) -> Result<i64, ApiError> {
+    let bytes = sqlx::query_scalar::<_, i64>(
+        "SELECT snapshot_bytes FROM devices WHERE device_id = $1",
+    )
+    .bind(device_id)
+    .fetch_optional(pool)
+    .instrument(db_span("fetch_snapshot_bytes"))
+    .await
+    .map_err(db_error)?;
+
+    Ok(bytes.unwrap_or_default())
+}
+
+/// Per-device secret when one has been provisioned, otherwise the shared one.
+async fn fetch_sync_secret(state: &AppState, device_id: &str) -> Result<Option<String>, ApiError> {
+    let device_secret = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT sync_secret FROM devices WHERE device_id = $1",
+    )
+    .bind(device_id)
+    .fetch_optional(&state.pool)
+    .instrument(db_span("fetch_sync_secret"))
+    .await
+    .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+    .flatten()
+    .filter(|value| !value.trim().is_empty());
+
+    Ok(device_secret.or_else(|| state.sync_signing_secret.clone()))
+}
+
 struct AdminConfigRow {
     version: i64,
     config: serde_json::Value,
+    signature: Option<String>,
 }
 
 async fn fetch_admin_config(
     pool: &PgPool,
     device_id: &str,
 ) -> Result<Option<AdminConfigRow>, ApiError> {
-    let row = sqlx::query_as::<_, (i64, SqlxJson<serde_json::Value>)>(
-        "SELECT version, config FROM admin_configs WHERE device_id = $1",
+    let row = sqlx::query_as::<_, (i64, SqlxJson<serde_json::Value>, Option<String>)>(
+        "SELECT version, config, signature FROM admin_configs WHERE device_id = $1",
     )
     .bind(device_id)
     .fetch_optional(pool)
+    .instrument(db_span("fetch_admin_config"))
     .await
     .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
 
-    let Some((version, config)) = row else {
+    let Some((version, config, signature)) = row else {
         return Ok(None);
     };
 
     Ok(Some(AdminConfigRow {
         version,
         config: config.0,
+        signature,
     }))
 }
 
-async fn upsert_admin_config_value(
+async fn fetch_pending_commands(
     pool: &PgPool,
     device_id: &str,
+    now: DateTime<Utc>,
+    ttl_secs: i64,
+) -> Result<Vec<DeviceCommand>, ApiError> {
+    let rows = sqlx::query_as::<_, (i64, String, SqlxJson<serde_json::Value>, DateTime<Utc>)>(
+        "SELECT id, command, payload, created_at
+         FROM device_commands
+         WHERE device_id = $1 AND acknowledged_at IS NULL AND created_at > $2
+         ORDER BY created_at",
+    )
+    .bind(device_id)
+    .bind(now - chrono::Duration::seconds(ttl_secs))
+    .fetch_all(pool)
+    .instrument(db_span("fetch_pending_commands"))
+    .await
+    .map_err(db_error)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, command, payload, created_at)| DeviceCommand {
+            id,
+            command,
+            payload: payload.0,
+            created_at: Some(created_at),
+        })
+        .collect())
+}
+
+async fn upsert_admin_config_value(
+    state: &AppState,
+    device_id: &str,
     config: &serde_json::Value,
     now: DateTime<Utc>,
 ) -> Result<i64, ApiError> {
+    let signature = state
+        .config_signer
+        .as_ref()
+        .map(|signer| signer.sign(config));
+
     let version = sqlx::query_scalar(
-        "INSERT INTO admin_configs (device_id, version, config, updated_at)
-         VALUES ($1, 1, $2, $3)
+        "INSERT INTO admin_configs (device_id, version, config, updated_at, signature)
+         VALUES ($1, 1, $2, $3, $4)
          ON CONFLICT (device_id)
-         DO UPDATE SET version = admin_configs.version + 1, config = EXCLUDED.config, updated_at = EXCLUDED.updated_at
+         DO UPDATE SET version = admin_configs.version + 1, config = EXCLUDED.config,
+                       updated_at = EXCLUDED.updated_at, signature = EXCLUDED.signature
          RETURNING version",
     )
     .bind(device_id)
     .bind(SqlxJson(config.clone()))
     .bind(now)
-    .fetch_one(pool)
+    .bind(signature)
+    .fetch_one(&state.pool)
+    .instrument(db_span("upsert_admin_config_value"))
     .await
     .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
 
     Ok(version)
 }
 
+async fn insert_audit_log(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    action: &str,
+    device_id: &str,
+    detail: serde_json::Value,
+    now: DateTime<Utc>,
+) -> Result<(), ApiError> {
+    sqlx::query(
+        "INSERT INTO admin_audit_log (action, device_id, detail, created_at) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(action)
+    .bind(device_id)
+    .bind(SqlxJson(detail))
+    .bind(now)
+    .execute(&mut **tx)
+    .instrument(db_span("insert_audit_log"))
+    .await
+    .map_err(db_error)?;
+
+    Ok(())
+}
+
+fn db_error(err: sqlx::Error) -> ApiError {
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
+
 fn require_env(key: &str) -> String {
     env::var(key).unwrap_or_else(|_| panic!("missing env: {}", key))
 }
@@ -0,0 +1,64 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A device that missed its sync for this many thresholds is `offline`
+/// rather than `stale`.
+const OFFLINE_AFTER_THRESHOLDS: i32 = 3;
+
+/// How recently a device synced: `online` within the threshold, `stale`
+/// within [`OFFLINE_AFTER_THRESHOLDS`] thresholds, `offline` after that or
+/// when it never synced.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnlineStatus {
+    Online,
+    Stale,
+    Offline,
+}
+
+/// The `last_seen` boundaries between the statuses at one moment, so every
+/// device in a list is judged against the same clock.
+#[derive(Clone, Copy, Debug)]
+pub struct OnlineCutoffs {
+    /// Seen at or after this is `online`.
+    pub online_since: DateTime<Utc>,
+    /// Seen at or after this, but before `online_since`, is `stale`.
+    pub stale_since: DateTime<Utc>,
+}
+
+impl OnlineCutoffs {
+    pub fn at(now: DateTime<Utc>, threshold_secs: i64) -> Self {
+        let threshold = TimeDelta::seconds(threshold_secs);
+        Self {
+            online_since: now - threshold,
+            stale_since: now - threshold * OFFLINE_AFTER_THRESHOLDS,
+        }
+    }
+
+    pub fn status(&self, last_seen: Option<DateTime<Utc>>) -> OnlineStatus {
+        match last_seen {
+            Some(seen) if seen >= self.online_since => OnlineStatus::Online,
+            Some(seen) if seen >= self.stale_since => OnlineStatus::Stale,
+            _ => OnlineStatus::Offline,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_follow_the_threshold() {
+        let now: DateTime<Utc> = "2026-01-10T12:00:00Z".parse().unwrap();
+        let cutoffs = OnlineCutoffs::at(now, 26 * 3600);
+        let seen = |hours: i64| cutoffs.status(Some(now - TimeDelta::hours(hours)));
+
+        assert_eq!(seen(0), OnlineStatus::Online);
+        assert_eq!(seen(26), OnlineStatus::Online);
+        assert_eq!(seen(27), OnlineStatus::Stale);
+        assert_eq!(seen(78), OnlineStatus::Stale);
+        assert_eq!(seen(79), OnlineStatus::Offline);
+        assert_eq!(cutoffs.status(None), OnlineStatus::Offline);
+    }
+}
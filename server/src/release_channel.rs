@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+/// Release channel of a client build, derived from its semver `app_version`:
+/// no pre-release is `stable`, a pre-release starting with `beta` or `rc` is
+/// `beta`, any other pre-release (`alpha`, `dev`, `nightly`, ...) is `dev`.
+/// Missing or non-semver versions are `unknown`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    Stable,
+    Beta,
+    Dev,
+    Unknown,
+}
+
+impl ReleaseChannel {
+    pub fn from_app_version(version: Option<&str>) -> Self {
+        let Some(parsed) = version.and_then(pre_release) else {
+            return Self::Unknown;
+        };
+        let Some(pre_release) = parsed else {
+            return Self::Stable;
+        };
+        let first = pre_release
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if first.starts_with("beta") || first.starts_with("rc") {
+            Self::Beta
+        } else {
+            Self::Dev
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Dev => "dev",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// `None` when `version` is not semver; otherwise its pre-release, if any.
+/// Build metadata is ignored.
+fn pre_release(version: &str) -> Option<Option<&str>> {
+    let version = version.trim();
+    let (version, build) = match version.split_once('+') {
+        Some((version, build)) => (version, Some(build)),
+        None => (version, None),
+    };
+    if build.is_some_and(|build| !identifiers_valid(build, false)) {
+        return None;
+    }
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+
+    let parts: Vec<&str> = core.split('.').collect();
+    if parts.len() != 3 || !parts.iter().all(|part| numeric_valid(part)) {
+        return None;
+    }
+    match pre {
+        Some(pre) if !identifiers_valid(pre, true) => None,
+        pre => Some(pre),
+    }
+}
+
+/// Dot-separated, non-empty `[0-9A-Za-z-]` identifiers; numeric pre-release
+/// identifiers must not have leading zeros.
+fn identifiers_valid(text: &str, pre_release: bool) -> bool {
+    text.split('.').all(|identifier| {
+        !identifier.is_empty()
+            && identifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && (!pre_release
+                || !identifier.chars().all(|c| c.is_ascii_digit())
+                || numeric_valid(identifier))
+    })
+}
+
+fn numeric_valid(part: &str) -> bool {
+    !part.is_empty()
+        && part.chars().all(|c| c.is_ascii_digit())
+        && (part == "0" || !part.starts_with('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(version: &str) -> &'static str {
+        ReleaseChannel::from_app_version(Some(version)).as_str()
+    }
+
+    #[test]
+    fn derives_channels_from_pre_releases() {
+        assert_eq!(channel("1.6.0"), "stable");
+        assert_eq!(channel("1.6.0+build.7"), "stable");
+        assert_eq!(channel("1.6.0-beta.3"), "beta");
+        assert_eq!(channel("1.6.0-BETA3"), "beta");
+        assert_eq!(channel("2.0.0-rc.1+sha.5114f85"), "beta");
+        assert_eq!(channel("1.6.0-alpha.1"), "dev");
+        assert_eq!(channel("1.6.0-dev"), "dev");
+        assert_eq!(channel("1.6.0-0.3.7"), "dev");
+    }
+
+    #[test]
+    fn invalid_versions_are_unknown() {
+        assert_eq!(ReleaseChannel::from_app_version(None).as_str(), "unknown");
+        for version in [
+            "",
+            "1.6",
+            "1.6.0.1",
+            "v1.6.0",
+            "01.6.0",
+            "1.6.0-",
+            "1.6.0-beta..1",
+            "1.6.0-beta.01",
+            "1.6.0+",
+            "1.6.0-beta_1",
+            "latest",
+        ] {
+            assert_eq!(channel(version), "unknown", "{version}");
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::tasks::TaskRegistry;
+
+/// Upper bounds (ms) of the latency histogram; the last bucket is open-ended.
+const LATENCY_BUCKETS_MS: [f64; 10] = [
+    5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+];
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Hash, PartialEq, Eq, Clone)]
+struct BucketKey {
+    minute: DateTime<Utc>,
+    route: String,
+    status_class: &'static str,
+}
+
+#[derive(Default)]
+struct Bucket {
+    count: i64,
+    total_ms: f64,
+    max_ms: f64,
+    latency: [i64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+/// Per-minute request counters, flushed to `request_stats`.
+#[derive(Default)]
+pub struct RequestStats {
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+impl RequestStats {
+    fn record(&self, route: String, status: u16, elapsed: Duration, now: DateTime<Utc>) {
+        let key = BucketKey {
+            minute: truncate_to_minute(now),
+            route,
+            status_class: status_class(status),
+        };
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        let slot = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let bucket = buckets.entry(key).or_default();
+        bucket.count += 1;
+        bucket.total_ms += ms;
+        bucket.max_ms = bucket.max_ms.max(ms);
+        bucket.latency[slot] += 1;
+    }
+
+    /// Removes finished minutes, or everything when `include_current` is set.
+    fn drain(&self, now: DateTime<Utc>, include_current: bool) -> Vec<(BucketKey, Bucket)> {
+        let current = truncate_to_minute(now);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let keys: Vec<BucketKey> = buckets
+            .keys()
+            .filter(|key| include_current || key.minute < current)
+            .cloned()
+            .collect();
+        keys.into_iter()
+            .filter_map(|key| buckets.remove(&key).map(|bucket| (key, bucket)))
+            .collect()
+    }
+
+    /// Writes drained buckets; on failure they are merged back for the next attempt
+    /// and an error is returned.
+    pub async fn flush(&self, pool: &PgPool, include_current: bool) -> Result<(), String> {
+        let drained = self.drain(Utc::now(), include_current);
+        if drained.is_empty() {
+            return Ok(());
+        }
+
+        let mut failed = Vec::new();
+        for (key, bucket) in drained {
+            let result = sqlx::query(
+                "INSERT INTO request_stats (bucket_start, route, status_class, request_count,
+                                            total_duration_ms, max_duration_ms, latency_buckets)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            )
+            .bind(key.minute)
+            .bind(&key.route)
+            .bind(key.status_class)
+            .bind(bucket.count)
+            .bind(bucket.total_ms)
+            .bind(bucket.max_ms)
+            .bind(bucket.latency.to_vec())
+            .execute(pool)
+            .await;
+
+            if let Err(err) = result {
+                tracing::warn!("failed to flush request stats: {}", err);
+                failed.push((key, bucket));
+            }
+        }
+
+        if failed.is_empty() {
+            return Ok(());
+        }
+        let failed_count = failed.len();
+        {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+            for (key, bucket) in failed {
+                let entry = buckets.entry(key).or_default();
+                entry.count += bucket.count;
+                entry.total_ms += bucket.total_ms;
+                entry.max_ms = entry.max_ms.max(bucket.max_ms);
+                for (slot, value) in bucket.latency.iter().enumerate() {
+                    entry.latency[slot] += value;
+                }
+            }
+        }
+        Err(format!(
+            "{failed_count} request stats buckets failed to flush"
+        ))
+    }
+}
+
+pub fn spawn_flush_loop(tasks: &Arc<TaskRegistry>, stats: Arc<RequestStats>, pool: PgPool) {
+    tasks.spawn_periodic("request_stats_flush", FLUSH_INTERVAL, move || {
+        let stats = stats.clone();
+        let pool = pool.clone();
+        async move { stats.flush(&pool, false).await }
+    });
+}
+
+pub async fn track_requests(
+    State(stats): State<Arc<RequestStats>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    if let Some(route) = route {
+        stats.record(
+            route,
+            response.status().as_u16(),
+            started.elapsed(),
+            Utc::now(),
+        );
+    }
+
+    response
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestStatsHour {
+    hour: DateTime<Utc>,
+    route: String,
+    requests: i64,
+    client_errors: i64,
+    server_errors: i64,
+    avg_duration_ms: f64,
+    max_duration_ms: f64,
+    /// Upper bound of the histogram bucket containing the 95th percentile;
+    /// `None` when it falls into the open-ended bucket.
+    p95_duration_ms: Option<f64>,
+}
+
+#[derive(Default)]
+struct HourAccumulator {
+    requests: i64,
+    client_errors: i64,
+    server_errors: i64,
+    total_ms: f64,
+    max_ms: f64,
+    latency: [i64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+/// Aggregates stored minute buckets into hourly rows per route.
+pub async fn load_hourly(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+) -> Result<Vec<RequestStatsHour>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (DateTime<Utc>, String, String, i64, f64, f64, Vec<i64>)>(
+        "SELECT date_trunc('hour', bucket_start), route, status_class, request_count,
+                total_duration_ms, max_duration_ms, latency_buckets
+         FROM request_stats
+         WHERE bucket_start >= $1",
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let mut hours: HashMap<(DateTime<Utc>, String), HourAccumulator> = HashMap::new();
+    for (hour, route, status_class, count, total_ms, max_ms, latency) in rows {
+        let entry = hours.entry((hour, route)).or_default();
+        entry.requests += count;
+        entry.total_ms += total_ms;
+        entry.max_ms = entry.max_ms.max(max_ms);
+        match status_class.as_str() {
+            "4xx" => entry.client_errors += count,
+            "5xx" => entry.server_errors += count,
+            _ => {}
+        }
+        for (slot, value) in latency.iter().enumerate().take(entry.latency.len()) {
+            entry.latency[slot] += value;
+        }
+    }
+
+    let mut result: Vec<RequestStatsHour> = hours
+        .into_iter()
+        .map(|((hour, route), acc)| RequestStatsHour {
+            hour,
+            route,
+            requests: acc.requests,
+            client_errors: acc.client_errors,
+            server_errors: acc.server_errors,
+            avg_duration_ms: if acc.requests > 0 {
+                acc.total_ms / acc.requests as f64
+            } else {
+                0.0
+            },
+            max_duration_ms: acc.max_ms,
+            p95_duration_ms: percentile_bound(&acc.latency, 0.95),
+        })
+        .collect();
+    result.sort_by(|a, b| a.hour.cmp(&b.hour).then_with(|| a.route.cmp(&b.route)));
+
+    Ok(result)
+}
+
+fn percentile_bound(latency: &[i64], percentile: f64) -> Option<f64> {
+    let total: i64 = latency.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let target = (total as f64 * percentile).ceil() as i64;
+    let mut seen = 0;
+    for (slot, count) in latency.iter().enumerate() {
+        seen += count;
+        if seen >= target {
+            return LATENCY_BUCKETS_MS.get(slot).copied();
+        }
+    }
+    None
+}
+
+fn status_class(status: u16) -> &'static str {
+    match status {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+fn truncate_to_minute(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(TimeDelta::minutes(1)).unwrap_or(at)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn counts(drained: &[(BucketKey, Bucket)]) -> Vec<(DateTime<Utc>, i64)> {
+        let mut counts: Vec<_> = drained
+            .iter()
+            .map(|(key, bucket)| (key.minute, bucket.count))
+            .collect();
+        counts.sort();
+        counts
+    }
+
+    #[test]
+    fn drain_keeps_the_current_minute_unless_asked() {
+        let stats = RequestStats::default();
+        let elapsed = Duration::from_millis(3);
+        stats.record("/a".into(), 200, elapsed, at("2026-01-01T10:00:10Z"));
+        stats.record("/a".into(), 200, elapsed, at("2026-01-01T10:00:50Z"));
+        stats.record("/a".into(), 200, elapsed, at("2026-01-01T10:01:05Z"));
+
+        let now = at("2026-01-01T10:01:30Z");
+        let finished = stats.drain(now, false);
+        assert_eq!(counts(&finished), [(at("2026-01-01T10:00:00Z"), 2)]);
+        assert!(stats.drain(now, false).is_empty());
+
+        let rest = stats.drain(now, true);
+        assert_eq!(counts(&rest), [(at("2026-01-01T10:01:00Z"), 1)]);
+        assert!(stats.drain(now, true).is_empty());
+    }
+
+    #[test]
+    fn requests_are_bucketed_by_status_class_and_latency() {
+        let stats = RequestStats::default();
+        let now = at("2026-01-01T10:00:00Z");
+        stats.record("/a".into(), 200, Duration::from_millis(3), now);
+        stats.record("/a".into(), 204, Duration::from_millis(40), now);
+        stats.record("/a".into(), 404, Duration::from_secs(9), now);
+
+        let drained = stats.drain(now, true);
+        let ok = drained
+            .iter()
+            .find(|(key, _)| key.status_class == "2xx")
+            .map(|(_, bucket)| bucket)
+            .unwrap();
+        assert_eq!(ok.count, 2);
+        assert_eq!(ok.latency[0], 1);
+        assert_eq!(ok.latency[3], 1);
+        assert_eq!(ok.max_ms, 40.0);
+        let missing = drained
+            .iter()
+            .find(|(key, _)| key.status_class == "4xx")
+            .map(|(_, bucket)| bucket)
+            .unwrap();
+        assert_eq!(missing.latency[LATENCY_BUCKETS_MS.len()], 1);
+    }
+
+    #[test]
+    fn percentile_bound_is_the_bucket_upper_bound() {
+        let mut latency = [0i64; LATENCY_BUCKETS_MS.len() + 1];
+        assert_eq!(percentile_bound(&latency, 0.95), None);
+
+        latency[0] = 95;
+        latency[4] = 5;
+        assert_eq!(percentile_bound(&latency, 0.95), Some(5.0));
+        latency[0] = 94;
+        latency[4] = 6;
+        assert_eq!(percentile_bound(&latency, 0.95), Some(100.0));
+
+        latency = [0; LATENCY_BUCKETS_MS.len() + 1];
+        latency[0] = 1;
+        latency[LATENCY_BUCKETS_MS.len()] = 99;
+        assert_eq!(percentile_bound(&latency, 0.95), None);
+    }
+
+    #[tokio::test]
+    async fn failed_flushes_keep_the_buckets() {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_secs(1))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        let stats = RequestStats::default();
+        let now = Utc::now();
+        stats.record("/a".into(), 200, Duration::from_millis(3), now);
+
+        assert!(stats.flush(&pool, true).await.is_err());
+        stats.record("/a".into(), 200, Duration::from_millis(7), now);
+
+        let drained = stats.drain(now, true);
+        assert_eq!(drained.len(), 1);
+        let bucket = &drained[0].1;
+        assert_eq!(bucket.count, 2);
+        assert_eq!(bucket.total_ms, 10.0);
+        assert_eq!(bucket.latency[0], 1);
+        assert_eq!(bucket.latency[1], 1);
+    }
+}
@@ -0,0 +1,290 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Provider fields that change without any meaningful config change.
+const VOLATILE_PROVIDER_FIELDS: [&str; 2] = ["createdAt", "sortIndex"];
+
+/// Clients with a snapshot privacy level upload secrets as `****<tail>` or
+/// `enc:v1:<payload>`; those match any pushed value (with the same tail).
+const MASK_PREFIX: &str = "****";
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Differences for one app section between a pushed config and a snapshot.
+#[derive(Serialize, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppDiff {
+    pub app: String,
+    pub current_changed: bool,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl AppDiff {
+    pub fn is_empty(&self) -> bool {
+        !self.current_changed
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+    }
+}
+
+/// Snapshots stored as quota markers carry no provider data to compare.
+pub fn is_comparable(snapshot: &Value) -> bool {
+    snapshot.is_object() && snapshot.get("truncated").and_then(Value::as_bool) != Some(true)
+}
+
+/// Diffs every app section present in `target` against `base`. Sections that
+/// `target` omits are not applied by clients and therefore not compared.
+/// `added` lists providers in `target` missing from `base`. Configs pushed with
+/// `"mode": "merge"` leave local-only providers in place, so those are not
+/// reported as `removed`.
+pub fn diff_config(target: &Value, base: &Value) -> Vec<AppDiff> {
+    let Some(sections) = target.as_object() else {
+        return Vec::new();
+    };
+    let merge = target.get("mode").and_then(Value::as_str) == Some("merge");
+
+    sections
+        .iter()
+        .filter(|(_, section)| section.is_object())
+        .map(|(app, section)| diff_app(app, section, base.get(app), merge))
+        .filter(|diff| !diff.is_empty())
+        .collect()
+}
+
+fn diff_app(app: &str, target: &Value, base: Option<&Value>, merge: bool) -> AppDiff {
+    let target_current = target.get("currentId").and_then(Value::as_str);
+    let base_current = base
+        .and_then(|section| section.get("currentId"))
+        .and_then(Value::as_str);
+    let empty = serde_json::Map::new();
+    let target_providers = target
+        .get("providers")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let base_providers = base
+        .and_then(|section| section.get("providers"))
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    let mut diff = AppDiff {
+        app: app.to_string(),
+        current_changed: target_current != base_current,
+        ..AppDiff::default()
+    };
+
+    for (id, provider) in target_providers {
+        match base_providers.get(id) {
+            None => diff.added.push(id.clone()),
+            Some(existing)
+                if !values_match(&normalize_provider(provider), &normalize_provider(existing)) =>
+            {
+                diff.modified.push(id.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    for id in base_providers.keys() {
+        if !merge && !target_providers.contains_key(id) {
+            diff.removed.push(id.clone());
+        }
+    }
+
+    diff
+}
+
+fn normalize_provider(provider: &Value) -> Value {
+    let mut provider = provider.clone();
+    if let Some(fields) = provider.as_object_mut() {
+        for field in VOLATILE_PROVIDER_FIELDS {
+            fields.remove(field);
+        }
+        if let Some(endpoints) = fields
+            .get_mut("meta")
+            .and_then(|meta| meta.get_mut("custom_endpoints"))
+            .and_then(Value::as_object_mut)
+        {
+            for endpoint in endpoints.values_mut() {
+                if let Some(endpoint) = endpoint.as_object_mut() {
+                    endpoint.remove("lastUsed");
+                }
+            }
+        }
+    }
+    provider
+}
+
+fn values_match(target: &Value, base: &Value) -> bool {
+    match (target, base) {
+        (Value::String(target), Value::String(base)) => string_matches(target, base),
+        (Value::Array(target), Value::Array(base)) => {
+            target.len() == base.len()
+                && target
+                    .iter()
+                    .zip(base)
+                    .all(|(target, base)| values_match(target, base))
+        }
+        (Value::Object(target), Value::Object(base)) => {
+            target.len() == base.len()
+                && target.iter().all(|(key, target)| {
+                    base.get(key).is_some_and(|base| values_match(target, base))
+                })
+        }
+        _ => target == base,
+    }
+}
+
+/// Whole-value secrets (env vars, auth fields) are protected as a whole string;
+/// secrets inside Codex `config.toml` are protected as quoted values.
+fn string_matches(target: &str, base: &str) -> bool {
+    if let Some(tail) = base.strip_prefix(MASK_PREFIX) {
+        return target.ends_with(tail);
+    }
+    if base.starts_with(ENCRYPTED_PREFIX) {
+        return true;
+    }
+
+    let mut target = target;
+    let mut base = base;
+    loop {
+        let next = [MASK_PREFIX, ENCRYPTED_PREFIX]
+            .iter()
+            .filter_map(|prefix| {
+                base.find(&format!("\"{prefix}"))
+                    .map(|at| (at + 1, *prefix))
+            })
+            .min();
+        let Some((at, prefix)) = next else {
+            return target == base;
+        };
+        let Some(rest) = target.strip_prefix(&base[..at]) else {
+            return false;
+        };
+        let (Some(base_end), Some(target_end)) = (base[at..].find('"'), rest.find('"')) else {
+            return false;
+        };
+        if prefix == MASK_PREFIX
+            && !rest[..target_end].ends_with(&base[at + prefix.len()..at + base_end])
+        {
+            return false;
+        }
+        base = &base[at + base_end..];
+        target = &rest[target_end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn config(providers: Value) -> Value {
+        json!({ "claude": { "currentId": "main", "providers": providers } })
+    }
+
+    fn provider(settings: Value) -> Value {
+        json!({ "id": "main", "name": "Main", "settingsConfig": settings })
+    }
+
+    fn modified(target: &Value, base: &Value) -> Vec<String> {
+        diff_config(target, base)
+            .into_iter()
+            .flat_map(|diff| diff.modified)
+            .collect()
+    }
+
+    #[test]
+    fn volatile_fields_are_ignored() {
+        let with = |created: i64, sort: i64, used: i64| {
+            let mut main = provider(json!({}));
+            main["createdAt"] = json!(created);
+            main["sortIndex"] = json!(sort);
+            main["meta"] = json!({
+                "custom_endpoints": { "https://a": { "url": "https://a", "lastUsed": used } },
+            });
+            config(json!({ "main": main }))
+        };
+
+        assert_eq!(diff_config(&with(1, 0, 10), &with(2, 5, 20)), Vec::new());
+
+        let mut renamed = with(1, 0, 10);
+        renamed["claude"]["providers"]["main"]["name"] = json!("Renamed");
+        assert_eq!(modified(&renamed, &with(1, 0, 10)), ["main"]);
+    }
+
+    #[test]
+    fn masked_secrets_match_on_their_tail() {
+        let env =
+            |token: &str| config(json!({ "main": provider(json!({ "env": { "TOKEN": token } })) }));
+        let base = env("****1234");
+
+        assert_eq!(diff_config(&env("sk-abcd1234"), &base), Vec::new());
+        assert_eq!(modified(&env("sk-abcd9999"), &base), ["main"]);
+    }
+
+    #[test]
+    fn encrypted_secrets_match_any_value() {
+        let env =
+            |token: &str| config(json!({ "main": provider(json!({ "env": { "TOKEN": token } })) }));
+
+        assert_eq!(
+            diff_config(&env("sk-anything"), &env("enc:v1:c2VjcmV0")),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn codex_configs_compare_each_quoted_secret() {
+        let codex = |toml: &str| {
+            json!({
+                "codex": {
+                    "currentId": "main",
+                    "providers": { "main": provider(json!({ "config": toml })) },
+                },
+            })
+        };
+        let pushed =
+            codex("model = \"gpt-5\"\n[a]\nkey = \"sk-one-1111\"\n[b]\nkey = \"sk-two-2222\"\n");
+
+        let masked =
+            codex("model = \"gpt-5\"\n[a]\nkey = \"****1111\"\n[b]\nkey = \"enc:v1:abc\"\n");
+        assert_eq!(diff_config(&pushed, &masked), Vec::new());
+
+        let wrong_tail =
+            codex("model = \"gpt-5\"\n[a]\nkey = \"****9999\"\n[b]\nkey = \"enc:v1:abc\"\n");
+        assert_eq!(modified(&pushed, &wrong_tail), ["main"]);
+
+        let other_model =
+            codex("model = \"gpt-4\"\n[a]\nkey = \"****1111\"\n[b]\nkey = \"enc:v1:abc\"\n");
+        assert_eq!(modified(&pushed, &other_model), ["main"]);
+
+        let after_secret =
+            codex("model = \"gpt-5\"\n[a]\nkey = \"****1111\"\n[c]\nkey = \"enc:v1:abc\"\n");
+        assert_eq!(modified(&pushed, &after_secret), ["main"]);
+    }
+
+    #[test]
+    fn merge_mode_keeps_local_only_providers() {
+        let base = config(json!({
+            "main": provider(json!({})),
+            "local": provider(json!({})),
+        }));
+        let mut target = config(json!({
+            "main": provider(json!({})),
+            "pushed": provider(json!({})),
+        }));
+
+        let replace = diff_config(&target, &base);
+        assert_eq!(replace.len(), 1);
+        assert_eq!(replace[0].added, ["pushed"]);
+        assert_eq!(replace[0].removed, ["local"]);
+        assert!(!replace[0].current_changed);
+
+        target["mode"] = json!("merge");
+        let merge = diff_config(&target, &base);
+        assert_eq!(merge[0].added, ["pushed"]);
+        assert!(merge[0].removed.is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+use serde_json::Value;
+
+/// Same markers clients use for the `redacted` and `encrypted` privacy levels;
+/// values already carrying one are left as they are.
+const MASK_PREFIX: &str = "****";
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const MASK_TAIL_CHARS: usize = 4;
+
+/// Masks provider and `extras` secrets in a stored snapshot the way clients do
+/// for the `redacted` privacy level, so responses carrying whole snapshots
+/// never return secrets that were uploaded as is.
+pub fn redact(snapshot: &mut Value) {
+    let Some(sections) = snapshot.as_object_mut() else {
+        return;
+    };
+    for (app, section) in sections.iter_mut() {
+        if app == "extras" {
+            redact_extras(section);
+            continue;
+        }
+        let Some(providers) = section.get_mut("providers").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        for provider in providers.values_mut() {
+            if app == "codex" {
+                redact_codex_settings(provider);
+            }
+            redact_by_key_name(provider);
+        }
+    }
+}
+
+/// Everything in Codex `auth.json` is a credential; `config.toml` is a string
+/// whose quoted secret values are masked in place.
+fn redact_codex_settings(provider: &mut Value) {
+    let Some(settings) = provider.get_mut("settingsConfig") else {
+        return;
+    };
+    if let Some(auth) = settings.get_mut("auth") {
+        redact_all_strings(auth);
+    }
+    if let Some(Value::String(config)) = settings.get_mut("config") {
+        *config = redact_toml(config);
+    }
+}
+
+/// MCP server `env` and `headers` values are all treated as credentials.
+/// Common config snippets are opaque text and are masked when they mention a
+/// secret-looking key.
+fn redact_extras(extras: &mut Value) {
+    if let Some(servers) = extras.get_mut("mcpServers").and_then(Value::as_object_mut) {
+        for server in servers.values_mut() {
+            if let Some(spec) = server.get_mut("server") {
+                for field in ["env", "headers"] {
+                    if let Some(values) = spec.get_mut(field) {
+                        redact_all_strings(values);
+                    }
+                }
+            }
+            redact_by_key_name(server);
+        }
+    }
+    if let Some(snippets) = extras
+        .get_mut("commonConfigSnippets")
+        .and_then(Value::as_object_mut)
+    {
+        for snippet in snippets.values_mut() {
+            let Value::String(text) = snippet else {
+                continue;
+            };
+            *text = match serde_json::from_str::<Value>(text) {
+                Ok(mut parsed) => {
+                    redact_by_key_name(&mut parsed);
+                    parsed.to_string()
+                }
+                Err(_) => redact_toml(text),
+            };
+        }
+    }
+}
+
+fn redact_all_strings(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = mask(text),
+        Value::Array(items) => items.iter_mut().for_each(redact_all_strings),
+        Value::Object(fields) => fields.values_mut().for_each(redact_all_strings),
+        _ => {}
+    }
+}
+
+fn redact_by_key_name(value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(redact_by_key_name),
+        Value::Object(fields) => {
+            for (key, item) in fields.iter_mut() {
+                match item {
+                    Value::String(text) if is_secret_key(key) => *text = mask(text),
+                    _ => redact_by_key_name(item),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_toml(config: &str) -> String {
+    config
+        .split_inclusive('\n')
+        .map(|line| {
+            let Some((key, value)) = line.split_once('=') else {
+                return line.to_string();
+            };
+            let key_name = key.trim().rsplit('.').next().unwrap_or_default();
+            let Some(quoted) = value.trim_start().strip_prefix('"') else {
+                return line.to_string();
+            };
+            let Some(end) = quoted.find('"') else {
+                return line.to_string();
+            };
+            if !is_secret_key(key_name) {
+                return line.to_string();
+            }
+            let indent = &value[..value.len() - value.trim_start().len()];
+            format!(
+                "{key}={indent}\"{}\"{}",
+                mask(&quoted[..end]),
+                &quoted[end + 1..]
+            )
+        })
+        .collect()
+}
+
+/// Matches `api_key`, `apiKey`, `ANTHROPIC_AUTH_TOKEN`, `accessToken`,
+/// `client_secret` and the like.
+fn is_secret_key(key: &str) -> bool {
+    let key: String = key
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    ["apikey", "secret", "password"]
+        .iter()
+        .any(|needle| key.contains(needle))
+        || key.ends_with("token")
+}
+
+/// Keeps the last 4 characters; short values are hidden entirely.
+fn mask(value: &str) -> String {
+    if value.is_empty() || value.starts_with(MASK_PREFIX) || value.starts_with(ENCRYPTED_PREFIX) {
+        return value.to_string();
+    }
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= MASK_TAIL_CHARS * 2 {
+        return MASK_PREFIX.to_string();
+    }
+    let tail: String = chars[chars.len() - MASK_TAIL_CHARS..].iter().collect();
+    format!("{MASK_PREFIX}{tail}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn masks_claude_env_secrets_only() {
+        let mut snapshot = json!({
+            "claude": {
+                "currentId": "packycode",
+                "providers": {
+                    "packycode": {
+                        "id": "packycode",
+                        "name": "PackyCode",
+                        "settingsConfig": {
+                            "env": {
+                                "ANTHROPIC_AUTH_TOKEN": "sk-packy-1234567890",
+                                "ANTHROPIC_BASE_URL": "https://api.packycode.com",
+                                "CLAUDE_CODE_MAX_OUTPUT_TOKENS": "32000",
+                            }
+                        },
+                        "meta": {
+                            "usage_script": { "apiKey": "usage-key-abcdef", "accessToken": "short" }
+                        }
+                    }
+                }
+            }
+        });
+
+        redact(&mut snapshot);
+
+        let provider = &snapshot["claude"]["providers"]["packycode"];
+        let env = &provider["settingsConfig"]["env"];
+        assert_eq!(env["ANTHROPIC_AUTH_TOKEN"], json!("****7890"));
+        assert_eq!(
+            env["ANTHROPIC_BASE_URL"],
+            json!("https://api.packycode.com")
+        );
+        assert_eq!(env["CLAUDE_CODE_MAX_OUTPUT_TOKENS"], json!("32000"));
+        assert_eq!(
+            provider["meta"]["usage_script"]["apiKey"],
+            json!("****cdef")
+        );
+        assert_eq!(
+            provider["meta"]["usage_script"]["accessToken"],
+            json!("****")
+        );
+        assert_eq!(snapshot["claude"]["currentId"], json!("packycode"));
+    }
+
+    #[test]
+    fn masks_codex_auth_and_config_toml() {
+        let mut snapshot = json!({
+            "codex": {
+                "providers": {
+                    "aicodewith": {
+                        "settingsConfig": {
+                            "auth": { "OPENAI_API_KEY": "sk-codex-abcdefgh", "tokens": { "id": "eyJhbGciOi.payload" } },
+                            "config": "model = \"gpt-5-codex\"\n[model_providers.aicodewith]\nexperimental_bearer_token = \"sk-toml-12345678\" # inline\n"
+                        }
+                    }
+                }
+            }
+        });
+
+        redact(&mut snapshot);
+
+        let settings = &snapshot["codex"]["providers"]["aicodewith"]["settingsConfig"];
+        assert_eq!(settings["auth"]["OPENAI_API_KEY"], json!("****efgh"));
+        assert_eq!(settings["auth"]["tokens"]["id"], json!("****load"));
+        assert_eq!(
+            settings["config"],
+            json!("model = \"gpt-5-codex\"\n[model_providers.aicodewith]\nexperimental_bearer_token = \"****5678\" # inline\n")
+        );
+    }
+
+    #[test]
+    fn masks_mcp_server_env_and_snippet_secrets() {
+        let mut snapshot = json!({
+            "extras": {
+                "mcpServers": {
+                    "github": {
+                        "id": "github",
+                        "server": {
+                            "command": "npx",
+                            "env": { "GITHUB_PERSONAL_ACCESS_TOKEN": "ghp_abcdefghijkl" },
+                            "headers": { "Authorization": "Bearer abcdefghijkl" }
+                        }
+                    }
+                },
+                "commonConfigSnippets": {
+                    "claude": "{\"env\":{\"ANTHROPIC_AUTH_TOKEN\":\"sk-snippet-12345678\"}}",
+                    "codex": "model = \"gpt-5\"\nexperimental_bearer_token = \"sk-toml-12345678\"\n"
+                }
+            }
+        });
+
+        redact(&mut snapshot);
+
+        let server = &snapshot["extras"]["mcpServers"]["github"]["server"];
+        assert_eq!(
+            server["env"]["GITHUB_PERSONAL_ACCESS_TOKEN"],
+            json!("****ijkl")
+        );
+        assert_eq!(server["headers"]["Authorization"], json!("****ijkl"));
+        assert_eq!(server["command"], json!("npx"));
+        let snippets = &snapshot["extras"]["commonConfigSnippets"];
+        assert_eq!(
+            snippets["claude"],
+            json!("{\"env\":{\"ANTHROPIC_AUTH_TOKEN\":\"****5678\"}}")
+        );
+        assert_eq!(
+            snippets["codex"],
+            json!("model = \"gpt-5\"\nexperimental_bearer_token = \"****5678\"\n")
+        );
+    }
+
+    #[test]
+    fn leaves_protected_values_and_non_snapshots_alone() {
+        let mut snapshot = json!({
+            "privacy": "encrypted",
+            "gemini": {
+                "providers": {
+                    "google": {
+                        "settingsConfig": {
+                            "env": { "GEMINI_API_KEY": "enc:v1:AAAA", "GOOGLE_API_KEY": "****abcd" }
+                        }
+                    }
+                }
+            }
+        });
+        let expected = snapshot.clone();
+
+        redact(&mut snapshot);
+        assert_eq!(snapshot, expected);
+
+        let mut marker = json!({ "truncated": true, "reason": "quota_exceeded" });
+        redact(&mut marker);
+        assert_eq!(
+            marker,
+            json!({ "truncated": true, "reason": "quota_exceeded" })
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// App sections summarized for the device detail page, in display order.
+const APPS: [&str; 3] = ["claude", "codex", "gemini"];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SectionStatus {
+    Present,
+    /// The snapshot has no section for this app (or it is `null`).
+    Missing,
+    /// The section exists but is not shaped like `{ currentId, providers }`.
+    Unparseable,
+}
+
+/// Provider overview of one app section of a snapshot.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSummary {
+    pub app: &'static str,
+    pub status: SectionStatus,
+    pub provider_count: usize,
+    pub current_id: Option<String>,
+    /// `name` of the current provider; `None` when it is not in `providers`.
+    pub current_name: Option<String>,
+}
+
+impl AppSummary {
+    fn empty(app: &'static str, status: SectionStatus) -> Self {
+        Self {
+            app,
+            status,
+            provider_count: 0,
+            current_id: None,
+            current_name: None,
+        }
+    }
+}
+
+/// Summarizes every known app of a stored snapshot. `None` when there is no
+/// provider data to summarize (null snapshots and quota markers).
+pub fn summarize(snapshot: &Value) -> Option<Vec<AppSummary>> {
+    if snapshot.is_null() || snapshot.get("truncated").and_then(Value::as_bool) == Some(true) {
+        return None;
+    }
+    if !snapshot.is_object() {
+        return Some(
+            APPS.iter()
+                .map(|app| AppSummary::empty(app, SectionStatus::Unparseable))
+                .collect(),
+        );
+    }
+    Some(
+        APPS.iter()
+            .map(|app| summarize_app(app, snapshot.get(app)))
+            .collect(),
+    )
+}
+
+fn summarize_app(app: &'static str, section: Option<&Value>) -> AppSummary {
+    let section = match section {
+        None | Some(Value::Null) => return AppSummary::empty(app, SectionStatus::Missing),
+        Some(Value::Object(section)) => section,
+        Some(_) => return AppSummary::empty(app, SectionStatus::Unparseable),
+    };
+    let Some(providers) = section.get("providers").and_then(Value::as_object) else {
+        return AppSummary::empty(app, SectionStatus::Unparseable);
+    };
+    // Older clients omitted `currentId` when no provider was selected.
+    let current_id = match section.get("currentId") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(id)) => Some(id.clone()),
+        Some(_) => return AppSummary::empty(app, SectionStatus::Unparseable),
+    };
+    let current_name = current_id
+        .as_ref()
+        .and_then(|id| providers.get(id))
+        .and_then(|provider| provider.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    AppSummary {
+        app,
+        status: SectionStatus::Present,
+        provider_count: providers.len(),
+        current_id,
+        current_name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture(source: &str) -> Value {
+        serde_json::from_str(source).expect("valid fixture")
+    }
+
+    fn present(
+        app: &'static str,
+        provider_count: usize,
+        current: Option<(&str, &str)>,
+    ) -> AppSummary {
+        AppSummary {
+            app,
+            status: SectionStatus::Present,
+            provider_count,
+            current_id: current.map(|(id, _)| id.to_string()),
+            current_name: current.map(|(_, name)| name.to_string()),
+        }
+    }
+
+    #[test]
+    fn summarizes_current_snapshots() {
+        let snapshot = fixture(include_str!("../fixtures/snapshots/current.json"));
+
+        assert_eq!(
+            summarize(&snapshot).unwrap(),
+            vec![
+                present("claude", 4, Some(("packycode", "PackyCode"))),
+                present("codex", 2, Some(("codex-official", "OpenAI Official"))),
+                AppSummary::empty("gemini", SectionStatus::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn legacy_snapshots_without_current_id_have_no_current_provider() {
+        let snapshot = fixture(include_str!(
+            "../fixtures/snapshots/legacy_without_current_id.json"
+        ));
+
+        assert_eq!(
+            summarize(&snapshot).unwrap(),
+            vec![
+                present("claude", 2, None),
+                present("codex", 0, None),
+                AppSummary::empty("gemini", SectionStatus::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_sections_are_unparseable_on_their_own() {
+        let mut snapshot = fixture(include_str!("../fixtures/snapshots/malformed.json"));
+
+        let statuses = |snapshot: &Value| {
+            summarize(snapshot)
+                .unwrap()
+                .into_iter()
+                .map(|app| app.status)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statuses(&snapshot), vec![SectionStatus::Unparseable; 3]);
+
+        snapshot["codex"]["currentId"] = json!("gone");
+        assert_eq!(
+            summarize(&snapshot).unwrap()[1],
+            AppSummary {
+                current_name: None,
+                ..present("codex", 1, Some(("gone", "")))
+            }
+        );
+    }
+
+    #[test]
+    fn null_snapshots_and_quota_markers_have_no_summary() {
+        assert_eq!(summarize(&Value::Null), None);
+        assert_eq!(
+            summarize(&json!({
+                "truncated": true,
+                "reason": "quota_exceeded",
+                "originalBytes": 1024,
+            })),
+            None
+        );
+        assert_eq!(
+            summarize(&json!("not a snapshot")).unwrap(),
+            APPS.iter()
+                .map(|app| AppSummary::empty(app, SectionStatus::Unparseable))
+                .collect::<Vec<_>>()
+        );
+    }
+}
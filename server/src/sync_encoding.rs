@@ -0,0 +1,100 @@
+use std::io::Read;
+
+use axum::{
+    body::Bytes,
+    http::{header::CONTENT_ENCODING, HeaderMap, StatusCode},
+};
+use flate2::read::GzDecoder;
+
+use crate::ApiError;
+
+/// Upper bound for a decompressed sync body, so a small gzip bomb cannot
+/// exhaust memory.
+const MAX_DECODED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Undoes `Content-Encoding: gzip` on a sync request. Identity bodies pass
+/// through unchanged; any other encoding is answered with 415 so clients can
+/// fall back to sending plain JSON.
+pub fn decode_body(headers: &HeaderMap, body: Bytes) -> Result<Bytes, ApiError> {
+    let encoding = headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match encoding.as_str() {
+        "" | "identity" => Ok(body),
+        "gzip" => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(body.as_ref())
+                .take(MAX_DECODED_BYTES + 1)
+                .read_to_end(&mut decoded)
+                .map_err(|err| {
+                    ApiError::new(StatusCode::BAD_REQUEST, format!("invalid gzip body: {err}"))
+                })?;
+            if decoded.len() as u64 > MAX_DECODED_BYTES {
+                return Err(ApiError::new(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "decompressed body too large",
+                ));
+            }
+            Ok(Bytes::from(decoded))
+        }
+        other => Err(ApiError::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("unsupported content encoding: {other}"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    fn encoded(encoding: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, encoding.parse().unwrap());
+        headers
+    }
+
+    fn gzip(body: &[u8]) -> Bytes {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body).unwrap();
+        Bytes::from(encoder.finish().unwrap())
+    }
+
+    #[test]
+    fn identity_and_gzip_bodies_decode() {
+        let body = Bytes::from_static(br#"{"deviceId":"device-a"}"#);
+        assert_eq!(decode_body(&HeaderMap::new(), body.clone()).unwrap(), body);
+        assert_eq!(
+            decode_body(&encoded("identity"), body.clone()).unwrap(),
+            body
+        );
+        assert_eq!(decode_body(&encoded(" GZIP "), gzip(&body)).unwrap(), body);
+    }
+
+    #[test]
+    fn gzip_bombs_are_refused() {
+        let bomb = gzip(&vec![0; MAX_DECODED_BYTES as usize + 1]);
+        assert!(bomb.len() < 64 * 1024);
+        let err = decode_body(&encoded("gzip"), bomb).unwrap_err();
+        assert_eq!(err.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let exact = gzip(&vec![0; MAX_DECODED_BYTES as usize]);
+        assert!(decode_body(&encoded("gzip"), exact).is_ok());
+    }
+
+    #[test]
+    fn unknown_encodings_and_broken_gzip_are_rejected() {
+        let err = decode_body(&encoded("br"), Bytes::from_static(b"{}")).unwrap_err();
+        assert_eq!(err.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let err = decode_body(&encoded("gzip"), Bytes::from_static(b"{}")).unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+}
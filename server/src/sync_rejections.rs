@@ -0,0 +1,269 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    net::IpAddr,
+    sync::Mutex,
+};
+
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Minute buckets kept in memory; a flood from many addresses evicts the
+/// oldest first.
+const CAPACITY: usize = 10_000;
+/// Hex characters of the presented token's SHA-256 that are kept: enough to
+/// tell tokens apart, far too few to recover one.
+const TOKEN_PREFIX_LEN: usize = 8;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+struct BucketKey {
+    minute: DateTime<Utc>,
+    ip: Option<String>,
+    token_prefix: Option<String>,
+}
+
+struct Bucket {
+    count: u64,
+    device_id: Option<String>,
+    last_at: DateTime<Utc>,
+}
+
+/// Sync requests rejected for a wrong or missing token, counted per minute,
+/// source address and token hash prefix. The presented token itself is
+/// never kept.
+#[derive(Default)]
+pub struct SyncRejections {
+    buckets: Mutex<BTreeMap<BucketKey, Bucket>>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRejectionSummary {
+    pub since: DateTime<Utc>,
+    pub total: u64,
+    pub by_token_prefix: Vec<TokenPrefixCount>,
+    pub by_ip: Vec<IpCount>,
+}
+
+/// `tokenPrefix` is `null` for requests without a bearer token.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPrefixCount {
+    pub token_prefix: Option<String>,
+    pub count: u64,
+    pub addresses: usize,
+    pub last_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IpCount {
+    pub ip: Option<String>,
+    pub count: u64,
+    /// Device IDs claimed in the rejected requests that had a parseable body.
+    pub device_ids: Vec<String>,
+    pub last_at: DateTime<Utc>,
+}
+
+impl SyncRejections {
+    /// Records one rejection. `claimed_device_id` only runs (and the rejection
+    /// is only logged) for the first one per address and token in a minute,
+    /// so a flood costs a counter increment per request.
+    pub fn record(
+        &self,
+        ip: Option<IpAddr>,
+        presented_token: Option<&str>,
+        now: DateTime<Utc>,
+        claimed_device_id: impl FnOnce() -> Option<String>,
+    ) {
+        let key = BucketKey {
+            minute: now.duration_trunc(TimeDelta::minutes(1)).unwrap_or(now),
+            ip: ip.map(|ip| ip.to_string()),
+            token_prefix: presented_token.map(token_prefix),
+        };
+        if let Some(bucket) = self.lock().get_mut(&key) {
+            bucket.count += 1;
+            bucket.last_at = now;
+            return;
+        }
+
+        let device_id = claimed_device_id();
+        tracing::warn!(
+            ip = key.ip.as_deref().unwrap_or("unknown"),
+            token_prefix = key.token_prefix.as_deref().unwrap_or("none"),
+            device_id = device_id.as_deref().unwrap_or("unknown"),
+            "rejected sync authentication"
+        );
+        let mut buckets = self.lock();
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            count: 0,
+            device_id: None,
+            last_at: now,
+        });
+        bucket.count += 1;
+        bucket.last_at = bucket.last_at.max(now);
+        bucket.device_id = bucket.device_id.take().or(device_id);
+        while buckets.len() > CAPACITY {
+            buckets.pop_first();
+        }
+    }
+
+    /// Counts since `since`, busiest token prefix and address first.
+    pub fn summary(&self, since: DateTime<Utc>) -> SyncRejectionSummary {
+        let start = BucketKey {
+            minute: since.duration_trunc(TimeDelta::minutes(1)).unwrap_or(since),
+            ip: None,
+            token_prefix: None,
+        };
+        let mut total = 0;
+        let mut prefixes: BTreeMap<Option<String>, Totals<Option<String>>> = BTreeMap::new();
+        let mut ips: BTreeMap<Option<String>, Totals<String>> = BTreeMap::new();
+        for (key, bucket) in self.lock().range(start..) {
+            total += bucket.count;
+            prefixes
+                .entry(key.token_prefix.clone())
+                .or_insert_with(|| Totals::new(bucket.last_at))
+                .add(bucket, [key.ip.clone()]);
+            ips.entry(key.ip.clone())
+                .or_insert_with(|| Totals::new(bucket.last_at))
+                .add(bucket, bucket.device_id.clone());
+        }
+
+        let mut by_token_prefix: Vec<TokenPrefixCount> = prefixes
+            .into_iter()
+            .map(|(token_prefix, totals)| TokenPrefixCount {
+                token_prefix,
+                count: totals.count,
+                addresses: totals.members.len(),
+                last_at: totals.last_at,
+            })
+            .collect();
+        by_token_prefix.sort_by_key(|prefix| Reverse(prefix.count));
+        let mut by_ip: Vec<IpCount> = ips
+            .into_iter()
+            .map(|(ip, totals)| IpCount {
+                ip,
+                count: totals.count,
+                device_ids: totals.members.into_iter().collect(),
+                last_at: totals.last_at,
+            })
+            .collect();
+        by_ip.sort_by_key(|ip| Reverse(ip.count));
+
+        SyncRejectionSummary {
+            since,
+            total,
+            by_token_prefix,
+            by_ip,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<BucketKey, Bucket>> {
+        self.buckets.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Buckets added up for one token prefix or address.
+struct Totals<T> {
+    count: u64,
+    members: BTreeSet<T>,
+    last_at: DateTime<Utc>,
+}
+
+impl<T: Ord> Totals<T> {
+    fn new(last_at: DateTime<Utc>) -> Self {
+        Self {
+            count: 0,
+            members: BTreeSet::new(),
+            last_at,
+        }
+    }
+
+    fn add(&mut self, bucket: &Bucket, members: impl IntoIterator<Item = T>) {
+        self.count += bucket.count;
+        self.members.extend(members);
+        self.last_at = self.last_at.max(bucket.last_at);
+    }
+}
+
+fn token_prefix(token: &str) -> String {
+    let mut hash = hex::encode(Sha256::digest(token.as_bytes()));
+    hash.truncate(TOKEN_PREFIX_LEN);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::from([10, 0, 0, last]))
+    }
+
+    #[test]
+    fn repeated_rejections_share_a_bucket_and_parse_the_body_once() {
+        let rejections = SyncRejections::default();
+        let mut parsed = 0;
+        for second in 0..3 {
+            rejections.record(
+                ip(1),
+                Some("old-token"),
+                at(&format!("2026-01-02T03:04:0{second}Z")),
+                || {
+                    parsed += 1;
+                    Some("device-a".to_string())
+                },
+            );
+        }
+        rejections.record(ip(2), Some("old-token"), at("2026-01-02T03:05:00Z"), || {
+            None
+        });
+        rejections.record(ip(2), None, at("2026-01-02T03:05:01Z"), || None);
+        assert_eq!(parsed, 1);
+        assert_eq!(rejections.lock().len(), 3);
+
+        let summary = rejections.summary(at("2026-01-02T00:00:00Z"));
+        assert_eq!(summary.total, 5);
+        let old = &summary.by_token_prefix[0];
+        assert_eq!(old.token_prefix, Some(token_prefix("old-token")));
+        assert_eq!((old.count, old.addresses), (4, 2));
+        assert_eq!(summary.by_token_prefix[1].token_prefix, None);
+        assert_eq!(summary.by_ip[0].ip.as_deref(), Some("10.0.0.1"));
+        assert_eq!(summary.by_ip[0].device_ids, ["device-a"]);
+        assert_eq!(summary.by_ip[0].last_at, at("2026-01-02T03:04:02Z"));
+
+        // Older minutes fall outside the window
+        assert_eq!(rejections.summary(at("2026-01-02T03:05:00Z")).total, 2);
+    }
+
+    #[test]
+    fn only_a_short_hash_prefix_of_the_token_is_kept() {
+        let token = "super-secret-sync-token";
+        let prefix = token_prefix(token);
+        assert_eq!(prefix.len(), TOKEN_PREFIX_LEN);
+        assert!(!prefix.contains(token));
+
+        let rejections = SyncRejections::default();
+        rejections.record(ip(1), Some(token), Utc::now(), || None);
+        let summary =
+            serde_json::to_string(&rejections.summary(Utc::now() - TimeDelta::hours(1))).unwrap();
+        assert!(!summary.contains(token), "{summary}");
+        assert!(summary.contains(&prefix));
+    }
+
+    #[test]
+    fn the_oldest_buckets_are_evicted_over_capacity() {
+        let rejections = SyncRejections::default();
+        let start = at("2026-01-02T00:00:00Z");
+        for minute in 0..=CAPACITY as i64 {
+            rejections.record(ip(1), None, start + TimeDelta::minutes(minute), || None);
+        }
+        assert_eq!(rejections.lock().len(), CAPACITY);
+        assert_eq!(rejections.summary(start).total, CAPACITY as u64);
+    }
+}
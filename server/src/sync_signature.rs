@@ -0,0 +1,261 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Mutex,
+};
+
+use axum::http::{HeaderMap, StatusCode};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::ApiError;
+
+const SIGNATURE_HEADER: &str = "x-signature";
+const TIMESTAMP_HEADER: &str = "x-timestamp";
+const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+const REPLAY_CACHE_CAPACITY: usize = 4096;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub struct SyncSignature {
+    timestamp: i64,
+    signature: Vec<u8>,
+}
+
+/// Reads `X-Timestamp` / `X-Signature`. Returns `None` for legacy unsigned
+/// requests; a request carrying only one of the headers is rejected.
+pub fn extract_signature(headers: &HeaderMap) -> Result<Option<SyncSignature>, ApiError> {
+    let timestamp = headers
+        .get(TIMESTAMP_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    let (timestamp, signature) = match (timestamp, signature) {
+        (None, None) => return Ok(None),
+        (Some(timestamp), Some(signature)) => (timestamp, signature),
+        _ => {
+            return Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "incomplete signature headers",
+            ))
+        }
+    };
+
+    let timestamp = timestamp
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| ApiError::new(StatusCode::UNAUTHORIZED, "invalid signature timestamp"))?;
+    let signature = hex::decode(signature.trim())
+        .map_err(|_| ApiError::new(StatusCode::UNAUTHORIZED, "invalid signature encoding"))?;
+
+    Ok(Some(SyncSignature {
+        timestamp,
+        signature,
+    }))
+}
+
+/// Verifies `hex(hmac_sha256(secret, timestamp + body))` and the clock skew.
+pub fn verify_signature(
+    secret: &str,
+    signature: &SyncSignature,
+    body: &[u8],
+    now: i64,
+) -> Result<(), ApiError> {
+    if (now - signature.timestamp).abs() > MAX_CLOCK_SKEW_SECS {
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "signature timestamp outside allowed skew",
+        ));
+    }
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    mac.update(signature.timestamp.to_string().as_bytes());
+    mac.update(body);
+    mac.verify_slice(&signature.signature)
+        .map_err(|_| ApiError::new(StatusCode::UNAUTHORIZED, "invalid signature"))
+}
+
+/// Remembers recently accepted timestamp+signature pairs. Entries older than
+/// the skew window are dropped since those requests fail verification anyway.
+pub struct ReplayCache {
+    inner: Mutex<ReplayCacheInner>,
+}
+
+#[derive(Default)]
+struct ReplayCacheInner {
+    order: VecDeque<(i64, String)>,
+    seen: HashSet<String>,
+}
+
+impl ReplayCache {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(ReplayCacheInner::default()),
+        }
+    }
+
+    /// Returns `false` when the pair was already seen.
+    pub fn check_and_insert(&self, signature: &SyncSignature, now: i64) -> bool {
+        let key = format!(
+            "{}:{}",
+            signature.timestamp,
+            hex::encode(&signature.signature)
+        );
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+
+        while let Some((timestamp, _)) = inner.order.front() {
+            if now - *timestamp <= MAX_CLOCK_SKEW_SECS && inner.order.len() < REPLAY_CACHE_CAPACITY
+            {
+                break;
+            }
+            if let Some((_, expired)) = inner.order.pop_front() {
+                inner.seen.remove(&expired);
+            }
+        }
+
+        if !inner.seen.insert(key.clone()) {
+            return false;
+        }
+        inner.order.push_back((signature.timestamp, key));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "sync-secret";
+    const NOW: i64 = 1_767_225_600;
+
+    fn signed(secret: &str, timestamp: i64, body: &[u8]) -> SyncSignature {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(body);
+        SyncSignature {
+            timestamp,
+            signature: mac.finalize().into_bytes().to_vec(),
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn valid_signatures_verify() {
+        let body = br#"{"deviceId":"device-a"}"#;
+        let signature = signed(SECRET, NOW, body);
+        assert!(verify_signature(SECRET, &signature, body, NOW).is_ok());
+        // Within the allowed skew either way
+        assert!(verify_signature(SECRET, &signature, body, NOW + MAX_CLOCK_SKEW_SECS).is_ok());
+        assert!(verify_signature(SECRET, &signature, body, NOW - MAX_CLOCK_SKEW_SECS).is_ok());
+    }
+
+    #[test]
+    fn tampered_bodies_and_wrong_secrets_are_rejected() {
+        let signature = signed(SECRET, NOW, br#"{"deviceId":"device-a"}"#);
+        let err =
+            verify_signature(SECRET, &signature, br#"{"deviceId":"device-b"}"#, NOW).unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(err.message, "invalid signature");
+
+        assert!(verify_signature(
+            "other-secret",
+            &signature,
+            br#"{"deviceId":"device-a"}"#,
+            NOW
+        )
+        .is_err());
+        // The timestamp is covered by the signature too
+        let moved = SyncSignature {
+            timestamp: NOW + 1,
+            signature: signature.signature.clone(),
+        };
+        assert!(verify_signature(SECRET, &moved, br#"{"deviceId":"device-a"}"#, NOW).is_err());
+    }
+
+    #[test]
+    fn skewed_timestamps_are_rejected() {
+        let body = b"{}";
+        for timestamp in [NOW - MAX_CLOCK_SKEW_SECS - 1, NOW + MAX_CLOCK_SKEW_SECS + 1] {
+            let err =
+                verify_signature(SECRET, &signed(SECRET, timestamp, body), body, NOW).unwrap_err();
+            assert_eq!(err.message, "signature timestamp outside allowed skew");
+        }
+    }
+
+    #[test]
+    fn headers_must_come_in_pairs() {
+        assert!(extract_signature(&HeaderMap::new()).unwrap().is_none());
+
+        let signature = extract_signature(&headers(&[
+            (TIMESTAMP_HEADER, " 1767225600 "),
+            (SIGNATURE_HEADER, "00ff"),
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(signature.timestamp, NOW);
+        assert_eq!(signature.signature, [0x00, 0xff]);
+
+        for pairs in [
+            &[(TIMESTAMP_HEADER, "1767225600")][..],
+            &[(SIGNATURE_HEADER, "00ff")][..],
+            &[(TIMESTAMP_HEADER, "yesterday"), (SIGNATURE_HEADER, "00ff")][..],
+            &[
+                (TIMESTAMP_HEADER, "1767225600"),
+                (SIGNATURE_HEADER, "not-hex"),
+            ][..],
+        ] {
+            let err = extract_signature(&headers(pairs)).err().unwrap();
+            assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[test]
+    fn replayed_signatures_are_rejected() {
+        let cache = ReplayCache::new();
+        let signature = signed(SECRET, NOW, b"{}");
+        assert!(cache.check_and_insert(&signature, NOW));
+        assert!(!cache.check_and_insert(&signature, NOW + 10));
+        // Another body signed in the same second is a different request
+        assert!(cache.check_and_insert(&signed(SECRET, NOW, b"[]"), NOW));
+    }
+
+    #[test]
+    fn replay_entries_expire_with_the_skew_window() {
+        let cache = ReplayCache::new();
+        let old = signed(SECRET, NOW, b"{}");
+        assert!(cache.check_and_insert(&old, NOW));
+
+        // Once outside the window the entry is dropped; verification rejects
+        // the request on its timestamp instead
+        let later = NOW + MAX_CLOCK_SKEW_SECS + 1;
+        assert!(cache.check_and_insert(&signed(SECRET, later, b"{}"), later));
+        assert_eq!(cache.inner.lock().unwrap().order.len(), 1);
+        assert!(cache.check_and_insert(&old, later));
+    }
+
+    #[test]
+    fn the_oldest_entries_are_evicted_over_capacity() {
+        let cache = ReplayCache::new();
+        let first = signed(SECRET, NOW, b"0");
+        assert!(cache.check_and_insert(&first, NOW));
+        for index in 1..=REPLAY_CACHE_CAPACITY {
+            let body = index.to_string();
+            assert!(cache.check_and_insert(&signed(SECRET, NOW, body.as_bytes()), NOW));
+        }
+        let inner = cache.inner.lock().unwrap();
+        assert_eq!(inner.order.len(), REPLAY_CACHE_CAPACITY);
+        assert_eq!(inner.seen.len(), REPLAY_CACHE_CAPACITY);
+        drop(inner);
+        // The first entry made room for the newest
+        assert!(cache.check_and_insert(&first, NOW));
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::Notify;
+
+/// First retry delay after a failed run; doubles per consecutive failure up to
+/// the task's interval.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Background jobs and when they last ran. Jobs are started with
+/// [`TaskRegistry::spawn_periodic`], which keeps their entry up to date.
+#[derive(Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<BTreeMap<&'static str, TaskEntry>>,
+}
+
+struct TaskEntry {
+    status: TaskStatus,
+    trigger: Arc<Notify>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub name: &'static str,
+    pub interval_secs: u64,
+    pub running: bool,
+    pub runs: u64,
+    pub consecutive_failures: u32,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_result: Option<TaskResult>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskResult {
+    pub ok: bool,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+impl TaskRegistry {
+    /// Runs `job` every `every` (first run after one interval) and whenever
+    /// [`TaskRegistry::run_now`] is called. Errors and panics are logged and
+    /// recorded, and the job is retried with backoff instead of stopping, so
+    /// it survives database outages.
+    pub fn spawn_periodic<F, Fut>(self: &Arc<Self>, name: &'static str, every: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let trigger = Arc::new(Notify::new());
+        self.lock().insert(
+            name,
+            TaskEntry {
+                status: TaskStatus {
+                    name,
+                    interval_secs: every.as_secs(),
+                    running: false,
+                    runs: 0,
+                    consecutive_failures: 0,
+                    last_run_at: None,
+                    last_result: None,
+                    next_run_at: Some(after(Utc::now(), every)),
+                },
+                trigger: trigger.clone(),
+            },
+        );
+
+        let registry = self.clone();
+        tokio::spawn(async move {
+            let mut delay = every;
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = trigger.notified() => {}
+                }
+
+                let started = Utc::now();
+                registry.update(name, |status| {
+                    status.running = true;
+                    status.last_run_at = Some(started);
+                });
+                let result = match tokio::spawn(job()).await {
+                    Ok(result) => result,
+                    Err(err) => Err(format!("task panicked: {err}")),
+                };
+                if let Err(err) = &result {
+                    tracing::warn!(task = name, "background task failed: {}", err);
+                }
+                let finished = Utc::now();
+                registry.update(name, |status| {
+                    status.running = false;
+                    status.runs += 1;
+                    status.consecutive_failures = match result {
+                        Ok(()) => 0,
+                        Err(_) => status.consecutive_failures.saturating_add(1),
+                    };
+                    delay = retry_delay(every, status.consecutive_failures);
+                    status.last_result = Some(TaskResult {
+                        ok: result.is_ok(),
+                        error: result.err(),
+                        duration_ms: (finished - started).num_milliseconds(),
+                    });
+                    status.next_run_at = Some(after(finished, delay));
+                });
+            }
+        });
+    }
+
+    pub fn list(&self) -> Vec<TaskStatus> {
+        self.lock()
+            .values()
+            .map(|entry| entry.status.clone())
+            .collect()
+    }
+
+    /// Starts a run as soon as the task is idle. Returns `false` for unknown
+    /// tasks.
+    pub fn run_now(&self, name: &str) -> bool {
+        match self.lock().get(name) {
+            Some(entry) => {
+                entry.trigger.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update(&self, name: &str, apply: impl FnOnce(&mut TaskStatus)) {
+        if let Some(entry) = self.lock().get_mut(name) {
+            apply(&mut entry.status);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<&'static str, TaskEntry>> {
+        self.tasks.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+fn retry_delay(every: Duration, consecutive_failures: u32) -> Duration {
+    if consecutive_failures == 0 {
+        return every;
+    }
+    RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(consecutive_failures - 1))
+        .min(every)
+}
+
+fn after(time: DateTime<Utc>, every: Duration) -> DateTime<Utc> {
+    time + chrono::Duration::from_std(every).unwrap_or(chrono::TimeDelta::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    const NEVER: Duration = Duration::from_secs(24 * 60 * 60);
+
+    async fn wait_for_runs(registry: &TaskRegistry, name: &str, runs: u64) -> TaskStatus {
+        for _ in 0..200 {
+            let status = registry
+                .list()
+                .into_iter()
+                .find(|status| status.name == name)
+                .expect("task is registered");
+            if status.runs >= runs && !status.running {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("task {name} did not reach {runs} runs");
+    }
+
+    #[test]
+    fn failed_runs_are_retried_with_backoff_up_to_the_interval() {
+        let every = Duration::from_secs(60);
+        let delays: Vec<u64> = (0..9)
+            .map(|failures| retry_delay(every, failures).as_secs())
+            .collect();
+        assert_eq!(delays, [60, 1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(retry_delay(every, u32::MAX), every);
+    }
+
+    #[tokio::test]
+    async fn run_now_records_each_run() {
+        let registry = Arc::new(TaskRegistry::default());
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = calls.clone();
+        registry.spawn_periodic("count", NEVER, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        let status = &registry.list()[0];
+        assert_eq!(status.runs, 0);
+        assert!(status.last_run_at.is_none());
+        assert!(status.next_run_at.is_some());
+
+        assert!(registry.run_now("count"));
+        let status = wait_for_runs(&registry, "count", 1).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(status.last_run_at.is_some());
+        assert!(status.last_result.as_ref().is_some_and(|result| result.ok));
+        assert!(status.next_run_at > status.last_run_at);
+
+        assert!(!registry.run_now("unknown"));
+    }
+
+    #[tokio::test]
+    async fn failures_and_panics_are_recorded_and_the_task_keeps_running() {
+        let registry = Arc::new(TaskRegistry::default());
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = calls.clone();
+        registry.spawn_periodic("flaky", NEVER, move || {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if call == 0 {
+                    return Err("database unavailable".to_string());
+                }
+                panic!("boom");
+            }
+        });
+
+        registry.run_now("flaky");
+        let status = wait_for_runs(&registry, "flaky", 1).await;
+        assert_eq!(
+            status.last_result.map(|result| result.error),
+            Some(Some("database unavailable".to_string()))
+        );
+
+        registry.run_now("flaky");
+        let status = wait_for_runs(&registry, "flaky", 2).await;
+        assert_eq!(status.consecutive_failures, 2);
+        let error = status.last_result.and_then(|result| result.error);
+        assert!(error.is_some_and(|error| error.starts_with("task panicked")));
+    }
+}
@@ -0,0 +1,326 @@
+use axum::{body::Body, extract::MatchedPath, http::Request};
+use tracing::Span;
+
+pub struct TraceParent {
+    pub trace_id: String,
+    pub parent_id: String,
+    /// Only the OTLP exporter carries the flags over to its spans.
+    #[cfg_attr(not(feature = "otel"), allow(dead_code))]
+    pub flags: u8,
+}
+
+/// Parses a W3C `traceparent` header (`00-<trace-id>-<parent-id>-<flags>`).
+pub fn parse_traceparent(value: &str) -> Option<TraceParent> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+
+    let is_hex = |text: &str, len: usize| {
+        text.len() == len
+            && text
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    };
+    if !is_hex(version, 2) || version == "ff" || !is_hex(flags, 2) {
+        return None;
+    }
+    if version == "00" && parts.next().is_some() {
+        return None;
+    }
+    if !is_hex(trace_id, 32) || trace_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    if !is_hex(parent_id, 16) || parent_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+
+    Some(TraceParent {
+        trace_id: trace_id.to_string(),
+        parent_id: parent_id.to_string(),
+        flags: u8::from_str_radix(flags, 16).ok()?,
+    })
+}
+
+/// Request span for `TraceLayer`. Headers are not recorded so credentials
+/// never end up in spans; handlers fill in `device_id` once known.
+pub fn make_request_span(request: &Request<Body>) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let parent = request
+        .headers()
+        .get("traceparent")
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_traceparent);
+
+    let span = tracing::info_span!(
+        "http.request",
+        otel.kind = "server",
+        http.method = %request.method(),
+        http.route = %route,
+        trace_id = parent.as_ref().map(|value| value.trace_id.as_str()),
+        parent_span_id = parent.as_ref().map(|value| value.parent_id.as_str()),
+        device_id = tracing::field::Empty,
+    );
+    #[cfg(feature = "otel")]
+    if let Some(parent) = &parent {
+        otlp::set_remote_parent(&span, parent);
+    }
+    span
+}
+
+pub fn db_span(operation: &'static str) -> Span {
+    tracing::info_span!(
+        "db.query",
+        db.system = "postgresql",
+        db.operation = operation
+    )
+}
+
+pub fn record_device_id(device_id: &str) {
+    Span::current().record("device_id", device_id);
+}
+
+#[cfg(feature = "otel")]
+pub mod otlp {
+    use std::{env, time::Duration};
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::{
+        trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+        Context,
+    };
+    use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{
+        error::OTelSdkResult,
+        trace::{SdkTracer, SdkTracerProvider, SpanData, SpanProcessor},
+        Resource,
+    };
+    use tracing::{Span, Subscriber};
+    use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+    use tracing_subscriber::registry::LookupSpan;
+
+    use super::TraceParent;
+
+    const SERVICE_NAME: &str = "aicodewith-management-server";
+
+    fn is_sensitive(key: &str) -> bool {
+        let lowered = key.to_ascii_lowercase();
+        lowered.contains("token") || lowered.contains("authorization")
+    }
+
+    /// Drops token and authorization fields from spans and their events
+    /// before they reach the exporter.
+    #[derive(Debug)]
+    struct RedactingProcessor<P>(P);
+
+    impl<P: SpanProcessor> SpanProcessor for RedactingProcessor<P> {
+        fn on_start(&self, span: &mut opentelemetry_sdk::trace::Span, cx: &Context) {
+            self.0.on_start(span, cx);
+        }
+
+        fn on_end(&self, mut span: SpanData) {
+            span.attributes.retain(|kv| !is_sensitive(kv.key.as_str()));
+            for event in &mut span.events.events {
+                event.attributes.retain(|kv| !is_sensitive(kv.key.as_str()));
+            }
+            self.0.on_end(span);
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            self.0.force_flush()
+        }
+
+        fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+            self.0.shutdown_with_timeout(timeout)
+        }
+
+        fn set_resource(&mut self, resource: &Resource) {
+            self.0.set_resource(resource);
+        }
+    }
+
+    fn tracer_provider(processor: impl SpanProcessor + 'static) -> SdkTracerProvider {
+        let service_name =
+            env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| SERVICE_NAME.to_string());
+        SdkTracerProvider::builder()
+            .with_resource(Resource::builder().with_service_name(service_name).build())
+            .with_span_processor(RedactingProcessor(processor))
+            .build()
+    }
+
+    fn layer<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME))
+    }
+
+    /// Builds the layer from the standard OTLP env vars; `None` when unset.
+    /// The provider must be shut down on exit to flush the last batch.
+    pub fn layer_from_env<S>() -> Option<(OpenTelemetryLayer<S, SdkTracer>, SdkTracerProvider)>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        [
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+        ]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.trim().is_empty())?;
+        let exporter = match SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpJson)
+            .build()
+        {
+            Ok(exporter) => exporter,
+            Err(err) => {
+                eprintln!("otlp exporter disabled: {err}");
+                return None;
+            }
+        };
+
+        let provider = tracer_provider(
+            opentelemetry_sdk::trace::BatchSpanProcessor::builder(exporter).build(),
+        );
+        Some((layer(&provider), provider))
+    }
+
+    /// Continues the caller's trace from its `traceparent`.
+    pub fn set_remote_parent(span: &Span, parent: &TraceParent) {
+        let (Ok(trace_id), Ok(span_id)) = (
+            TraceId::from_hex(&parent.trace_id),
+            SpanId::from_hex(&parent.parent_id),
+        ) else {
+            return;
+        };
+        let context = SpanContext::new(
+            trace_id,
+            span_id,
+            TraceFlags::new(parent.flags),
+            true,
+            TraceState::default(),
+        );
+        let _ = span.set_parent(Context::new().with_remote_span_context(context));
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use opentelemetry_sdk::trace::{InMemorySpanExporter, SimpleSpanProcessor};
+        use tracing_subscriber::layer::SubscriberExt;
+
+        use super::*;
+
+        fn export(record: impl FnOnce()) -> Vec<SpanData> {
+            let exporter = InMemorySpanExporter::default();
+            let provider = tracer_provider(SimpleSpanProcessor::new(exporter.clone()));
+            let subscriber = tracing_subscriber::registry().with(layer(&provider));
+            tracing::subscriber::with_default(subscriber, record);
+            provider.force_flush().expect("flush spans");
+            exporter.get_finished_spans().expect("finished spans")
+        }
+
+        #[test]
+        fn token_fields_are_not_exported() {
+            let spans = export(|| {
+                let span = tracing::info_span!(
+                    "http.request",
+                    sync_token = "secret",
+                    Authorization = "Bearer secret",
+                    device_id = tracing::field::Empty,
+                );
+                span.record("device_id", "device-a");
+                span.in_scope(|| tracing::info!(admin_token = "secret", "checked"));
+            });
+
+            let keys: Vec<&str> = spans[0]
+                .attributes
+                .iter()
+                .map(|kv| kv.key.as_str())
+                .collect();
+            assert!(keys.contains(&"device_id"), "{keys:?}");
+            assert!(keys.iter().all(|key| !is_sensitive(key)), "{keys:?}");
+            let event = &spans[0].events.events[0];
+            assert!(event
+                .attributes
+                .iter()
+                .all(|kv| !is_sensitive(kv.key.as_str())));
+        }
+
+        #[test]
+        fn request_spans_continue_the_remote_trace() {
+            let parent = crate::telemetry::parse_traceparent(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .expect("valid traceparent");
+            let spans = export(|| {
+                let span = tracing::info_span!("http.request");
+                set_remote_parent(&span, &parent);
+                span.in_scope(|| {});
+            });
+
+            let trace_id = spans[0].span_context.trace_id();
+            assert_eq!(trace_id.to_string(), parent.trace_id);
+            assert_eq!(spans[0].parent_span_id.to_string(), parent.parent_id);
+            assert!(spans[0].parent_span_is_remote);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    #[test]
+    fn valid_traceparents_are_parsed() {
+        let parent = parse_traceparent(&format!(" 00-{TRACE_ID}-{PARENT_ID}-01 ")).unwrap();
+        assert_eq!(parent.trace_id, TRACE_ID);
+        assert_eq!(parent.parent_id, PARENT_ID);
+        assert_eq!(parent.flags, 1);
+    }
+
+    #[test]
+    fn invalid_versions_are_rejected() {
+        for version in ["ff", "0", "000", "0g", "AB"] {
+            let value = format!("{version}-{TRACE_ID}-{PARENT_ID}-01");
+            assert!(parse_traceparent(&value).is_none(), "{value}");
+        }
+    }
+
+    #[test]
+    fn all_zero_ids_are_rejected() {
+        let zero_trace = format!("00-{}-{PARENT_ID}-01", "0".repeat(32));
+        let zero_parent = format!("00-{TRACE_ID}-{}-01", "0".repeat(16));
+        assert!(parse_traceparent(&zero_trace).is_none());
+        assert!(parse_traceparent(&zero_parent).is_none());
+    }
+
+    #[test]
+    fn extra_fields_are_only_allowed_after_version_00() {
+        let extra = format!("00-{TRACE_ID}-{PARENT_ID}-01-extra");
+        assert!(parse_traceparent(&extra).is_none());
+        let future = format!("01-{TRACE_ID}-{PARENT_ID}-01-extra");
+        assert!(parse_traceparent(&future).is_some());
+    }
+
+    #[test]
+    fn malformed_ids_and_flags_are_rejected() {
+        for value in [
+            format!("00-{}-{PARENT_ID}-01", &TRACE_ID[1..]),
+            format!("00-{}-{PARENT_ID}-01", TRACE_ID.to_uppercase()),
+            format!("00-{TRACE_ID}-{}-01", &PARENT_ID[1..]),
+            format!("00-{TRACE_ID}-{PARENT_ID}-1"),
+            format!("00-{TRACE_ID}-{PARENT_ID}"),
+        ] {
+            assert!(parse_traceparent(&value).is_none(), "{value}");
+        }
+    }
+}
@@ -53,10 +53,29 @@ type DeviceConfigSnapshotView = {
   gemini: AppSnapshotView | null;
 };
 
+type DeviceListResponse = {
+  devices: DeviceSummary[];
+  total: number;
+  hasMore: boolean;
+};
+
+const DEVICE_PAGE_SIZE = 50;
+/** 服务端要求设备 ID / 指纹前缀至少 4 个字符 */
+const MIN_SEARCH_LENGTH = 4;
+
+type AppSummary = {
+  app: string;
+  status: "present" | "missing" | "unparseable";
+  providerCount: number;
+  currentId: string | null;
+  currentName: string | null;
+};
+
 type DeviceDetail = {
   device: DeviceSummary;
   snapshots: Snapshot[];
   adminConfig: AdminConfig | null;
+  parsedSummary: AppSummary[] | null;
 };
 
 type BatchResponse = {
@@ -157,10 +176,29 @@ const PLATFORM_LABELS = [
   { key: "gemini", label: "Gemini" },
 ] as const;
 
+function formatAppSummary(summary: AppSummary) {
+  const label =
+    PLATFORM_LABELS.find((platform) => platform.key === summary.app)?.label ??
+    summary.app;
+  if (summary.status === "missing") {
+    return `${label}: 无`;
+  }
+  if (summary.status === "unparseable") {
+    return `${label}: 无法解析`;
+  }
+  const current = summary.currentName ?? summary.currentId;
+  return current
+    ? `${label}: ${summary.providerCount} 个供应商（当前: ${current}）`
+    : `${label}: ${summary.providerCount} 个供应商`;
+}
+
 export default function App() {
   const [tokenInput, setTokenInput] = useState("");
   const [token, setToken] = useState("");
   const [devices, setDevices] = useState<DeviceSummary[]>([]);
+  const [deviceTotal, setDeviceTotal] = useState(0);
+  const [hasMore, setHasMore] = useState(false);
+  const [limit, setLimit] = useState(DEVICE_PAGE_SIZE);
   const [selectedId, setSelectedId] = useState<string | null>(null);
   const [selectedSet, setSelectedSet] = useState<Set<string>>(new Set());
   const [detail, setDetail] = useState<DeviceDetail | null>(null);
@@ -177,18 +215,26 @@ export default function App() {
   }, []);
 
   useEffect(() => {
-    void refreshDevices();
-  }, [token]);
+    // 搜索在服务端进行，输入停顿后再请求
+    const timer = setTimeout(() => void refreshDevices(), 300);
+    return () => clearTimeout(timer);
+  }, [token, search, limit]);
 
   async function refreshDevices() {
     try {
       setLoading(true);
       setError(null);
-      const data = await apiFetch<{ devices: DeviceSummary[] }>(
-        "/api/v1/admin/devices",
+      const params = new URLSearchParams({ limit: String(limit) });
+      if (search.trim().length >= MIN_SEARCH_LENGTH) {
+        params.set("q", search.trim());
+      }
+      const data = await apiFetch<DeviceListResponse>(
+        `/api/v1/admin/devices?${params}`,
         token,
       );
       setDevices(data.devices);
+      setDeviceTotal(data.total);
+      setHasMore(data.hasMore);
       if (data.devices.length > 0 && !selectedId) {
         setSelectedId(data.devices[0].deviceId);
       }
@@ -215,25 +261,6 @@ export default function App() {
     })();
   }, [selectedId, token]);
 
-  const filteredDevices = useMemo(() => {
-    const keyword = search.trim().toLowerCase();
-    if (!keyword) return devices;
-    return devices.filter((device) => {
-      const haystack = [
-        device.deviceId,
-        device.lastIp,
-        device.geoCountry,
-        device.geoRegion,
-        device.geoCity,
-        device.appVersion,
-      ]
-        .filter(Boolean)
-        .join(" ")
-        .toLowerCase();
-      return haystack.includes(keyword);
-    });
-  }, [devices, search]);
-
   function toggleSelect(deviceId: string) {
     setSelectedSet((prev) => {
       const next = new Set(prev);
@@ -448,8 +475,11 @@ export default function App() {
           <div className="search-row">
             <input
               value={search}
-              onChange={(event) => setSearch(event.target.value)}
-              placeholder="搜索设备 ID / IP / 地区 / 版本"
+              onChange={(event) => {
+                setSearch(event.target.value);
+                setLimit(DEVICE_PAGE_SIZE);
+              }}
+              placeholder="设备 ID / 指纹前缀（至少 4 个字符）"
             />
             <button className="action-btn secondary" onClick={refreshDevices}>
               {loading ? "刷新中..." : "刷新"}
@@ -464,10 +494,13 @@ export default function App() {
               {selectedSet.size === devices.length ? "取消全选" : "全选"}
             </button>
             <span className="helper">已选 {selectedSet.size} 台</span>
+            <span className="helper">
+              显示 {devices.length} / {deviceTotal} 台
+            </span>
           </div>
 
           <div className="list">
-            {filteredDevices.map((device) => (
+            {devices.map((device) => (
               <div
                 key={device.deviceId}
                 className={
@@ -495,6 +528,14 @@ export default function App() {
               </div>
             ))}
           </div>
+          {hasMore ? (
+            <button
+              className="action-btn secondary"
+              onClick={() => setLimit((prev) => prev + DEVICE_PAGE_SIZE)}
+            >
+              {loading ? "加载中..." : "加载更多"}
+            </button>
+          ) : null}
         </section>
 
         <section className="panel">
@@ -547,6 +588,14 @@ export default function App() {
                 <span>下发更新时间</span>
                 <strong>{formatDate(detail.device.adminUpdatedAt)}</strong>
               </div>
+              <div className="detail-row">
+                <span>供应商概览</span>
+                <strong>
+                  {detail.parsedSummary
+                    ? detail.parsedSummary.map(formatAppSummary).join("，")
+                    : "-"}
+                </strong>
+              </div>
 
               {renderSnapshotSection(
                 "最新配置快照",
@@ -1,35 +1,409 @@
 use std::{env, fs, path::PathBuf};
 
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{Map, Value};
+
+#[allow(dead_code)]
+#[path = "src/services/management_secret_box.rs"]
+mod secret_box;
+
 fn main() {
     tauri_build::build();
     build_management_secrets();
 }
 
 fn build_management_secrets() {
+    // 未启用 management-sync 时不需要任何编译期密钥
+    if env::var_os("CARGO_FEATURE_MANAGEMENT_SYNC").is_none() {
+        return;
+    }
+
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_MANAGEMENT_CONFIG");
     println!("cargo:rerun-if-env-changed=AI_CODE_WITH_MANAGEMENT_URL");
     println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SYNC_TOKEN");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SYNC_TOKENS");
     println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SYNC_ON_START");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SYNC_SIGNING_SECRET");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_MANAGEMENT_PINNED_SPKI");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SNAPSHOT_PUBLIC_KEY");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_CONFIG_PUBLIC_KEY");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_MANAGEMENT_URL_STAGING");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SYNC_TOKEN_STAGING");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SYNC_TOKENS_STAGING");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_SYNC_SIGNING_SECRET_STAGING");
+    println!("cargo:rerun-if-env-changed=AI_CODE_WITH_ALLOW_INSECURE");
+
+    // 配置文件提供默认值，同名环境变量逐项覆盖
+    let config = ConfigFile::load();
+    let config = config.as_ref();
+    let setting = |var: &str, section: Section, key: &str| {
+        env_value(var).or_else(|| config.and_then(|config| config.string(section, key)))
+    };
+
+    let url = setting("AI_CODE_WITH_MANAGEMENT_URL", Section::Root, "url");
+    let token = sync_tokens("").or_else(|| config.and_then(|config| config.tokens(Section::Root)));
+    let (url, token) = match (url, token) {
+        (Some(url), Some(token)) => (url, token),
+        (url, token) => {
+            let from_file = |key: &str| match config {
+                Some(config) => format!(" (or \"{key}\" in {})", config.path.display()),
+                None => String::new(),
+            };
+            let mut missing = Vec::new();
+            if url.is_none() {
+                missing.push(format!(
+                    "  - AI_CODE_WITH_MANAGEMENT_URL{}",
+                    from_file("url")
+                ));
+            }
+            if token.is_none() {
+                missing.push(format!(
+                    "  - AI_CODE_WITH_SYNC_TOKEN (or AI_CODE_WITH_SYNC_TOKENS){}",
+                    from_file("token")
+                ));
+            }
+            panic!(
+                "the management-sync feature is enabled but these build-time variables are missing:\n{}\n\
+                 Set them (or point AI_CODE_WITH_MANAGEMENT_CONFIG at a JSON file that has them), \
+                 or build without management sync: cargo build --no-default-features",
+                missing.join("\n")
+            );
+        }
+    };
+    // 地址或令牌写错的构建在运行时每次同步都会失败，构建时就报出来
+    let allow_insecure = env_value("AI_CODE_WITH_ALLOW_INSECURE").is_some_and(|value| value == "1");
+    let url = normalize_url("AI_CODE_WITH_MANAGEMENT_URL", &url, allow_insecure);
+    check_tokens_value("AI_CODE_WITH_SYNC_TOKEN(S)", &token);
 
-    let url = env::var("AI_CODE_WITH_MANAGEMENT_URL")
-        .expect("AI_CODE_WITH_MANAGEMENT_URL is required at build time");
-    let token =
-        env::var("AI_CODE_WITH_SYNC_TOKEN").expect("AI_CODE_WITH_SYNC_TOKEN is required at build time");
-    let sync_on_start = env::var("AI_CODE_WITH_SYNC_ON_START")
-        .map(|value| value == "true" || value == "1")
-        .unwrap_or(false);
+    // 可选的预发布环境，运行时通过 CC_SWITCH_MANAGEMENT_ENV=staging 切换
+    let staging_url = setting(
+        "AI_CODE_WITH_MANAGEMENT_URL_STAGING",
+        Section::Staging,
+        "url",
+    )
+    .unwrap_or_default();
+    let staging_token = sync_tokens("_STAGING")
+        .or_else(|| config.and_then(|config| config.tokens(Section::Staging)))
+        .unwrap_or_default();
+    let staging_url = if staging_url.is_empty() {
+        staging_url
+    } else {
+        check_tokens_value("AI_CODE_WITH_SYNC_TOKEN(S)_STAGING", &staging_token);
+        normalize_url(
+            "AI_CODE_WITH_MANAGEMENT_URL_STAGING",
+            &staging_url,
+            allow_insecure,
+        )
+    };
+    let staging_signing_secret = setting(
+        "AI_CODE_WITH_SYNC_SIGNING_SECRET_STAGING",
+        Section::Staging,
+        "signingSecret",
+    )
+    .unwrap_or_default();
+    let sync_on_start = match env_value("AI_CODE_WITH_SYNC_ON_START") {
+        Some(value) => value == "true" || value == "1",
+        None => config.and_then(ConfigFile::sync_on_start).unwrap_or(false),
+    };
+    let signing_secret = setting(
+        "AI_CODE_WITH_SYNC_SIGNING_SECRET",
+        Section::Root,
+        "signingSecret",
+    )
+    .unwrap_or_default();
+    let pinned_spki = setting(
+        "AI_CODE_WITH_MANAGEMENT_PINNED_SPKI",
+        Section::Root,
+        "pinnedSpki",
+    )
+    .unwrap_or_default();
+    let snapshot_public_key = setting(
+        "AI_CODE_WITH_SNAPSHOT_PUBLIC_KEY",
+        Section::Root,
+        "snapshotPublicKey",
+    )
+    .unwrap_or_default();
+    let config_public_key = setting(
+        "AI_CODE_WITH_CONFIG_PUBLIC_KEY",
+        Section::Root,
+        "configPublicKey",
+    )
+    .unwrap_or_default();
 
-    let key: u8 = 0x5A;
-    let url_bytes: Vec<u8> = url.as_bytes().iter().map(|b| b ^ key).collect();
-    let token_bytes: Vec<u8> = token.as_bytes().iter().map(|b| b ^ key).collect();
+    // 每次构建生成新的随机密钥，拆成几份分散在各个密文之间
+    let rng = SystemRandom::new();
+    let key: [u8; secret_box::KEY_LEN] = random(&rng);
+    let masks: Vec<[u8; secret_box::KEY_LEN]> =
+        (1..secret_box::KEY_SHARES).map(|_| random(&rng)).collect();
+    let shares = secret_box::split_key(&key, &masks);
+
+    let secrets = [
+        ("MANAGEMENT_URL_BYTES", url.as_str()),
+        ("MANAGEMENT_TOKEN_BYTES", token.as_str()),
+        ("MANAGEMENT_SIGNING_SECRET_BYTES", signing_secret.as_str()),
+        ("MANAGEMENT_PINNED_SPKI_BYTES", pinned_spki.as_str()),
+        ("MANAGEMENT_SNAPSHOT_PUBLIC_KEY_BYTES", snapshot_public_key.as_str()),
+        ("MANAGEMENT_CONFIG_PUBLIC_KEY_BYTES", config_public_key.as_str()),
+        ("MANAGEMENT_STAGING_URL_BYTES", staging_url.as_str()),
+        ("MANAGEMENT_STAGING_TOKEN_BYTES", staging_token.as_str()),
+        ("MANAGEMENT_STAGING_SIGNING_SECRET_BYTES", staging_signing_secret.as_str()),
+    ];
+    let mut contents = String::new();
+    let mut shares = shares.iter().enumerate();
+    for (index, (name, value)) in secrets.iter().enumerate() {
+        let sealed = secret_box::seal(&key, random(&rng), name, value.as_bytes());
+        contents.push_str(&format!("pub const {name}: &[u8] = &{sealed:?};\n"));
+        if index % 2 == 0 {
+            if let Some((share_index, share)) = shares.next() {
+                contents.push_str(&format!(
+                    "pub const MANAGEMENT_KEY_SHARE_{share_index}: [u8; {}] = {share:?};\n",
+                    secret_box::KEY_LEN
+                ));
+            }
+        }
+    }
+    for (share_index, share) in shares {
+        contents.push_str(&format!(
+            "pub const MANAGEMENT_KEY_SHARE_{share_index}: [u8; {}] = {share:?};\n",
+            secret_box::KEY_LEN
+        ));
+    }
+    contents.push_str(&format!("pub const SYNC_ON_START: bool = {sync_on_start};\n"));
+    contents.push_str(&format!(
+        "pub const ALLOW_INSECURE: bool = {allow_insecure};\n"
+    ));
 
     let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
     let dest = out_dir.join("management_secrets.rs");
-    let contents = format!(
-        "pub const MANAGEMENT_XOR_KEY: u8 = {key};\n\
-pub const MANAGEMENT_URL_BYTES: &[u8] = &{url_bytes:?};\n\
-pub const MANAGEMENT_TOKEN_BYTES: &[u8] = &{token_bytes:?};\n\
-pub const SYNC_ON_START: bool = {sync_on_start};\n"
-    );
-
     fs::write(dest, contents).expect("failed to write management secrets");
 }
+
+/// 轮换令牌时以逗号分隔列出当前令牌与旧令牌（当前在前）；未设置时使用单个令牌
+fn sync_tokens(suffix: &str) -> Option<String> {
+    let tokens = env_value(&format!("AI_CODE_WITH_SYNC_TOKENS{suffix}"))
+        .or_else(|| env_value(&format!("AI_CODE_WITH_SYNC_TOKEN{suffix}")))?;
+    join_tokens(tokens.split(','))
+}
+
+fn join_tokens<'a>(tokens: impl Iterator<Item = &'a str>) -> Option<String> {
+    let tokens = tokens
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>();
+    (!tokens.is_empty()).then(|| tokens.join(","))
+}
+
+/// 令牌会放进请求头，单个令牌不超过该长度
+const MAX_TOKEN_LEN: usize = 512;
+
+/// 去掉首尾空白与末尾的 `/` 后校验地址：必须是 https，
+/// 只有 `AI_CODE_WITH_ALLOW_INSECURE=1` 时才接受 http
+fn normalize_url(var: &str, value: &str, allow_insecure: bool) -> String {
+    let normalized = value.trim().trim_end_matches('/');
+    let fail = |reason: &str| -> ! {
+        panic!("{var} is not a usable management URL ({value:?}): {reason}")
+    };
+    let parsed = match url::Url::parse(normalized) {
+        Ok(parsed) => parsed,
+        Err(url::ParseError::RelativeUrlWithoutBase) => fail("missing the https:// scheme"),
+        Err(err) => fail(&err.to_string()),
+    };
+    match parsed.scheme() {
+        "https" => {}
+        "http" if allow_insecure => {
+            println!("cargo:warning={var} uses plain http; the sync token is sent unencrypted");
+        }
+        "http" => fail("plain http is only allowed with AI_CODE_WITH_ALLOW_INSECURE=1"),
+        _ => fail("the scheme must be https"),
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        fail("the URL has no host");
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        fail("use the server base address without a query or fragment");
+    }
+    normalized.to_string()
+}
+
+/// 逗号分隔的令牌列表里每个令牌都应是可放进请求头的可见 ASCII 字符
+fn check_tokens_value(var: &str, tokens: &str) {
+    if tokens.is_empty() {
+        panic!("{var} is empty; set the sync token for this environment");
+    }
+    for (index, token) in tokens.split(',').enumerate() {
+        if token.len() > MAX_TOKEN_LEN {
+            panic!(
+                "{var}: token #{} is {} characters long (at most {MAX_TOKEN_LEN} allowed)",
+                index + 1,
+                token.len()
+            );
+        }
+        if !token.bytes().all(|byte| byte.is_ascii_graphic()) {
+            panic!(
+                "{var}: token #{} contains whitespace or non-ASCII characters",
+                index + 1
+            );
+        }
+    }
+}
+
+/// 设置且非空的环境变量；空值视为未设置，不会覆盖配置文件
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Root,
+    Staging,
+}
+
+const ROOT_KEYS: &[&str] = &[
+    "url",
+    "token",
+    "tokens",
+    "syncOnStart",
+    "signingSecret",
+    "pinnedSpki",
+    "snapshotPublicKey",
+    "configPublicKey",
+    "staging",
+];
+const STAGING_KEYS: &[&str] = &["url", "token", "tokens", "signingSecret"];
+
+/// `AI_CODE_WITH_MANAGEMENT_CONFIG` 指向的 JSON 配置文件，例如：
+///
+/// ```json
+/// {
+///   "url": "https://manage.example.com",
+///   "tokens": ["current", "previous"],
+///   "syncOnStart": false,
+///   "configPublicKey": "...",
+///   "staging": { "url": "https://staging.example.com", "token": "..." }
+/// }
+/// ```
+///
+/// 读取时即校验字段名与类型，拼错的字段名会直接报错而不是被静默忽略。
+struct ConfigFile {
+    path: PathBuf,
+    root: Map<String, Value>,
+}
+
+impl ConfigFile {
+    fn load() -> Option<Self> {
+        let path = PathBuf::from(env_value("AI_CODE_WITH_MANAGEMENT_CONFIG")?);
+        println!("cargo:rerun-if-changed={}", path.display());
+        let label = path.display().to_string();
+        let fail =
+            |message: String| -> ! { panic!("AI_CODE_WITH_MANAGEMENT_CONFIG={label}: {message}") };
+        let text = fs::read_to_string(&path)
+            .unwrap_or_else(|err| fail(format!("cannot read the file: {err}")));
+        let root = match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Object(root)) => root,
+            Ok(_) => fail("expected a JSON object at the top level".to_string()),
+            Err(err) => fail(format!("invalid JSON: {err}")),
+        };
+        let config = Self { path, root };
+        if let Err(message) = config.validate() {
+            fail(message);
+        }
+        Some(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check_keys(&self.root, ROOT_KEYS, "")?;
+        for key in [
+            "url",
+            "signingSecret",
+            "pinnedSpki",
+            "snapshotPublicKey",
+            "configPublicKey",
+        ] {
+            check_string(&self.root, key, "")?;
+        }
+        check_tokens(&self.root, "")?;
+        match self.root.get("syncOnStart") {
+            None | Some(Value::Bool(_)) => {}
+            Some(_) => return Err("\"syncOnStart\" must be true or false".to_string()),
+        }
+        match self.root.get("staging") {
+            None => {}
+            Some(Value::Object(staging)) => {
+                check_keys(staging, STAGING_KEYS, "staging.")?;
+                check_string(staging, "url", "staging.")?;
+                check_string(staging, "signingSecret", "staging.")?;
+                check_tokens(staging, "staging.")?;
+            }
+            Some(_) => return Err("\"staging\" must be an object".to_string()),
+        }
+        Ok(())
+    }
+
+    fn section(&self, section: Section) -> Option<&Map<String, Value>> {
+        match section {
+            Section::Root => Some(&self.root),
+            Section::Staging => self.root.get("staging").and_then(Value::as_object),
+        }
+    }
+
+    fn string(&self, section: Section, key: &str) -> Option<String> {
+        self.section(section)?
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    }
+
+    /// `tokens` 数组优先，其次是单个 `token`，与环境变量的规则一致
+    fn tokens(&self, section: Section) -> Option<String> {
+        let section = self.section(section)?;
+        let list = section
+            .get("tokens")
+            .and_then(Value::as_array)
+            .and_then(|tokens| join_tokens(tokens.iter().filter_map(Value::as_str)));
+        list.or_else(|| {
+            section
+                .get("token")
+                .and_then(Value::as_str)
+                .and_then(|token| join_tokens(token.split(',')))
+        })
+    }
+
+    fn sync_on_start(&self) -> Option<bool> {
+        self.root.get("syncOnStart").and_then(Value::as_bool)
+    }
+}
+
+fn check_keys(map: &Map<String, Value>, allowed: &[&str], prefix: &str) -> Result<(), String> {
+    match map.keys().find(|key| !allowed.contains(&key.as_str())) {
+        Some(key) => Err(format!(
+            "unknown field \"{prefix}{key}\" (expected one of: {})",
+            allowed.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+fn check_string(map: &Map<String, Value>, key: &str, prefix: &str) -> Result<(), String> {
+    match map.get(key) {
+        None | Some(Value::String(_)) => Ok(()),
+        Some(_) => Err(format!("\"{prefix}{key}\" must be a string")),
+    }
+}
+
+fn check_tokens(map: &Map<String, Value>, prefix: &str) -> Result<(), String> {
+    check_string(map, "token", prefix)?;
+    match map.get("tokens") {
+        None => Ok(()),
+        Some(Value::Array(tokens)) if tokens.iter().all(Value::is_string) => Ok(()),
+        Some(_) => Err(format!("\"{prefix}tokens\" must be an array of strings")),
+    }
+}
+
+fn random<const N: usize>(rng: &SystemRandom) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rng.fill(&mut bytes)
+        .expect("failed to generate random bytes for management secrets");
+    bytes
+}
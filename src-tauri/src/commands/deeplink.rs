@@ -2,6 +2,7 @@ use crate::deeplink::{
     import_mcp_from_deeplink, import_prompt_from_deeplink, import_provider_from_deeplink,
     import_skill_from_deeplink, parse_deeplink_url, DeepLinkImportRequest,
 };
+use crate::services::ManagementSyncService;
 use crate::store::AppState;
 use tauri::State;
 
@@ -35,6 +36,7 @@ pub fn import_from_deeplink(
     );
 
     let provider_id = import_provider_from_deeplink(&state, request).map_err(|e| e.to_string())?;
+    ManagementSyncService::schedule_push_sync();
 
     log::info!("Successfully imported provider with ID: {provider_id}");
 
@@ -53,6 +55,7 @@ pub async fn import_from_deeplink_unified(
         "provider" => {
             let provider_id =
                 import_provider_from_deeplink(&state, request).map_err(|e| e.to_string())?;
+            ManagementSyncService::schedule_push_sync();
             Ok(serde_json::json!({
                 "type": "provider",
                 "id": provider_id
@@ -0,0 +1,639 @@
+//! 管理同步命令
+
+use std::path::Path;
+use std::str::FromStr;
+
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+use crate::app_config::AppType;
+use crate::services::management_diagnostics;
+use crate::services::management_privacy::SnapshotPrivacy;
+use crate::services::management_schedule::{ManagementSyncSchedule, SyncPauseWindow};
+use crate::services::management_sync::{
+    AdminApplyReport, AdminConfigPreview, ApplyConfirmation, ApplyMode, ConfigBackupSummary,
+    ConflictPolicy, ConnectionTestResult, ManagementInfo, ManagementProxySettings,
+    ManagementSyncStatus, ManualSyncResult, PendingAdminConfig, ServerTimeOffset, SyncHistoryEntry,
+    SyncMetrics, SyncProfileInfo,
+};
+use crate::services::management_tls::ManagementTlsSettings;
+use crate::services::ManagementSyncService;
+use crate::store::AppState;
+
+/// 未编译管理同步时直接拒绝，避免命令读写任何管理同步相关的设置
+fn ensure_included() -> Result<(), String> {
+    if cfg!(feature = "management-sync") {
+        Ok(())
+    } else {
+        Err("Management sync is not included in this build".to_string())
+    }
+}
+
+/// 获取管理同步状态
+#[tauri::command]
+pub async fn get_management_sync_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<ManagementSyncStatus, String> {
+    ensure_included()?;
+    ManagementSyncService::status(&state).map_err(|e| e.to_string())
+}
+
+/// 获取管理同步计划
+#[tauri::command]
+pub async fn get_management_sync_schedule(
+    state: tauri::State<'_, AppState>,
+) -> Result<ManagementSyncSchedule, String> {
+    ensure_included()?;
+    ManagementSyncService::schedule(&state).map_err(|e| e.to_string())
+}
+
+/// 更新管理同步计划
+#[tauri::command]
+pub async fn set_management_sync_schedule(
+    state: tauri::State<'_, AppState>,
+    schedule: ManagementSyncSchedule,
+) -> Result<ManagementSyncSchedule, String> {
+    ensure_included()?;
+    ManagementSyncService::set_schedule(&state, schedule).map_err(|e| e.to_string())
+}
+
+/// 获取暂停同步的时段
+#[tauri::command]
+pub async fn get_management_pause_window(
+    state: tauri::State<'_, AppState>,
+) -> Result<SyncPauseWindow, String> {
+    ensure_included()?;
+    ManagementSyncService::pause_window(&state).map_err(|e| e.to_string())
+}
+
+/// 更新暂停同步的时段
+#[tauri::command]
+pub async fn set_management_pause_window(
+    state: tauri::State<'_, AppState>,
+    window: SyncPauseWindow,
+) -> Result<SyncPauseWindow, String> {
+    ensure_included()?;
+    ManagementSyncService::set_pause_window(&state, window).map_err(|e| e.to_string())
+}
+
+/// 列出应用管理员配置前的本地备份
+#[tauri::command]
+pub async fn list_config_backups(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ConfigBackupSummary>, String> {
+    ensure_included()?;
+    ManagementSyncService::list_backups(&state).map_err(|e| e.to_string())
+}
+
+/// 从本地备份恢复供应商
+#[tauri::command]
+pub async fn restore_config_backup(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::restore_backup(&state, id).map_err(|e| e.to_string())
+}
+
+/// 按同步快照格式导出本地供应商配置
+#[tauri::command]
+pub async fn export_provider_config(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::export_provider_config(&state, Path::new(&path))
+        .map_err(|e| e.to_string())
+}
+
+/// 从导出的快照文件导入供应商配置（替换或合并）
+#[tauri::command]
+pub async fn import_provider_config(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    mode: ApplyMode,
+) -> Result<AdminApplyReport, String> {
+    ensure_included()?;
+    let report = ManagementSyncService::import_provider_config(&state, Path::new(&path), mode)
+        .map_err(|e| e.to_string())?;
+    ManagementSyncService::schedule_push_sync();
+    Ok(report)
+}
+
+/// 获取管理员配置默认的替换 / 合并方式
+#[tauri::command]
+pub async fn get_management_merge_mode(
+    state: tauri::State<'_, AppState>,
+) -> Result<ApplyMode, String> {
+    ensure_included()?;
+    ManagementSyncService::merge_mode(&state).map_err(|e| e.to_string())
+}
+
+/// 更新管理员配置默认的替换 / 合并方式
+#[tauri::command]
+pub async fn set_management_merge_mode(
+    state: tauri::State<'_, AppState>,
+    mode: ApplyMode,
+) -> Result<ApplyMode, String> {
+    ensure_included()?;
+    ManagementSyncService::set_merge_mode(&state, mode).map_err(|e| e.to_string())
+}
+
+/// 获取本地修改冲突的处理方式
+#[tauri::command]
+pub async fn get_management_conflict_policy(
+    state: tauri::State<'_, AppState>,
+) -> Result<ConflictPolicy, String> {
+    ensure_included()?;
+    ManagementSyncService::conflict_policy(&state).map_err(|e| e.to_string())
+}
+
+/// 更新本地修改冲突的处理方式
+#[tauri::command]
+pub async fn set_management_conflict_policy(
+    state: tauri::State<'_, AppState>,
+    policy: ConflictPolicy,
+) -> Result<ConflictPolicy, String> {
+    ensure_included()?;
+    ManagementSyncService::set_conflict_policy(&state, policy).map_err(|e| e.to_string())
+}
+
+/// 放弃本地修改，下次同步应用管理员配置
+#[tauri::command]
+pub async fn resolve_management_conflict(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::resolve_conflict(&state).map_err(|e| e.to_string())
+}
+
+/// 获取管理同步的代理设置
+#[tauri::command]
+pub async fn get_management_proxy_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<ManagementProxySettings, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::proxy_settings(&state))
+}
+
+/// 更新管理同步的代理设置
+#[tauri::command]
+pub async fn set_management_proxy_settings(
+    state: tauri::State<'_, AppState>,
+    settings: ManagementProxySettings,
+) -> Result<ManagementProxySettings, String> {
+    ensure_included()?;
+    ManagementSyncService::set_proxy_settings(&state, settings).map_err(|e| e.to_string())
+}
+
+/// 立即同步一次（“立即同步”按钮）
+#[tauri::command]
+pub async fn sync_management_now(app: tauri::AppHandle) -> Result<ManualSyncResult, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::sync_now(&app).await)
+}
+
+/// 通过当前代理设置测试与管理服务器的连接
+#[tauri::command]
+pub async fn test_management_connection(
+    state: tauri::State<'_, AppState>,
+) -> Result<ConnectionTestResult, String> {
+    ensure_included()?;
+    ManagementSyncService::test_connection(&state)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取管理同步的 TLS 设置
+#[tauri::command]
+pub async fn get_management_tls_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<ManagementTlsSettings, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::tls_settings(&state))
+}
+
+/// 更新管理同步的 TLS 设置（额外根证书或证书固定）
+#[tauri::command]
+pub async fn set_management_tls_settings(
+    state: tauri::State<'_, AppState>,
+    settings: ManagementTlsSettings,
+) -> Result<ManagementTlsSettings, String> {
+    ensure_included()?;
+    ManagementSyncService::set_tls_settings(&state, settings).map_err(|e| e.to_string())
+}
+
+/// 获取快照上传的隐私级别
+#[tauri::command]
+pub async fn get_management_snapshot_privacy(
+    state: tauri::State<'_, AppState>,
+) -> Result<SnapshotPrivacy, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::snapshot_privacy(&state))
+}
+
+/// 更新快照上传的隐私级别
+#[tauri::command]
+pub async fn set_management_snapshot_privacy(
+    state: tauri::State<'_, AppState>,
+    privacy: SnapshotPrivacy,
+) -> Result<SnapshotPrivacy, String> {
+    ensure_included()?;
+    ManagementSyncService::set_snapshot_privacy(&state, privacy).map_err(|e| e.to_string())
+}
+
+/// 获取管理同步是否开启
+#[tauri::command]
+pub async fn get_management_sync_enabled(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::enabled(&state))
+}
+
+/// 开启或关闭管理同步
+#[tauri::command]
+pub async fn set_management_sync_enabled(
+    app: tauri::AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_enabled(&app, enabled).map_err(|e| e.to_string())
+}
+
+/// 列出同步配置档（0 号为默认配置档）
+#[tauri::command]
+pub async fn list_sync_profiles(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SyncProfileInfo>, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::profiles(&state))
+}
+
+/// 新建同步配置档；地址与令牌留空时使用构建时编入的值，新配置档默认关闭
+#[tauri::command]
+pub async fn create_sync_profile(
+    state: tauri::State<'_, AppState>,
+    name: String,
+    url: Option<String>,
+    token: Option<String>,
+) -> Result<SyncProfileInfo, String> {
+    ensure_included()?;
+    ManagementSyncService::create_profile(&state, &name, url, token).map_err(|e| e.to_string())
+}
+
+/// 开启或关闭一个同步配置档
+#[tauri::command]
+pub async fn set_sync_profile_enabled(
+    app: tauri::AppHandle,
+    id: u32,
+    enabled: bool,
+) -> Result<SyncProfileInfo, String> {
+    ensure_included()?;
+    ManagementSyncService::set_profile_enabled(&app, id, enabled).map_err(|e| e.to_string())
+}
+
+/// 获取一个同步配置档的同步状态
+#[tauri::command]
+pub async fn get_sync_profile_status(
+    state: tauri::State<'_, AppState>,
+    id: u32,
+) -> Result<ManagementSyncStatus, String> {
+    ensure_included()?;
+    ManagementSyncService::profile_status(&state, id).map_err(|e| e.to_string())
+}
+
+/// 获取设置中的管理服务器覆盖地址
+#[tauri::command]
+pub async fn get_management_url_override(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::url_override(&state))
+}
+
+/// 设置管理服务器覆盖地址，传空则恢复构建时地址
+#[tauri::command]
+pub async fn set_management_url_override(
+    state: tauri::State<'_, AppState>,
+    url: Option<String>,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_url_override(&state, url).map_err(|e| e.to_string())
+}
+
+/// 获取主地址不可达时依次尝试的备用服务器地址
+#[tauri::command]
+pub async fn get_management_fallback_urls(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::fallback_urls(&state))
+}
+
+/// 设置备用服务器地址，返回去重后的列表
+#[tauri::command]
+pub async fn set_management_fallback_urls(
+    state: tauri::State<'_, AppState>,
+    urls: Vec<String>,
+) -> Result<Vec<String>, String> {
+    ensure_included()?;
+    ManagementSyncService::set_fallback_urls(&state, urls).map_err(|e| e.to_string())
+}
+
+/// 获取最近的同步记录（最新在前），默认 20 条
+#[tauri::command]
+pub async fn get_sync_history(
+    state: tauri::State<'_, AppState>,
+    limit: Option<u32>,
+) -> Result<Vec<SyncHistoryEntry>, String> {
+    ensure_included()?;
+    ManagementSyncService::sync_history(&state, limit.unwrap_or(20) as usize)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取最近几次同步的分阶段耗时（最新在前），默认 20 条
+#[tauri::command]
+pub async fn get_sync_metrics(
+    state: tauri::State<'_, AppState>,
+    limit: Option<u32>,
+) -> Result<Vec<SyncMetrics>, String> {
+    ensure_included()?;
+    ManagementSyncService::sync_metrics(&state, limit.unwrap_or(20) as usize)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取当前设备 ID
+#[tauri::command]
+pub async fn get_device_id(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    ensure_included()?;
+    ManagementSyncService::device_id(&state).map_err(|e| e.to_string())
+}
+
+/// 服务器下发的功能开关；未下发时返回 null
+#[tauri::command]
+pub async fn get_feature_flag(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> Result<Option<serde_json::Value>, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::feature_flag(&state, &name))
+}
+
+/// 获取设备 ID 与登记信息（不含令牌）
+#[tauri::command]
+pub async fn get_management_info(
+    state: tauri::State<'_, AppState>,
+) -> Result<ManagementInfo, String> {
+    ensure_included()?;
+    ManagementSyncService::info(&state).map_err(|e| e.to_string())
+}
+
+/// 获取与服务器的时钟偏差及最近一次联系服务器的时间与地址
+#[tauri::command]
+pub async fn get_server_time_offset(
+    state: tauri::State<'_, AppState>,
+) -> Result<ServerTimeOffset, String> {
+    ensure_included()?;
+    ManagementSyncService::server_time_offset(&state).map_err(|e| e.to_string())
+}
+
+/// 重新生成设备 ID 并立即同步，需显式传入 `confirm: true`
+#[tauri::command]
+pub async fn regenerate_device_id(app: tauri::AppHandle, confirm: bool) -> Result<String, String> {
+    ensure_included()?;
+    ManagementSyncService::regenerate_device_id(&app, confirm)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 同步时是否上报主机名
+#[tauri::command]
+pub async fn get_management_report_hostname(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::report_hostname(&state))
+}
+
+#[tauri::command]
+pub async fn set_management_report_hostname(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_report_hostname(&state, enabled).map_err(|e| e.to_string())
+}
+
+/// 计费网络上是否仍上传完整快照
+#[tauri::command]
+pub async fn get_management_full_sync_on_metered(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::full_sync_on_metered(&state))
+}
+
+#[tauri::command]
+pub async fn set_management_full_sync_on_metered(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_full_sync_on_metered(&state, enabled).map_err(|e| e.to_string())
+}
+
+/// 是否允许管理员远程收集日志
+#[tauri::command]
+pub async fn get_management_log_upload_allowed(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::log_upload_allowed(&state))
+}
+
+#[tauri::command]
+pub async fn set_management_log_upload_allowed(
+    state: tauri::State<'_, AppState>,
+    allowed: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_log_upload_allowed(&state, allowed).map_err(|e| e.to_string())
+}
+
+/// 应用管理员配置后是否弹出系统通知
+#[tauri::command]
+pub async fn get_management_apply_notification(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::apply_notification(&state))
+}
+
+#[tauri::command]
+pub async fn set_management_apply_notification(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_apply_notification(&state, enabled).map_err(|e| e.to_string())
+}
+
+/// 收到新的管理员配置时是否需要用户确认
+#[tauri::command]
+pub async fn get_management_apply_confirmation(
+    state: tauri::State<'_, AppState>,
+) -> Result<ApplyConfirmation, String> {
+    ensure_included()?;
+    ManagementSyncService::apply_confirmation(&state).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_management_apply_confirmation(
+    state: tauri::State<'_, AppState>,
+    confirmation: ApplyConfirmation,
+) -> Result<ApplyConfirmation, String> {
+    ensure_included()?;
+    ManagementSyncService::set_apply_confirmation(&state, confirmation).map_err(|e| e.to_string())
+}
+
+/// 等待用户确认的管理员配置
+#[tauri::command]
+pub async fn get_pending_admin_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<PendingAdminConfig>, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::pending_admin_config(&state))
+}
+
+/// 批准并应用待确认的管理员配置
+#[tauri::command]
+pub async fn approve_pending_admin_config(app: tauri::AppHandle) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::approve_pending_admin_config(&app)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 拒绝待确认的管理员配置，下次同步时上报
+#[tauri::command]
+pub async fn reject_pending_admin_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::reject_pending_admin_config(&state).map_err(|e| e.to_string())
+}
+
+/// 预览管理员配置会带来的改动，不修改本地状态
+#[tauri::command]
+pub async fn preview_admin_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<AdminConfigPreview, String> {
+    ensure_included()?;
+    ManagementSyncService::preview_admin_config(&state).map_err(|e| e.to_string())
+}
+
+/// 固定的供应商不会被管理员配置覆盖或删除
+#[tauri::command]
+pub async fn get_pinned_providers(
+    state: tauri::State<'_, AppState>,
+    app: String,
+) -> Result<Vec<String>, String> {
+    ensure_included()?;
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    Ok(ManagementSyncService::pinned_providers(&state, &app_type))
+}
+
+#[tauri::command]
+pub async fn set_provider_pinned(
+    state: tauri::State<'_, AppState>,
+    app: String,
+    id: String,
+    pinned: bool,
+) -> Result<Vec<String>, String> {
+    ensure_included()?;
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ManagementSyncService::set_provider_pinned(&state, &app_type, &id, pinned)
+        .map_err(|e| e.to_string())
+}
+
+/// 是否拒绝应用未签名的管理员配置
+#[tauri::command]
+pub async fn get_management_require_signed_configs(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::require_signed_configs(&state))
+}
+
+#[tauri::command]
+pub async fn set_management_require_signed_configs(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_require_signed_configs(&state, enabled).map_err(|e| e.to_string())
+}
+
+/// 上传快照时是否去掉固定的供应商
+#[tauri::command]
+pub async fn get_management_exclude_pinned(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::exclude_pinned_from_snapshot(&state))
+}
+
+#[tauri::command]
+pub async fn set_management_exclude_pinned(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_exclude_pinned_from_snapshot(&state, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// 上传快照时是否带上 MCP 服务器与通用配置片段
+#[tauri::command]
+pub async fn get_management_include_extras(
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    ensure_included()?;
+    Ok(ManagementSyncService::include_extras_in_snapshot(&state))
+}
+
+#[tauri::command]
+pub async fn set_management_include_extras(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_included()?;
+    ManagementSyncService::set_include_extras_in_snapshot(&state, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// 管理同步诊断日志的路径，便于附在问题反馈里
+#[tauri::command]
+pub async fn get_sync_log_path() -> Result<String, String> {
+    ensure_included()?;
+    Ok(management_diagnostics::sync_log_path()
+        .to_string_lossy()
+        .to_string())
+}
+
+/// 在文件管理器中显示管理同步诊断日志；还没有日志时先创建空文件
+#[tauri::command]
+pub async fn reveal_sync_log(handle: AppHandle) -> Result<bool, String> {
+    ensure_included()?;
+    let path = management_diagnostics::sync_log_path();
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {e}"))?;
+        }
+        std::fs::File::create(&path).map_err(|e| format!("创建日志文件失败: {e}"))?;
+    }
+
+    handle
+        .opener()
+        .reveal_item_in_dir(&path)
+        .map_err(|e| format!("打开文件夹失败: {e}"))?;
+
+    Ok(true)
+}
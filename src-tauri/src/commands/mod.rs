@@ -5,6 +5,7 @@ mod deeplink;
 mod env;
 mod failover;
 mod import_export;
+mod management;
 mod mcp;
 mod misc;
 mod plugin;
@@ -21,6 +22,7 @@ pub use deeplink::*;
 pub use env::*;
 pub use failover::*;
 pub use import_export::*;
+pub use management::*;
 pub use mcp::*;
 pub use misc::*;
 pub use plugin::*;
@@ -4,7 +4,9 @@ use tauri::State;
 use crate::app_config::AppType;
 use crate::error::AppError;
 use crate::provider::Provider;
-use crate::services::{EndpointLatency, ProviderService, ProviderSortUpdate, SpeedtestService};
+use crate::services::{
+    EndpointLatency, ManagementSyncService, ProviderService, ProviderSortUpdate, SpeedtestService,
+};
 use crate::store::AppState;
 use std::str::FromStr;
 
@@ -33,7 +35,10 @@ pub fn add_provider(
     provider: Provider,
 ) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::add(state.inner(), app_type, provider).map_err(|e| e.to_string())
+    let added =
+        ProviderService::add(state.inner(), app_type, provider).map_err(|e| e.to_string())?;
+    ManagementSyncService::schedule_push_sync();
+    Ok(added)
 }
 
 /// 更新供应商
@@ -44,7 +49,10 @@ pub fn update_provider(
     provider: Provider,
 ) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::update(state.inner(), app_type, provider).map_err(|e| e.to_string())
+    let updated =
+        ProviderService::update(state.inner(), app_type, provider).map_err(|e| e.to_string())?;
+    ManagementSyncService::schedule_push_sync();
+    Ok(updated)
 }
 
 /// 删除供应商
@@ -55,9 +63,9 @@ pub fn delete_provider(
     id: String,
 ) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::delete(state.inner(), app_type, &id)
-        .map(|_| true)
-        .map_err(|e| e.to_string())
+    ProviderService::delete(state.inner(), app_type, &id).map_err(|e| e.to_string())?;
+    ManagementSyncService::schedule_push_sync();
+    Ok(true)
 }
 
 /// 切换供应商
@@ -81,9 +89,9 @@ pub fn switch_provider(
     id: String,
 ) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    switch_provider_internal(&state, app_type, &id)
-        .map(|_| true)
-        .map_err(|e| e.to_string())
+    switch_provider_internal(&state, app_type, &id).map_err(|e| e.to_string())?;
+    ManagementSyncService::schedule_push_sync();
+    Ok(true)
 }
 
 fn import_default_config_internal(state: &AppState, app_type: AppType) -> Result<bool, AppError> {
@@ -102,7 +110,11 @@ pub fn import_default_config_test_hook(
 #[tauri::command]
 pub fn import_default_config(state: State<'_, AppState>, app: String) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    import_default_config_internal(&state, app_type).map_err(Into::into)
+    let imported = import_default_config_internal(&state, app_type).map_err(String::from)?;
+    if imported {
+        ManagementSyncService::schedule_push_sync();
+    }
+    Ok(imported)
 }
 
 /// 查询供应商用量
@@ -0,0 +1,278 @@
+//! 管理同步数据访问对象
+//!
+//! - 离线队列：无法连接管理服务器时暂存已序列化的同步请求，恢复连接后按入队顺序补发
+//! - 配置备份：应用管理员配置前保存的本地供应商快照
+//! - 同步历史：每次同步尝试的结果，供诊断面板查看
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::{params, OptionalExtension};
+
+/// 离线队列中的一条同步请求
+#[derive(Debug, Clone)]
+pub struct QueuedManagementSync {
+    pub id: i64,
+    pub payload: String,
+    pub client_time: String,
+}
+
+/// 一条配置备份
+#[derive(Debug, Clone)]
+pub struct ConfigBackupRow {
+    pub id: i64,
+    pub created_at: String,
+    pub admin_version: Option<i64>,
+    pub previous_admin_version: Option<i64>,
+    pub snapshot: String,
+}
+
+/// 一次同步尝试的记录
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncHistoryRow {
+    pub id: i64,
+    pub started_at: String,
+    pub finished_at: String,
+    /// `success` / `failed` / `rate_limited` / `maintenance`
+    pub outcome: String,
+    /// 服务器本次下发的管理员配置版本
+    pub offered_admin_version: Option<i64>,
+    /// 同步结束时本地已应用的版本
+    pub applied_admin_version: Option<i64>,
+    pub error: Option<String>,
+    /// 同步请求体的字节数
+    pub payload_bytes: Option<i64>,
+    /// gzip 压缩后实际发送的字节数；未压缩时为空
+    pub compressed_bytes: Option<i64>,
+    /// 本次实际使用的服务器地址（可能是备用地址）
+    pub endpoint: Option<String>,
+    /// 错误类别（JSON），由服务层解析后返回给前端
+    #[serde(skip_serializing)]
+    pub error_code: Option<String>,
+    /// 各阶段耗时（毫秒）：收集快照、序列化、网络往返、应用管理员配置
+    pub collect_ms: Option<i64>,
+    pub serialize_ms: Option<i64>,
+    pub network_ms: Option<i64>,
+    pub apply_ms: Option<i64>,
+    /// 本次未上传变化的快照的原因，如 `metered`（计费网络只发心跳）
+    pub snapshot_skipped: Option<String>,
+}
+
+impl Database {
+    /// 入队一条同步请求；超过 `max_entries` 时删除最旧的条目，返回删除数量
+    pub fn enqueue_management_sync(
+        &self,
+        payload: &str,
+        client_time: &str,
+        max_entries: usize,
+    ) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO management_sync_queue (payload, client_time) VALUES (?1, ?2)",
+            params![payload, client_time],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let dropped = conn
+            .execute(
+                "DELETE FROM management_sync_queue WHERE id NOT IN (
+                    SELECT id FROM management_sync_queue ORDER BY id DESC LIMIT ?1
+                )",
+                params![max_entries as i64],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(dropped)
+    }
+
+    /// 按入队顺序（最旧在前）列出离线队列
+    pub fn list_management_sync_queue(&self) -> Result<Vec<QueuedManagementSync>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare("SELECT id, payload, client_time FROM management_sync_queue ORDER BY id ASC")
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(QueuedManagementSync {
+                    id: row.get(0)?,
+                    payload: row.get(1)?,
+                    client_time: row.get(2)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 删除离线队列中的一条
+    pub fn delete_management_sync_entry(&self, id: i64) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM management_sync_queue WHERE id = ?1",
+            params![id],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 保存一条配置备份，只保留最新的 `keep` 条，返回新备份 ID
+    pub fn insert_config_backup(
+        &self,
+        created_at: &str,
+        admin_version: Option<i64>,
+        previous_admin_version: Option<i64>,
+        snapshot: &str,
+        keep: usize,
+    ) -> Result<i64, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO config_backups (created_at, admin_version, previous_admin_version, snapshot)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![created_at, admin_version, previous_admin_version, snapshot],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        let id = conn.last_insert_rowid();
+
+        conn.execute(
+            "DELETE FROM config_backups WHERE id NOT IN (
+                SELECT id FROM config_backups ORDER BY id DESC LIMIT ?1
+            )",
+            params![keep as i64],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(id)
+    }
+
+    /// 列出配置备份（最新在前）
+    pub fn list_config_backups(&self) -> Result<Vec<ConfigBackupRow>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, created_at, admin_version, previous_admin_version, snapshot
+                 FROM config_backups ORDER BY id DESC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ConfigBackupRow {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    admin_version: row.get(2)?,
+                    previous_admin_version: row.get(3)?,
+                    snapshot: row.get(4)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 按 ID 获取配置备份
+    pub fn get_config_backup(&self, id: i64) -> Result<Option<ConfigBackupRow>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT id, created_at, admin_version, previous_admin_version, snapshot
+             FROM config_backups WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(ConfigBackupRow {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    admin_version: row.get(2)?,
+                    previous_admin_version: row.get(3)?,
+                    snapshot: row.get(4)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 记录一次同步尝试，只保留最新的 `keep` 条，返回新记录 ID
+    pub fn insert_sync_history(
+        &self,
+        entry: &SyncHistoryRow,
+        keep: usize,
+    ) -> Result<i64, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO sync_history (
+                started_at, finished_at, outcome, offered_admin_version,
+                applied_admin_version, error, payload_bytes, compressed_bytes,
+                endpoint, error_code, collect_ms, serialize_ms, network_ms, apply_ms,
+                snapshot_skipped
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                entry.started_at,
+                entry.finished_at,
+                entry.outcome,
+                entry.offered_admin_version,
+                entry.applied_admin_version,
+                entry.error,
+                entry.payload_bytes,
+                entry.compressed_bytes,
+                entry.endpoint,
+                entry.error_code,
+                entry.collect_ms,
+                entry.serialize_ms,
+                entry.network_ms,
+                entry.apply_ms,
+                entry.snapshot_skipped
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        let id = conn.last_insert_rowid();
+
+        conn.execute(
+            "DELETE FROM sync_history WHERE id NOT IN (
+                SELECT id FROM sync_history ORDER BY id DESC LIMIT ?1
+            )",
+            params![keep as i64],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(id)
+    }
+
+    /// 列出最近的同步记录（最新在前）
+    pub fn list_sync_history(&self, limit: usize) -> Result<Vec<SyncHistoryRow>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, started_at, finished_at, outcome, offered_admin_version,
+                        applied_admin_version, error, payload_bytes, compressed_bytes,
+                        endpoint, error_code, collect_ms, serialize_ms, network_ms, apply_ms,
+                        snapshot_skipped
+                 FROM sync_history ORDER BY id DESC LIMIT ?1",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(SyncHistoryRow {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    finished_at: row.get(2)?,
+                    outcome: row.get(3)?,
+                    offered_admin_version: row.get(4)?,
+                    applied_admin_version: row.get(5)?,
+                    error: row.get(6)?,
+                    payload_bytes: row.get(7)?,
+                    compressed_bytes: row.get(8)?,
+                    endpoint: row.get(9)?,
+                    error_code: row.get(10)?,
+                    collect_ms: row.get(11)?,
+                    serialize_ms: row.get(12)?,
+                    network_ms: row.get(13)?,
+                    apply_ms: row.get(14)?,
+                    snapshot_skipped: row.get(15)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+}
@@ -3,6 +3,7 @@
 //! Database access operations for each domain
 
 pub mod failover;
+pub mod management_sync;
 pub mod mcp;
 pub mod prompts;
 pub mod providers;
@@ -12,5 +13,6 @@ pub mod skills;
 pub mod stream_check;
 
 // 所有 DAO 方法都通过 Database impl 提供，无需单独导出
-// 导出 FailoverQueueItem 供外部使用
+// 导出 FailoverQueueItem / SyncHistoryRow 供外部使用
 pub use failover::FailoverQueueItem;
+pub use management_sync::SyncHistoryRow;
@@ -32,7 +32,7 @@ mod schema;
 mod tests;
 
 // DAO 类型导出供外部使用
-pub use dao::FailoverQueueItem;
+pub use dao::{FailoverQueueItem, SyncHistoryRow};
 
 use crate::config::get_app_config_dir;
 use crate::error::AppError;
@@ -307,6 +307,63 @@ impl Database {
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        // 17. Management Sync Queue 表 (离线时暂存的同步请求)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS management_sync_queue (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                payload TEXT NOT NULL,
+                client_time TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 18. Config Backups 表 (应用管理员配置前的供应商备份)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS config_backups (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                admin_version INTEGER,
+                previous_admin_version INTEGER,
+                snapshot TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 19. Sync History 表 (管理同步尝试记录，用于诊断)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at TEXT NOT NULL,
+                finished_at TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                offered_admin_version INTEGER,
+                applied_admin_version INTEGER,
+                error TEXT,
+                payload_bytes INTEGER,
+                compressed_bytes INTEGER,
+                endpoint TEXT,
+                error_code TEXT,
+                collect_ms INTEGER,
+                serialize_ms INTEGER,
+                network_ms INTEGER,
+                apply_ms INTEGER,
+                snapshot_skipped TEXT
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 旧库补上后加的同步历史列
+        Self::add_column_if_missing(conn, "sync_history", "compressed_bytes", "INTEGER")?;
+        Self::add_column_if_missing(conn, "sync_history", "endpoint", "TEXT")?;
+        Self::add_column_if_missing(conn, "sync_history", "error_code", "TEXT")?;
+        for column in ["collect_ms", "serialize_ms", "network_ms", "apply_ms"] {
+            Self::add_column_if_missing(conn, "sync_history", column, "INTEGER")?;
+        }
+        Self::add_column_if_missing(conn, "sync_history", "snapshot_skipped", "TEXT")?;
+
         // 尝试添加 live_takeover_active 列到 proxy_config 表
         let _ = conn.execute(
             "ALTER TABLE proxy_config ADD COLUMN live_takeover_active INTEGER NOT NULL DEFAULT 0",
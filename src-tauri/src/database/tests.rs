@@ -332,3 +332,87 @@ fn model_pricing_is_seeded_on_init() {
         gemini_count
     );
 }
+
+#[test]
+fn management_sync_queue_drops_oldest_entries() {
+    let db = Database::memory().expect("create memory db");
+
+    for index in 0..5 {
+        let dropped = db
+            .enqueue_management_sync(&format!("{{\"n\":{index}}}"), &index.to_string(), 3)
+            .expect("enqueue");
+        assert_eq!(dropped, usize::from(index >= 3));
+    }
+
+    let queue = db.list_management_sync_queue().expect("list queue");
+    let times: Vec<&str> = queue.iter().map(|entry| entry.client_time.as_str()).collect();
+    assert_eq!(times, vec!["2", "3", "4"]);
+
+    db.delete_management_sync_entry(queue[0].id)
+        .expect("delete entry");
+    assert_eq!(db.list_management_sync_queue().expect("list queue").len(), 2);
+}
+
+#[test]
+fn config_backups_keep_latest_entries() {
+    let db = Database::memory().expect("create memory db");
+
+    let ids: Vec<i64> = (1..=4)
+        .map(|version| {
+            db.insert_config_backup("2025-01-01T00:00:00Z", Some(version), None, "{}", 2)
+                .expect("insert backup")
+        })
+        .collect();
+
+    let backups = db.list_config_backups().expect("list backups");
+    let versions: Vec<Option<i64>> = backups.iter().map(|backup| backup.admin_version).collect();
+    assert_eq!(versions, vec![Some(4), Some(3)]);
+    assert!(db.get_config_backup(ids[0]).expect("get backup").is_none());
+    assert_eq!(
+        db.get_config_backup(ids[3])
+            .expect("get backup")
+            .map(|backup| backup.snapshot),
+        Some("{}".to_string())
+    );
+}
+
+#[test]
+fn sync_history_keeps_latest_entries() {
+    let db = Database::memory().expect("create memory db");
+
+    for version in 1..=4 {
+        let entry = SyncHistoryRow {
+            started_at: "2025-01-01T00:00:00Z".to_string(),
+            finished_at: "2025-01-01T00:00:01Z".to_string(),
+            outcome: "success".to_string(),
+            offered_admin_version: Some(version),
+            payload_bytes: Some(1000),
+            compressed_bytes: Some(200 + version),
+            endpoint: Some("https://fallback.example.com".to_string()),
+            collect_ms: Some(12),
+            network_ms: Some(340),
+            snapshot_skipped: (version == 4).then(|| "metered".to_string()),
+            ..Default::default()
+        };
+        db.insert_sync_history(&entry, 3).expect("insert history");
+    }
+
+    let history = db.list_sync_history(10).expect("list history");
+    let versions: Vec<Option<i64>> = history
+        .iter()
+        .map(|entry| entry.offered_admin_version)
+        .collect();
+    assert_eq!(versions, vec![Some(4), Some(3), Some(2)]);
+    assert_eq!(history[0].payload_bytes, Some(1000));
+    assert_eq!(history[0].compressed_bytes, Some(204));
+    assert_eq!(
+        history[0].endpoint.as_deref(),
+        Some("https://fallback.example.com")
+    );
+    assert_eq!(history[0].collect_ms, Some(12));
+    assert_eq!(history[0].serialize_ms, None);
+    assert_eq!(history[0].network_ms, Some(340));
+    assert_eq!(history[0].snapshot_skipped.as_deref(), Some("metered"));
+    assert_eq!(history[1].snapshot_skipped, None);
+    assert_eq!(db.list_sync_history(1).expect("list history").len(), 1);
+}
@@ -209,6 +209,7 @@ pub fn run() {
         })
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .setup(|app| {
@@ -701,6 +702,70 @@ pub fn run() {
             commands::get_stream_check_config,
             commands::save_stream_check_config,
             commands::get_tool_versions,
+            // Management sync
+            commands::get_management_sync_status,
+            commands::get_management_sync_schedule,
+            commands::set_management_sync_schedule,
+            commands::get_management_pause_window,
+            commands::set_management_pause_window,
+            commands::list_config_backups,
+            commands::restore_config_backup,
+            commands::get_management_merge_mode,
+            commands::set_management_merge_mode,
+            commands::get_management_conflict_policy,
+            commands::set_management_conflict_policy,
+            commands::resolve_management_conflict,
+            commands::get_management_proxy_settings,
+            commands::set_management_proxy_settings,
+            commands::test_management_connection,
+            commands::sync_management_now,
+            commands::get_management_tls_settings,
+            commands::set_management_tls_settings,
+            commands::get_management_snapshot_privacy,
+            commands::set_management_snapshot_privacy,
+            commands::get_management_sync_enabled,
+            commands::set_management_sync_enabled,
+            commands::list_sync_profiles,
+            commands::create_sync_profile,
+            commands::set_sync_profile_enabled,
+            commands::get_sync_profile_status,
+            commands::get_management_url_override,
+            commands::set_management_url_override,
+            commands::get_sync_history,
+            commands::get_device_id,
+            commands::get_management_info,
+            commands::get_server_time_offset,
+            commands::get_feature_flag,
+            commands::regenerate_device_id,
+            commands::get_management_report_hostname,
+            commands::set_management_report_hostname,
+            commands::get_management_full_sync_on_metered,
+            commands::set_management_full_sync_on_metered,
+            commands::get_management_log_upload_allowed,
+            commands::set_management_log_upload_allowed,
+            commands::get_management_apply_notification,
+            commands::set_management_apply_notification,
+            commands::get_management_apply_confirmation,
+            commands::set_management_apply_confirmation,
+            commands::get_pending_admin_config,
+            commands::approve_pending_admin_config,
+            commands::reject_pending_admin_config,
+            commands::preview_admin_config,
+            commands::get_pinned_providers,
+            commands::set_provider_pinned,
+            commands::get_management_exclude_pinned,
+            commands::set_management_exclude_pinned,
+            commands::get_management_include_extras,
+            commands::set_management_include_extras,
+            commands::get_management_require_signed_configs,
+            commands::set_management_require_signed_configs,
+            commands::get_management_fallback_urls,
+            commands::set_management_fallback_urls,
+            commands::get_sync_metrics,
+            commands::get_sync_log_path,
+            commands::reveal_sync_log,
+            commands::export_provider_config,
+            commands::import_provider_config,
         ]);
 
     let app = builder
@@ -818,6 +883,9 @@ pub fn run() {
 /// 使用 stop_with_restore_keep_state 保留 settings 表中的代理状态，下次启动时自动恢复。
 pub async fn cleanup_before_exit(app_handle: &tauri::AppHandle) {
     if let Some(state) = app_handle.try_state::<store::AppState>() {
+        // 先让管理同步停下：正在应用的管理员配置会在限定时间内完成
+        services::ManagementSyncService::prepare_exit(&state).await;
+
         let proxy_service = &state.proxy_service;
 
         // 退出时也需要兜底：代理可能已崩溃/未运行，但 Live 接管残留仍在（占位符/备份）。
@@ -0,0 +1,1134 @@
+//! 应用管理员下发的配置：签名校验、确认模式下的待确认配置、冲突检查、备份与逐应用写入
+//!
+//! 同步流程与 [`ManagementSyncService`](super::ManagementSyncService) 的确认、预览、恢复等命令都经过这里；
+//! 配置与状态的结构仍定义在 `management_sync` 中。
+
+use chrono::Utc;
+use indexmap::IndexMap;
+use tauri::{Emitter, Runtime};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::management_error::{SyncError, SyncErrorKind};
+use crate::services::management_extras;
+use crate::services::management_profiles::setting_key;
+use crate::services::management_signing;
+use crate::services::management_sync::{
+    applied_extras_version_key, collect_snapshot, collect_snapshot_with, set_applied_admin_version,
+    should_apply_admin_config, show_notification, snapshot_hash, AdminApplyReport, AppApplyResult,
+    AppConfigDiff, AppProviderSnapshot, AppliedVersions, AppliedVersionsExt, ApplyConfirmation,
+    ApplyError, ApplyMode, ConfigConflict, ConflictPolicy, ConflictResolution,
+    DeviceConfigSnapshot, PendingAdminConfig, ProviderCounts, ProviderRef, RejectedAdminConfig,
+    StoredAdminConfig, SETTINGS_APPLIED_CONFIG_HASH, SETTINGS_APPLY_MODE, SETTINGS_CONFLICT_POLICY,
+    SETTINGS_LAST_CONFLICT, SETTINGS_MERGE_MODE, SETTINGS_REQUIRE_SIGNED_CONFIGS,
+    SETTINGS_RESTORED_BACKUP_ID, SNAPSHOT_SCHEMA_VERSION,
+};
+use crate::services::ProviderService;
+use crate::store::AppState;
+
+const SETTINGS_LAST_APPLY_ERROR: &str = "management_last_apply_error";
+const SETTINGS_LAST_APPLY: &str = "management_last_apply";
+const SETTINGS_PENDING_ADMIN_CONFIG: &str = "management_pending_admin_config";
+const SETTINGS_REJECTED_ADMIN_CONFIG: &str = "management_rejected_admin_config";
+/// 按应用存放固定的供应商 ID（JSON 数组），键为 `management_pinned_providers_<app>`
+const SETTINGS_PINNED_PROVIDERS: &str = "management_pinned_providers";
+/// 应用管理员配置前的本地备份保留数量
+const CONFIG_BACKUP_RETAIN: usize = 10;
+
+const EVENT_ADMIN_CONFIG_APPLIED: &str = "management-sync://admin-config-applied";
+const EVENT_ADMIN_CONFIG_PENDING: &str = "management-sync://admin-config-pending";
+
+/// 应用下发的管理员配置：冲突检查、备份、逐应用写入并记录版本
+///
+/// 自动模式的同步与批准待确认配置共用，返回是否改动了供应商以及冲突记录。
+pub(crate) fn apply_offered_config<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    state: &AppState,
+    config: DeviceConfigSnapshot,
+    admin_version: Option<i64>,
+    applied_versions: &AppliedVersions,
+    local_hash: &str,
+) -> Result<(bool, Option<ConfigConflict>), AppError> {
+    // 应用前的本地状态与上次应用后记录的哈希比对
+    let applied_hash = state
+        .db
+        .get_setting(&setting_key(SETTINGS_APPLIED_CONFIG_HASH))?;
+    let locally_modified = has_local_changes(applied_hash.as_deref(), local_hash);
+    if locally_modified && get_conflict_policy(&state.db)? == ConflictPolicy::Skip {
+        log::warn!(
+            "Local providers changed since the last admin config; skipping version {:?}",
+            admin_version
+        );
+        let conflict =
+            record_conflict(&state.db, admin_version, ConflictResolution::Skipped, None)?;
+        return Ok((false, Some(conflict)));
+    }
+
+    let backup_id = backup_before_apply(state, admin_version, applied_versions.oldest())?;
+    let report = apply_admin_config(state, config, admin_version, applied_versions)?;
+    let applied = report.result();
+    track_apply_result(&state.db, admin_version, &applied, report.any_partial())?;
+    record_apply_report(&state.db, &report)?;
+    if report.any_changed() {
+        notify_admin_config_applied(app_handle, &state.db, &report);
+    }
+    if report.any_applied() {
+        state
+            .db
+            .set_setting(&setting_key(SETTINGS_RESTORED_BACKUP_ID), "")?;
+        // 部分应用也要记录哈希，否则已应用的部分在下次同步时会被当成本地修改
+        state.db.set_setting(
+            &setting_key(SETTINGS_APPLIED_CONFIG_HASH),
+            &snapshot_hash(&collect_snapshot(state)?)?,
+        )?;
+    }
+    // 各应用的版本已分别记录，失败的应用下次同步重试
+    applied?;
+
+    let conflict = if locally_modified {
+        log::warn!(
+            "Local providers changed since the last admin config; saved them in backup {backup_id}"
+        );
+        Some(record_conflict(
+            &state.db,
+            admin_version,
+            ConflictResolution::BackedUp,
+            Some(backup_id),
+        )?)
+    } else {
+        state
+            .db
+            .set_setting(&setting_key(SETTINGS_LAST_CONFLICT), "")?;
+        None
+    };
+    clear_pending_config(&state.db)?;
+    state
+        .db
+        .set_setting(&setting_key(SETTINGS_REJECTED_ADMIN_CONFIG), "")?;
+    Ok((true, conflict))
+}
+
+/// 确认模式下保存收到的配置；有新的待确认配置时返回其摘要，用于通知
+///
+/// 更新的版本替换尚未处理的旧版本；已拒绝或已在等待的同一份配置不再重复提示。
+pub(crate) fn hold_pending_config(
+    db: &crate::database::Database,
+    config: DeviceConfigSnapshot,
+    admin_version: Option<i64>,
+) -> Result<Option<PendingAdminConfig>, AppError> {
+    let config_hash = snapshot_hash(&config)?;
+    let same = |version: Option<i64>, hash: &str| version == admin_version && hash == config_hash;
+    if get_rejected_config(db)
+        .is_some_and(|rejected| same(rejected.admin_version, &rejected.config_hash))
+    {
+        log::debug!("Admin config version {admin_version:?} was rejected; not asking again");
+        return Ok(None);
+    }
+    if let Some(existing) = get_pending_config(db) {
+        if same(existing.admin_version, &existing.config_hash) {
+            return Ok(None);
+        }
+        log::info!(
+            "Admin config version {:?} supersedes pending version {:?}",
+            admin_version,
+            existing.admin_version
+        );
+    }
+
+    let pending = StoredAdminConfig {
+        admin_version,
+        received_at: Utc::now().to_rfc3339(),
+        config_hash,
+        config,
+    };
+    pending.save(db, &setting_key(SETTINGS_PENDING_ADMIN_CONFIG))?;
+    // 新版本出现后旧的拒绝记录不再有意义
+    db.set_setting(&setting_key(SETTINGS_REJECTED_ADMIN_CONFIG), "")?;
+    Ok(Some(pending.summary()))
+}
+
+pub(crate) fn reject_pending_config(
+    db: &crate::database::Database,
+    pending: StoredAdminConfig,
+) -> Result<(), AppError> {
+    let rejected = RejectedAdminConfig {
+        admin_version: pending.admin_version,
+        config_hash: pending.config_hash,
+        rejected_at: Utc::now().to_rfc3339(),
+    };
+    let value =
+        serde_json::to_string(&rejected).map_err(|source| AppError::JsonSerialize { source })?;
+    db.set_setting(&setting_key(SETTINGS_REJECTED_ADMIN_CONFIG), &value)?;
+    clear_pending_config(db)
+}
+
+pub(crate) fn get_pending_config(db: &crate::database::Database) -> Option<StoredAdminConfig> {
+    StoredAdminConfig::load(db, &setting_key(SETTINGS_PENDING_ADMIN_CONFIG))
+}
+
+pub(crate) fn clear_pending_config(db: &crate::database::Database) -> Result<(), AppError> {
+    db.set_setting(&setting_key(SETTINGS_PENDING_ADMIN_CONFIG), "")
+}
+
+pub(crate) fn get_rejected_config(db: &crate::database::Database) -> Option<RejectedAdminConfig> {
+    let value = db
+        .get_setting(&setting_key(SETTINGS_REJECTED_ADMIN_CONFIG))
+        .ok()
+        .flatten()?;
+    if value.is_empty() {
+        return None;
+    }
+    serde_json::from_str(&value).ok()
+}
+
+pub(crate) fn get_apply_confirmation(
+    db: &crate::database::Database,
+) -> Result<ApplyConfirmation, AppError> {
+    Ok(match db.get_setting(SETTINGS_APPLY_MODE)?.as_deref() {
+        Some("ask") => ApplyConfirmation::Ask,
+        _ => ApplyConfirmation::Automatic,
+    })
+}
+
+pub(crate) fn provider_counts(snapshot: &DeviceConfigSnapshot) -> ProviderCounts {
+    let count =
+        |app: &Option<AppProviderSnapshot>| app.as_ref().map_or(0, |app| app.providers.len());
+    ProviderCounts {
+        claude: count(&snapshot.claude),
+        codex: count(&snapshot.codex),
+        gemini: count(&snapshot.gemini),
+    }
+}
+
+/// 各应用独立应用：某个应用的配置有问题不影响其余应用，成功的应用立即记录版本；
+/// 已应用该版本的应用跳过。MCP 服务器与通用配置片段作为 `extras` 同样独立应用
+pub(crate) fn apply_admin_config(
+    state: &AppState,
+    config: DeviceConfigSnapshot,
+    admin_version: Option<i64>,
+    applied_versions: &AppliedVersions,
+) -> Result<AdminApplyReport, AppError> {
+    let mode = match config.mode {
+        Some(mode) => mode,
+        None => get_merge_mode(&state.db)?,
+    };
+    if config.schema_version > SNAPSHOT_SCHEMA_VERSION {
+        log::warn!(
+            "Admin config uses snapshot schema {} (this client understands {}); unknown sections are skipped",
+            config.schema_version,
+            SNAPSHOT_SCHEMA_VERSION
+        );
+    }
+    for name in config.other_apps.keys() {
+        log::warn!("Skipping unrecognized admin config section {name}");
+    }
+
+    let mut apps = Vec::new();
+    for (app_type, snapshot) in [
+        (AppType::Claude, config.claude),
+        (AppType::Codex, config.codex),
+        (AppType::Gemini, config.gemini),
+    ] {
+        let Some(snapshot) = snapshot else {
+            continue;
+        };
+        if !should_apply_admin_config(admin_version, applied_versions.get(&app_type)) {
+            continue;
+        }
+        let providers = snapshot.providers.len();
+        let current = snapshot
+            .current_id
+            .as_ref()
+            .and_then(|id| snapshot.providers.get(id))
+            .map(|provider| provider.name.clone());
+        let mut progress = AppApplyProgress::default();
+        // 只有整个应用（含切换）都成功才记录版本，否则下次同步重试
+        let error = match apply_app_snapshot(state, app_type.clone(), snapshot, mode, &mut progress)
+        {
+            Ok(()) => {
+                if let Some(version) = admin_version {
+                    set_applied_admin_version(&state.db, &app_type, version)?;
+                }
+                None
+            }
+            Err(err) => {
+                log::warn!(
+                    "Failed to apply admin config for {}{}: {err}",
+                    app_type.as_str(),
+                    if progress.changed() {
+                        " after changing local providers"
+                    } else {
+                        ""
+                    }
+                );
+                Some(err.to_string())
+            }
+        };
+        apps.push(AppApplyResult {
+            app: app_type.as_str().to_string(),
+            changed: error.is_none() && progress.changed(),
+            partial: error.is_some() && progress.changed(),
+            error,
+            providers,
+            current,
+            switched: progress.switched,
+        });
+    }
+
+    if let Some(extras) = config
+        .extras
+        .filter(|_| should_apply_admin_config(admin_version, applied_versions.extras))
+    {
+        let servers = management_extras::server_count(&extras);
+        let mut written = false;
+        let error = match management_extras::apply(state, extras, mode, &mut written) {
+            Ok(()) => {
+                if let Some(version) = admin_version {
+                    state
+                        .db
+                        .set_setting(&applied_extras_version_key(), &version.to_string())?;
+                }
+                None
+            }
+            Err(err) => {
+                log::warn!("Failed to apply admin config extras: {err}");
+                Some(err.to_string())
+            }
+        };
+        apps.push(AppApplyResult {
+            app: management_extras::SECTION.to_string(),
+            changed: error.is_none() && written,
+            partial: error.is_some() && written,
+            error,
+            providers: servers,
+            current: None,
+            switched: false,
+        });
+    }
+
+    Ok(AdminApplyReport {
+        admin_version,
+        applied_at: Utc::now().to_rfc3339(),
+        apps,
+    })
+}
+
+/// 通知前端与用户管理员配置已生效；通知失败只记录日志
+fn notify_admin_config_applied<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    db: &crate::database::Database,
+    report: &AdminApplyReport,
+) {
+    if let Err(err) = app_handle.emit(EVENT_ADMIN_CONFIG_APPLIED, report) {
+        log::warn!("Failed to emit admin config applied event: {err}");
+    }
+    show_notification(app_handle, db, |language| report.notification(language));
+}
+
+/// 通知前端与用户有管理员配置等待确认
+pub(crate) fn notify_admin_config_pending<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    db: &crate::database::Database,
+    pending: &PendingAdminConfig,
+) {
+    if let Err(err) = app_handle.emit(EVENT_ADMIN_CONFIG_PENDING, pending) {
+        log::warn!("Failed to emit pending admin config event: {err}");
+    }
+    show_notification(app_handle, db, |language| {
+        match language {
+        "en" => (
+            "Provider configuration awaiting approval",
+            "Your administrator sent a new AI provider configuration. Open AI Code With to review it."
+                .to_string(),
+        ),
+        "ja" => (
+            "プロバイダー設定の承認待ち",
+            "管理者から新しい AI プロバイダー設定が届きました。AI Code With を開いて確認してください。"
+                .to_string(),
+        ),
+        _ => (
+            "供应商配置待确认",
+            "管理员下发了新的 AI 供应商配置，请打开 AI Code With 查看并确认。".to_string(),
+        ),
+    }
+    });
+}
+
+fn record_apply_report(
+    db: &crate::database::Database,
+    report: &AdminApplyReport,
+) -> Result<(), AppError> {
+    let value =
+        serde_json::to_string(report).map_err(|source| AppError::JsonSerialize { source })?;
+    db.set_setting(&setting_key(SETTINGS_LAST_APPLY), &value)
+}
+
+pub(crate) fn get_last_apply(db: &crate::database::Database) -> Option<AdminApplyReport> {
+    let value = db
+        .get_setting(&setting_key(SETTINGS_LAST_APPLY))
+        .ok()
+        .flatten()?;
+    if value.is_empty() {
+        return None;
+    }
+    serde_json::from_str(&value).ok()
+}
+
+/// 应用管理员配置前备份当前配置；备份失败则放弃应用
+pub(crate) fn backup_before_apply(
+    state: &AppState,
+    admin_version: Option<i64>,
+    previous_admin_version: Option<i64>,
+) -> Result<i64, AppError> {
+    // 管理员配置可能改动 MCP 服务器与通用配置片段，不论是否上传都一并备份
+    let snapshot = collect_snapshot_with(state, true)?;
+    let snapshot =
+        serde_json::to_string(&snapshot).map_err(|source| AppError::JsonSerialize { source })?;
+    let id = state.db.insert_config_backup(
+        &Utc::now().to_rfc3339(),
+        admin_version,
+        previous_admin_version,
+        &snapshot,
+        CONFIG_BACKUP_RETAIN,
+    )?;
+    log::info!("Saved config backup {id} before applying admin config {admin_version:?}");
+    Ok(id)
+}
+
+/// 从未记录过应用后的哈希（首次应用或旧版本升级）时不视为冲突
+fn has_local_changes(applied_hash: Option<&str>, current_hash: &str) -> bool {
+    applied_hash.is_some_and(|applied| !applied.is_empty() && applied != current_hash)
+}
+
+pub(crate) fn get_conflict_policy(
+    db: &crate::database::Database,
+) -> Result<ConflictPolicy, AppError> {
+    Ok(match db.get_setting(SETTINGS_CONFLICT_POLICY)?.as_deref() {
+        Some("skip") => ConflictPolicy::Skip,
+        _ => ConflictPolicy::Backup,
+    })
+}
+
+fn record_conflict(
+    db: &crate::database::Database,
+    admin_version: Option<i64>,
+    resolution: ConflictResolution,
+    backup_id: Option<i64>,
+) -> Result<ConfigConflict, AppError> {
+    let conflict = ConfigConflict {
+        detected_at: Utc::now().to_rfc3339(),
+        admin_version,
+        resolution,
+        backup_id,
+    };
+    let value =
+        serde_json::to_string(&conflict).map_err(|source| AppError::JsonSerialize { source })?;
+    db.set_setting(&setting_key(SETTINGS_LAST_CONFLICT), &value)?;
+    Ok(conflict)
+}
+
+/// 应用失败时记下原因，成功时清除此前的失败记录
+/// 应用失败时从逐应用结果中找出第一个失败的应用；找不到时归为其他错误
+pub(crate) fn apply_failure(
+    db: &crate::database::Database,
+    admin_version: Option<i64>,
+    err: AppError,
+) -> SyncError {
+    get_last_apply(db)
+        .filter(|report| report.admin_version == admin_version)
+        .and_then(|report| {
+            report.apps.into_iter().find_map(|app| {
+                app.error.map(|reason| SyncErrorKind::ApplyFailed {
+                    app: app.app,
+                    reason,
+                })
+            })
+        })
+        .map(|kind| SyncError::new(kind, err.to_string()))
+        .unwrap_or_else(|| err.into())
+}
+
+pub(crate) fn track_apply_result(
+    db: &crate::database::Database,
+    admin_version: Option<i64>,
+    result: &Result<(), AppError>,
+    partial: bool,
+) -> Result<(), AppError> {
+    match result {
+        Ok(()) => db.set_setting(&setting_key(SETTINGS_LAST_APPLY_ERROR), ""),
+        Err(err) => {
+            if partial {
+                log::warn!("Admin config {admin_version:?} was only partially applied: {err}");
+            } else {
+                log::warn!("Failed to apply admin config {admin_version:?}: {err}");
+            }
+            let error = ApplyError {
+                message: err.to_string(),
+                admin_version,
+                at: Utc::now().to_rfc3339(),
+                partial,
+            };
+            let value = serde_json::to_string(&error)
+                .map_err(|source| AppError::JsonSerialize { source })?;
+            db.set_setting(&setting_key(SETTINGS_LAST_APPLY_ERROR), &value)
+        }
+    }
+}
+
+pub(crate) fn get_apply_error(db: &crate::database::Database) -> Option<ApplyError> {
+    let value = db
+        .get_setting(&setting_key(SETTINGS_LAST_APPLY_ERROR))
+        .ok()
+        .flatten()?;
+    if value.is_empty() {
+        return None;
+    }
+    serde_json::from_str(&value).ok()
+}
+
+pub(crate) fn get_last_conflict(db: &crate::database::Database) -> Option<ConfigConflict> {
+    let value = db
+        .get_setting(&setting_key(SETTINGS_LAST_CONFLICT))
+        .ok()
+        .flatten()?;
+    if value.is_empty() {
+        return None;
+    }
+    serde_json::from_str(&value).ok()
+}
+
+/// 恢复单个应用：备份中没有供应商时清空该应用
+pub(crate) fn restore_app_snapshot(
+    state: &AppState,
+    app_type: AppType,
+    snapshot: Option<AppProviderSnapshot>,
+) -> Result<(), AppError> {
+    state.db.delete_providers_by_app_type(app_type.as_str())?;
+
+    let Some(snapshot) = snapshot else {
+        return Ok(());
+    };
+    for provider in snapshot.providers.values() {
+        ProviderService::add(state, app_type.clone(), provider.clone())?;
+    }
+    if let Some(current_id) = snapshot
+        .current_id
+        .as_deref()
+        .filter(|id| snapshot.providers.contains_key(*id))
+    {
+        ProviderService::switch(state, app_type.clone(), current_id)?;
+    }
+    Ok(())
+}
+
+/// 单个应用已经做了哪些改动；应用中途失败时据此判断本地是否处于半应用状态
+#[derive(Debug, Default)]
+struct AppApplyProgress {
+    /// 已新增、更新或删除供应商
+    providers_written: bool,
+    switched: bool,
+}
+
+impl AppApplyProgress {
+    fn changed(&self) -> bool {
+        self.providers_written || self.switched
+    }
+}
+
+fn apply_app_snapshot(
+    state: &AppState,
+    app_type: AppType,
+    snapshot: AppProviderSnapshot,
+    mode: ApplyMode,
+    progress: &mut AppApplyProgress,
+) -> Result<(), AppError> {
+    // 必须在动本地状态之前确认整份配置都能写入
+    let current_id = validate_app_snapshot(&app_type, &snapshot)?;
+
+    // 只改动有差异的供应商，配置一致时不重写 live 配置文件，避免与正在运行的 CLI 冲突
+    let local = state.db.get_all_providers(app_type.as_str())?;
+    let pinned = local_pins(pinned_ids(&state.db, &app_type), &local);
+    check_pinned_current(&app_type, current_id, &pinned)?;
+    let plan = plan_app_apply(&local, &snapshot.providers, mode, &pinned);
+    for provider in &plan.pinned {
+        log::info!(
+            "Keeping pinned local provider {} ({}) instead of the admin version",
+            provider.id,
+            app_type.as_str()
+        );
+    }
+    for provider in &plan.update {
+        if mode == ApplyMode::Merge {
+            log::warn!(
+                "Admin config overwrites local provider {} ({})",
+                provider.id,
+                app_type.as_str()
+            );
+        }
+        progress.providers_written = true;
+        ProviderService::update(state, app_type.clone(), (*provider).clone())?;
+    }
+    for provider in &plan.add {
+        progress.providers_written = true;
+        ProviderService::add(state, app_type.clone(), (*provider).clone())?;
+    }
+
+    let current = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+    let db_current = state.db.get_current_provider(app_type.as_str())?;
+    if needs_switch(current.as_deref(), db_current.as_deref(), current_id) {
+        ProviderService::switch(state, app_type.clone(), current_id)?;
+        // 切换返回成功也要确认确实生效，否则不能记录版本
+        let current = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+        let db_current = state.db.get_current_provider(app_type.as_str())?;
+        if needs_switch(current.as_deref(), db_current.as_deref(), current_id) {
+            return Err(AppError::Message(format!(
+                "Switching {} to admin provider {current_id} did not take effect",
+                app_type.as_str()
+            )));
+        }
+        progress.switched = true;
+    }
+
+    // 切换之后再删除，被删除的供应商此时不再是当前供应商
+    for id in &plan.delete {
+        progress.providers_written = true;
+        state.db.delete_provider(app_type.as_str(), id)?;
+    }
+
+    log::debug!(
+        "Applied admin config for {}: {} added, {} updated, {} removed, {} unchanged",
+        app_type.as_str(),
+        plan.add.len(),
+        plan.update.len(),
+        plan.delete.len(),
+        plan.unchanged
+    );
+    Ok(())
+}
+
+/// 本设备的当前供应商和数据库默认值都要与下发配置一致
+fn needs_switch(current: Option<&str>, db_current: Option<&str>, current_id: &str) -> bool {
+    current != Some(current_id) || db_current != Some(current_id)
+}
+
+/// 按 [`apply_app_snapshot`] 的校验与比对逻辑计算改动，不写入任何状态
+pub(crate) fn diff_app_snapshot(
+    app_type: &AppType,
+    local: &IndexMap<String, Provider>,
+    current: Option<&str>,
+    db_current: Option<&str>,
+    snapshot: &AppProviderSnapshot,
+    mode: ApplyMode,
+    pinned: &[String],
+) -> AppConfigDiff {
+    let mut diff = AppConfigDiff {
+        app: app_type.as_str().to_string(),
+        current_id: current.map(str::to_string),
+        ..Default::default()
+    };
+    let current_id = match validate_app_snapshot(app_type, snapshot).and_then(|current_id| {
+        check_pinned_current(app_type, current_id, pinned).map(|_| current_id)
+    }) {
+        Ok(current_id) => current_id,
+        Err(err) => {
+            diff.error = Some(err.to_string());
+            return diff;
+        }
+    };
+
+    let plan = plan_app_apply(local, &snapshot.providers, mode, pinned);
+    diff.pinned = plan.pinned.into_iter().map(ProviderRef::from).collect();
+    diff.added = plan.add.into_iter().map(ProviderRef::from).collect();
+    diff.modified = plan.update.into_iter().map(ProviderRef::from).collect();
+    diff.removed = plan
+        .delete
+        .iter()
+        .filter_map(|id| local.get(id))
+        .map(ProviderRef::from)
+        .collect();
+    diff.unchanged = plan.unchanged;
+    if needs_switch(current, db_current, current_id) {
+        diff.new_current_id = Some(current_id.to_string());
+    }
+    diff
+}
+
+/// 校验下发的单个应用配置，返回其当前供应商 ID；列出所有不合法的供应商
+fn validate_app_snapshot<'a>(
+    app_type: &AppType,
+    snapshot: &'a AppProviderSnapshot,
+) -> Result<&'a str, AppError> {
+    let Some(current_id) = snapshot.current_id.as_deref() else {
+        return Err(AppError::Message(format!(
+            "Admin config missing current provider: {}",
+            app_type.as_str()
+        )));
+    };
+
+    if !snapshot.providers.contains_key(current_id) {
+        return Err(AppError::Message(format!(
+            "Admin config current provider not found: {}",
+            current_id
+        )));
+    }
+
+    let invalid: Vec<String> = snapshot
+        .providers
+        .iter()
+        .filter_map(|(id, provider)| {
+            if provider.id != *id {
+                return Some(format!("{id}: provider id {} does not match", provider.id));
+            }
+            ProviderService::validate_provider(app_type, provider)
+                .err()
+                .map(|err| format!("{id}: {err}"))
+        })
+        .collect();
+    if !invalid.is_empty() {
+        return Err(AppError::Message(format!(
+            "Admin config has invalid {} providers: {}",
+            app_type.as_str(),
+            invalid.join("; ")
+        )));
+    }
+
+    Ok(current_id)
+}
+
+/// 逐个供应商比对下发配置与本地状态的结果
+#[derive(Debug, Default)]
+struct AppApplyPlan<'a> {
+    add: Vec<&'a Provider>,
+    update: Vec<&'a Provider>,
+    /// 仅替换模式：本地有而下发配置中没有的供应商
+    delete: Vec<String>,
+    /// 与本地固定供应商同 ID、因此不写入的下发条目
+    pinned: Vec<&'a Provider>,
+    unchanged: usize,
+}
+
+/// 替换模式的最终结果与下发配置一致；合并模式同 ID 的供应商以管理员版本为准，
+/// 新的供应商追加，其余本地供应商保持不变。固定的本地供应商在两种模式下都不会被覆盖或删除
+fn plan_app_apply<'a>(
+    local: &IndexMap<String, Provider>,
+    incoming: &'a IndexMap<String, Provider>,
+    mode: ApplyMode,
+    pinned: &[String],
+) -> AppApplyPlan<'a> {
+    let mut plan = AppApplyPlan::default();
+    for (id, provider) in incoming {
+        if pinned.contains(id) {
+            plan.pinned.push(provider);
+            continue;
+        }
+        match local.get(id) {
+            Some(existing) if !provider_differs(existing, provider) => plan.unchanged += 1,
+            Some(_) => plan.update.push(provider),
+            None => plan.add.push(provider),
+        }
+    }
+    if mode == ApplyMode::Replace {
+        plan.delete = local
+            .keys()
+            .filter(|id| !incoming.contains_key(*id) && !pinned.contains(id))
+            .cloned()
+            .collect();
+    }
+    plan
+}
+
+/// 下发的当前供应商不能是本地固定的供应商，否则要么切到本地版本，要么覆盖它
+fn check_pinned_current(
+    app_type: &AppType,
+    current_id: &str,
+    pinned: &[String],
+) -> Result<(), AppError> {
+    if pinned.iter().any(|id| id == current_id) {
+        return Err(AppError::Message(format!(
+            "Admin config current provider {current_id} conflicts with a pinned local {} provider; unpin it to accept the admin config",
+            app_type.as_str()
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn pinned_key(app_type: &AppType) -> String {
+    format!("{SETTINGS_PINNED_PROVIDERS}_{}", app_type.as_str())
+}
+
+pub(crate) fn pinned_ids(db: &crate::database::Database, app_type: &AppType) -> Vec<String> {
+    db.get_setting(&pinned_key(app_type))
+        .ok()
+        .flatten()
+        .filter(|value| !value.is_empty())
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// 只保留本地仍存在的固定供应商；已删除供应商留下的固定记录不参与比对
+pub(crate) fn local_pins(pinned: Vec<String>, local: &IndexMap<String, Provider>) -> Vec<String> {
+    pinned
+        .into_iter()
+        .filter(|id| local.contains_key(id))
+        .collect()
+}
+
+pub(crate) fn require_signed_configs(db: &crate::database::Database) -> bool {
+    db.get_setting(SETTINGS_REQUIRE_SIGNED_CONFIGS)
+        .ok()
+        .flatten()
+        .is_some_and(|value| value.trim() == "true")
+}
+
+/// 校验签名后解析管理员配置
+///
+/// 有签名时必须校验通过；没有签名（或本构建未编入公钥）时按
+/// `management_require_signed_configs` 决定是否拒绝。
+pub(crate) fn verify_admin_config(
+    db: &crate::database::Database,
+    config: serde_json::Value,
+    signature: Option<&str>,
+    public_key: &str,
+) -> Result<DeviceConfigSnapshot, SyncError> {
+    let refuse = |detail: String| SyncError::new(SyncErrorKind::InvalidSignature, detail);
+    match signature {
+        Some(signature) if !public_key.trim().is_empty() => {
+            management_signing::verify(public_key, &config, signature)
+                .map_err(|reason| refuse(format!("Admin config signature rejected: {reason}")))?;
+        }
+        Some(_) if require_signed_configs(db) => {
+            return Err(refuse(
+                "Admin config is signed but this build has no public key to verify it".to_string(),
+            ));
+        }
+        Some(_) => log::warn!("No config signing public key in this build; applying unverified"),
+        None if require_signed_configs(db) => {
+            return Err(refuse(
+                "Admin config is unsigned but signed configs are required".to_string(),
+            ));
+        }
+        None => {}
+    }
+    serde_json::from_value(config).map_err(|err| {
+        SyncError::new(
+            SyncErrorKind::ParseError,
+            format!("Admin config parse failed: {err}"),
+        )
+    })
+}
+
+fn provider_differs(local: &Provider, admin: &Provider) -> bool {
+    match (serde_json::to_value(local), serde_json::to_value(admin)) {
+        (Ok(local), Ok(admin)) => local != admin,
+        _ => true,
+    }
+}
+
+pub(crate) fn get_merge_mode(db: &crate::database::Database) -> Result<ApplyMode, AppError> {
+    Ok(match db.get_setting(SETTINGS_MERGE_MODE)?.as_deref() {
+        Some("merge") => ApplyMode::Merge,
+        _ => ApplyMode::Replace,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cc_switch_protocol::canonical_json;
+
+    fn app_snapshot(ids: &[&str]) -> AppProviderSnapshot {
+        let mut providers = IndexMap::new();
+        for id in ids {
+            providers.insert(
+                id.to_string(),
+                Provider::with_id(
+                    id.to_string(),
+                    id.to_string(),
+                    serde_json::json!({ "env": { "B": "2", "A": "1" } }),
+                    None,
+                ),
+            );
+        }
+        AppProviderSnapshot {
+            current_id: ids.first().map(|id| id.to_string()),
+            providers,
+        }
+    }
+
+    #[test]
+    fn admin_configs_must_carry_a_valid_signature() {
+        use base64::Engine;
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[3u8; 32]).unwrap();
+        let public_key = engine.encode(key_pair.public_key().as_ref());
+        let config = serde_json::json!({
+            "claude": {"currentId": "a", "providers": {}},
+        });
+        let signature = engine.encode(key_pair.sign(canonical_json(&config).as_bytes()));
+        let db = crate::database::Database::memory().expect("memory db");
+
+        let verified = verify_admin_config(&db, config.clone(), Some(&signature), &public_key)
+            .expect("valid signature");
+        assert_eq!(
+            verified.claude.and_then(|app| app.current_id),
+            Some("a".to_string())
+        );
+
+        let mut tampered = config.clone();
+        tampered["claude"]["currentId"] = serde_json::json!("b");
+        let Err(err) = verify_admin_config(&db, tampered, Some(&signature), &public_key) else {
+            panic!("tampered config must be refused");
+        };
+        assert_eq!(err.kind, SyncErrorKind::InvalidSignature);
+
+        // 未签名的配置默认照常应用，开启要求后拒绝
+        assert!(verify_admin_config(&db, config.clone(), None, &public_key).is_ok());
+        db.set_setting(SETTINGS_REQUIRE_SIGNED_CONFIGS, "true")
+            .unwrap();
+        let Err(err) = verify_admin_config(&db, config.clone(), None, &public_key) else {
+            panic!("unsigned config must be refused");
+        };
+        assert_eq!(err.kind, SyncErrorKind::InvalidSignature);
+        let Err(err) = verify_admin_config(&db, config, Some(&signature), "") else {
+            panic!("unverifiable config must be refused");
+        };
+        assert_eq!(err.kind, SyncErrorKind::InvalidSignature);
+    }
+
+    #[test]
+    fn local_changes_require_recorded_hash() {
+        assert!(!has_local_changes(None, "abc"));
+        assert!(!has_local_changes(Some(""), "abc"));
+        assert!(!has_local_changes(Some("abc"), "abc"));
+        assert!(has_local_changes(Some("abc"), "def"));
+    }
+
+    #[test]
+    fn apply_mode_and_merge_mode_are_separate_settings() {
+        let db = crate::database::Database::memory().expect("create memory db");
+        db.set_setting("management_apply_mode", "ask")
+            .expect("save apply mode");
+        assert_eq!(get_apply_confirmation(&db).unwrap(), ApplyConfirmation::Ask);
+        assert_eq!(get_merge_mode(&db).unwrap(), ApplyMode::Replace);
+
+        db.set_setting("management_merge_mode", "merge")
+            .expect("save merge mode");
+        assert_eq!(get_merge_mode(&db).unwrap(), ApplyMode::Merge);
+        assert_eq!(get_apply_confirmation(&db).unwrap(), ApplyConfirmation::Ask);
+    }
+
+    #[test]
+    fn pending_config_is_superseded_and_rejection_is_remembered() {
+        let db = crate::database::Database::memory().expect("memory db");
+        let config = |id: &str| DeviceConfigSnapshot {
+            claude: Some(app_snapshot(&[id])),
+            codex: None,
+            gemini: None,
+            mode: None,
+            privacy: None,
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            extras: None,
+            other_apps: IndexMap::new(),
+        };
+
+        let pending = hold_pending_config(&db, config("a"), Some(2)).unwrap();
+        assert_eq!(
+            pending.map(|pending| pending.provider_counts.claude),
+            Some(1)
+        );
+        // 同一份配置再次下发时不重复提示
+        assert!(hold_pending_config(&db, config("a"), Some(2))
+            .unwrap()
+            .is_none());
+
+        // 更新的版本替换旧的待确认配置
+        assert!(hold_pending_config(&db, config("b"), Some(3))
+            .unwrap()
+            .is_some());
+        assert_eq!(get_pending_config(&db).unwrap().admin_version, Some(3));
+
+        reject_pending_config(&db, get_pending_config(&db).unwrap()).unwrap();
+        assert!(get_pending_config(&db).is_none());
+        assert_eq!(get_rejected_config(&db).unwrap().admin_version, Some(3));
+        assert!(hold_pending_config(&db, config("b"), Some(3))
+            .unwrap()
+            .is_none());
+
+        // 新版本清除拒绝记录
+        assert!(hold_pending_config(&db, config("c"), Some(4))
+            .unwrap()
+            .is_some());
+        assert!(get_rejected_config(&db).is_none());
+    }
+
+    #[test]
+    fn apply_error_is_cleared_after_successful_apply() {
+        let db = crate::database::Database::memory().expect("create memory db");
+        assert_eq!(get_apply_error(&db), None);
+
+        let failed = Err(AppError::Message("missing currentId".to_string()));
+        track_apply_result(&db, Some(3), &failed, false).expect("record apply error");
+        let error = get_apply_error(&db).expect("apply error persisted");
+        assert_eq!(error.message, "missing currentId");
+        assert_eq!(error.admin_version, Some(3));
+        assert!(!error.partial);
+
+        // 半应用的失败单独标记，下次同步上报
+        track_apply_result(&db, Some(3), &failed, true).expect("record partial apply");
+        assert!(get_apply_error(&db).expect("apply error persisted").partial);
+
+        track_apply_result(&db, Some(4), &Ok(()), false).expect("clear apply error");
+        assert_eq!(get_apply_error(&db), None);
+    }
+
+    #[test]
+    fn invalid_admin_providers_are_listed_before_apply() {
+        let provider = |id: &str, name: &str| {
+            Provider::with_id(
+                id.to_string(),
+                name.to_string(),
+                serde_json::json!({ "env": {} }),
+                None,
+            )
+        };
+        let snapshot = |providers: Vec<Provider>| AppProviderSnapshot {
+            current_id: Some("a".to_string()),
+            providers: providers
+                .into_iter()
+                .map(|provider| (provider.id.clone(), provider))
+                .collect(),
+        };
+
+        let valid = snapshot(vec![provider("a", "A"), provider("b", "B")]);
+        assert_eq!(
+            validate_app_snapshot(&AppType::Claude, &valid).unwrap(),
+            "a"
+        );
+
+        let invalid = snapshot(vec![
+            provider("a", "A"),
+            provider("b", ""),
+            provider("c", " "),
+        ]);
+        let err = validate_app_snapshot(&AppType::Claude, &invalid)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("b: "), "unexpected error: {err}");
+        assert!(err.contains("c: "), "unexpected error: {err}");
+        assert!(!err.contains("a: "), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn matching_admin_config_rewrites_nothing() {
+        let provider = |id: &str, url: &str| {
+            Provider::with_id(
+                id.to_string(),
+                id.to_uppercase(),
+                serde_json::json!({ "env": { "ANTHROPIC_BASE_URL": url } }),
+                None,
+            )
+        };
+        let providers = |list: Vec<Provider>| -> IndexMap<String, Provider> {
+            list.into_iter()
+                .map(|provider| (provider.id.clone(), provider))
+                .collect()
+        };
+        let local = providers(vec![provider("a", "https://a"), provider("b", "https://b")]);
+
+        for mode in [ApplyMode::Replace, ApplyMode::Merge] {
+            let plan = plan_app_apply(&local, &local, mode, &[]);
+            assert!(plan.add.is_empty() && plan.update.is_empty() && plan.delete.is_empty());
+            assert_eq!(plan.unchanged, 2);
+        }
+
+        let incoming = providers(vec![
+            provider("a", "https://a2"),
+            provider("c", "https://c"),
+        ]);
+        let plan = plan_app_apply(&local, &incoming, ApplyMode::Replace, &[]);
+        let ids = |list: &[&Provider]| list.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&plan.update), vec!["a"]);
+        assert_eq!(ids(&plan.add), vec!["c"]);
+        assert_eq!(plan.delete, vec!["b"]);
+        assert_eq!(plan.unchanged, 0);
+
+        let plan = plan_app_apply(&local, &incoming, ApplyMode::Merge, &[]);
+        assert!(plan.delete.is_empty());
+    }
+
+    #[test]
+    fn preview_matches_the_apply_plan() {
+        let local: IndexMap<String, Provider> = app_snapshot(&["a", "b"]).providers;
+        let mut snapshot = app_snapshot(&["a", "c"]);
+        snapshot.current_id = Some("c".to_string());
+
+        let diff = diff_app_snapshot(
+            &AppType::Claude,
+            &local,
+            Some("a"),
+            Some("a"),
+            &snapshot,
+            ApplyMode::Replace,
+            &[],
+        );
+        let ids = |list: &[ProviderRef]| list.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&diff.added), vec!["c"]);
+        assert!(diff.modified.is_empty());
+        assert_eq!(ids(&diff.removed), vec!["b"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.new_current_id.as_deref(), Some("c"));
+
+        let diff = diff_app_snapshot(
+            &AppType::Claude,
+            &local,
+            Some("c"),
+            Some("c"),
+            &snapshot,
+            ApplyMode::Merge,
+            &[],
+        );
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.new_current_id, None);
+
+        snapshot.current_id = Some("missing".to_string());
+        let diff = diff_app_snapshot(
+            &AppType::Claude,
+            &local,
+            None,
+            None,
+            &snapshot,
+            ApplyMode::Replace,
+            &[],
+        );
+        assert!(diff.error.is_some());
+        assert!(diff.added.is_empty());
+    }
+
+    #[test]
+    fn pinned_providers_survive_admin_configs() {
+        let local: IndexMap<String, Provider> = app_snapshot(&["mine", "b"]).providers;
+        let mut incoming = app_snapshot(&["team", "mine"]).providers;
+        incoming["mine"].name = "Admin copy".to_string();
+        let pinned = vec!["mine".to_string()];
+
+        let plan = plan_app_apply(&local, &incoming, ApplyMode::Replace, &pinned);
+        let ids = |list: &[&Provider]| list.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&plan.pinned), vec!["mine"]);
+        assert!(plan.update.is_empty());
+        assert_eq!(ids(&plan.add), vec!["team"]);
+        assert_eq!(plan.delete, vec!["b"]);
+
+        assert!(check_pinned_current(&AppType::Claude, "team", &pinned).is_ok());
+        let err = check_pinned_current(&AppType::Claude, "mine", &pinned).unwrap_err();
+        assert!(err.to_string().contains("pinned"));
+
+        // 已删除供应商的固定记录不生效
+        assert_eq!(
+            local_pins(vec!["gone".to_string()], &local),
+            Vec::<String>::new()
+        );
+    }
+}
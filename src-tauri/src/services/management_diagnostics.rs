@@ -0,0 +1,313 @@
+//! 管理同步的诊断日志
+//!
+//! 独立于应用主日志的滚动文件（应用数据目录下 `logs/management-sync.log`，最多 3 个文件、
+//! 每个 1 MB），用户可以直接附在问题反馈里。每行一条 JSON：时间、阶段、结果、耗时与附加信息。
+//! 附加信息写入前去掉密钥：供应商的 `settingsConfig` 整体省略，密钥字段替换，
+//! 其余文本再按 [`management_logs::redact`] 的规则处理。
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::services::management_logs;
+
+const FILE_NAME: &str = "management-sync.log";
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// 含当前文件
+const MAX_FILES: usize = 3;
+const REDACTED: &str = "[REDACTED]";
+
+static SYNC_LOG: Lazy<DiagnosticsLog> = Lazy::new(|| DiagnosticsLog::new(sync_log_path()));
+
+/// 当前诊断日志文件；较旧的为同目录下的 `management-sync.1.log`、`management-sync.2.log`
+pub fn sync_log_path() -> PathBuf {
+    crate::config::get_app_config_dir()
+        .join("logs")
+        .join(FILE_NAME)
+}
+
+/// 写入一条诊断记录；失败只记到主日志，不影响同步
+pub fn record(phase: &str, outcome: &str, duration_ms: Option<u64>, detail: &impl Serialize) {
+    // 测试不写用户目录
+    if cfg!(test) {
+        return;
+    }
+    if let Err(err) = SYNC_LOG.write_entry(phase, outcome, duration_ms, detail) {
+        log::warn!("Failed to write management sync diagnostics: {err}");
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry<'a> {
+    at: String,
+    phase: &'a str,
+    outcome: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+    detail: Value,
+}
+
+pub struct DiagnosticsLog {
+    path: PathBuf,
+    max_file_bytes: u64,
+    lock: Mutex<()>,
+}
+
+impl DiagnosticsLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_file_bytes: MAX_FILE_BYTES,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn write_entry(
+        &self,
+        phase: &str,
+        outcome: &str,
+        duration_ms: Option<u64>,
+        detail: &impl Serialize,
+    ) -> Result<(), AppError> {
+        let detail =
+            serde_json::to_value(detail).map_err(|source| AppError::JsonSerialize { source })?;
+        let entry = Entry {
+            at: chrono::Utc::now().to_rfc3339(),
+            phase,
+            outcome,
+            duration_ms,
+            detail: sanitize(detail),
+        };
+        let mut line =
+            serde_json::to_string(&entry).map_err(|source| AppError::JsonSerialize { source })?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|err| AppError::io(dir, err))?;
+        }
+        let size = fs::metadata(&self.path).map(|meta| meta.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > self.max_file_bytes {
+            self.rotate()?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|err| AppError::io(&self.path, err))
+    }
+
+    /// `management-sync.log` → `.1.log` → `.2.log`，最旧的一个丢弃
+    fn rotate(&self) -> Result<(), AppError> {
+        let oldest = self.rotated_path(MAX_FILES - 1);
+        if oldest.exists() {
+            fs::remove_file(&oldest).map_err(|err| AppError::io(&oldest, err))?;
+        }
+        for index in (1..MAX_FILES - 1).rev() {
+            rename_if_exists(&self.rotated_path(index), &self.rotated_path(index + 1))?;
+        }
+        rename_if_exists(&self.path, &self.rotated_path(1))
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.path.with_file_name(format!("{stem}.{index}.log"))
+    }
+}
+
+fn rename_if_exists(from: &Path, to: &Path) -> Result<(), AppError> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(AppError::io(from, err)),
+        _ => Ok(()),
+    }
+}
+
+fn sanitize(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = if value.is_null() {
+                        value
+                    } else if key == "settingsConfig" || is_secret_key(&key) {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        sanitize(value)
+                    };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(sanitize).collect()),
+        Value::String(text) => Value::String(management_logs::redact(&text, &[])),
+        other => other,
+    }
+}
+
+/// 驼峰与下划线写法均可，如 `apiKey`、`ANTHROPIC_AUTH_TOKEN`、`accessToken`
+fn is_secret_key(key: &str) -> bool {
+    let key = key.replace(['_', '-'], "").to_ascii_lowercase();
+    ["apikey", "secret", "password", "authorization"]
+        .iter()
+        .any(|needle| key.contains(needle))
+        || key.ends_with("token")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cc_switch_protocol::{
+        AppProviderSnapshot, AppliedVersions, DeviceConfigSnapshot, SyncRequest, PROTOCOL_VERSION,
+    };
+    use indexmap::IndexMap;
+    use serde_json::json;
+
+    fn log_in(dir: &Path) -> DiagnosticsLog {
+        DiagnosticsLog::new(dir.join(FILE_NAME))
+    }
+
+    fn read_lines(path: &Path) -> Vec<Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line is JSON"))
+            .collect()
+    }
+
+    fn sync_request() -> SyncRequest {
+        let provider = json!({
+            "id": "packycode",
+            "name": "PackyCode",
+            "settingsConfig": {
+                "env": {
+                    "ANTHROPIC_AUTH_TOKEN": "sk-ant-REDACTED",
+                    "ANTHROPIC_BASE_URL": "https://api.packycode.com",
+                }
+            },
+            "meta": {
+                "usage_script": { "apiKey": "usage-script-api-key-value", "accessToken": "usage-access-token-value" }
+            }
+        });
+        SyncRequest {
+            protocol_version: PROTOCOL_VERSION,
+            environment: Default::default(),
+            device_id: "device-1".to_string(),
+            fingerprint_hash: Some("fingerprint".to_string()),
+            app_version: Some("1.0.22".to_string()),
+            os: Some("linux".to_string()),
+            os_version: None,
+            arch: None,
+            hostname: None,
+            applied_admin_version: None,
+            applied_admin_versions: AppliedVersions::default(),
+            last_error: Some(
+                "401 from https://ex.com with Bearer sync-secret-bearer-token".to_string(),
+            ),
+            last_error_at: None,
+            last_error_partial: false,
+            last_error_admin_version: None,
+            rejected_admin_version: None,
+            rejected_at: None,
+            snapshot: Some(DeviceConfigSnapshot {
+                schema_version: 1,
+                claude: Some(AppProviderSnapshot {
+                    current_id: Some("packycode".to_string()),
+                    providers: IndexMap::from([("packycode".to_string(), provider)]),
+                }),
+                codex: None,
+                gemini: None,
+                mode: None,
+                privacy: None,
+                extras: None,
+                other_apps: IndexMap::new(),
+            }),
+            snapshot_truncated: false,
+            snapshot_unchanged: false,
+            client_time: None,
+        }
+    }
+
+    #[test]
+    fn sync_requests_are_logged_without_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = log_in(dir.path());
+
+        log.write_entry("request", "prepared", Some(12), &sync_request())
+            .unwrap();
+
+        let content = fs::read_to_string(dir.path().join(FILE_NAME)).unwrap();
+        for secret in [
+            "sk-ant-REDACTED",
+            "usage-script-api-key-value",
+            "usage-access-token-value",
+            "sync-secret-bearer-token",
+            "api.packycode.com",
+        ] {
+            assert!(!content.contains(secret), "{secret} leaked: {content}");
+        }
+
+        let entry = &read_lines(&dir.path().join(FILE_NAME))[0];
+        assert_eq!(entry["phase"], json!("request"));
+        assert_eq!(entry["outcome"], json!("prepared"));
+        assert_eq!(entry["durationMs"], json!(12));
+        assert!(entry["at"].is_string());
+        let provider = &entry["detail"]["snapshot"]["claude"]["providers"]["packycode"];
+        assert_eq!(provider["name"], json!("PackyCode"));
+        assert_eq!(provider["settingsConfig"], json!(REDACTED));
+        assert_eq!(provider["meta"]["usage_script"]["apiKey"], json!(REDACTED));
+        assert_eq!(entry["detail"]["deviceId"], json!("device-1"));
+    }
+
+    #[test]
+    fn rotates_into_at_most_three_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = log_in(dir.path());
+        log.max_file_bytes = 200;
+
+        for index in 0..20 {
+            log.write_entry("sync", "success", None, &json!({ "index": index }))
+                .unwrap();
+        }
+
+        let mut files: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "management-sync.1.log",
+                "management-sync.2.log",
+                "management-sync.log"
+            ]
+        );
+        for name in &files {
+            assert!(fs::metadata(dir.path().join(name)).unwrap().len() <= 200);
+        }
+        // 最新的记录在当前文件末尾，更旧的按编号递增
+        let current = read_lines(&dir.path().join(FILE_NAME));
+        assert_eq!(current.last().unwrap()["detail"]["index"], json!(19));
+        let previous = read_lines(&dir.path().join("management-sync.1.log"));
+        assert_eq!(
+            previous.last().unwrap()["detail"]["index"]
+                .as_i64()
+                .unwrap()
+                + 1,
+            current[0]["detail"]["index"].as_i64().unwrap()
+        );
+    }
+}
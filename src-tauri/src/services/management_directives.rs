@@ -0,0 +1,401 @@
+//! 同步响应中的服务器指令：封禁、等待批准、最低版本要求与服务器消息
+//!
+//! 指令状态按配置档保存在设置中，状态变化时通知界面；封禁与等待批准会改变下一次同步的时间，
+//! 由 [`handle`] 统一交回同步流程。
+
+use cc_switch_protocol::ServerDirectives;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+use tauri::{Emitter, Runtime};
+
+use crate::error::AppError;
+use crate::services::management_error::{SyncError, SyncErrorKind};
+use crate::services::management_profiles::setting_key;
+use crate::services::management_sync::{show_notification, MAX_RETRY_AFTER, MIN_RETRY_AFTER};
+
+/// 服务器封禁本设备的时间与原因（JSON）；封禁期间不再自动同步，解封后清空
+const SETTINGS_BLOCKED: &str = "management_blocked";
+/// 等待管理员批准本设备的状态（JSON）；批准后清空
+const SETTINGS_PENDING_APPROVAL: &str = "management_pending_approval";
+/// 服务器最近一次要求的最低应用版本；每个版本只通知一次
+const SETTINGS_UPGRADE_REQUIRED: &str = "management_upgrade_required";
+/// 已展示过的服务器消息 ID（JSON 数组，最新在后）
+pub const SETTINGS_SHOWN_MESSAGES: &str = "management_shown_messages";
+
+/// 记住的已展示服务器消息数量
+const SHOWN_MESSAGES_LIMIT: usize = 50;
+/// 等待批准时的首次复查间隔，之后每次加倍
+const PENDING_APPROVAL_RETRY_BASE: ChronoDuration = ChronoDuration::minutes(15);
+const PENDING_APPROVAL_RETRY_MAX: ChronoDuration = ChronoDuration::hours(6);
+
+pub const EVENT_BLOCKED_CHANGED: &str = "management-sync://blocked-changed";
+pub const EVENT_APPROVAL_CHANGED: &str = "management-sync://approval-changed";
+pub const EVENT_UPGRADE_REQUIRED: &str = "management-sync://upgrade-required";
+pub const EVENT_SERVER_MESSAGE: &str = "management-sync://server-message";
+
+/// 服务器尚未批准本设备
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingApproval {
+    pub since: String,
+    /// 已复查的次数，决定下一次复查的间隔
+    pub checks: u32,
+}
+
+/// 服务器封禁本设备的状态
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceBlocked {
+    /// 服务器给出的原因
+    pub message: Option<String>,
+    pub since: String,
+}
+
+/// 服务器消息事件的负载；同一 `id` 只推送一次
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerMessageEvent {
+    id: String,
+    message: String,
+}
+
+/// 处理一次同步响应中的指令；`retry_at` 为同步流程安排的下一次重试时间，指令可以推迟它
+///
+/// 设备被封禁时返回错误，本次同步到此为止。
+pub fn handle<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    db: &crate::database::Database,
+    directives: &ServerDirectives,
+    now: DateTime<Utc>,
+    retry_at: &mut Option<DateTime<Utc>>,
+) -> Result<(), SyncError> {
+    if let Some(secs) = directives.retry_after_secs {
+        log::info!("Management server asks to retry after {secs}s");
+        *retry_at = Some(directive_retry_at(secs, now));
+    }
+    update_blocked(app_handle, db, directives, now)?;
+    if directives.blocked {
+        return Err(SyncError::new(
+            SyncErrorKind::Unauthorized,
+            format!(
+                "Device is blocked by the management server{}",
+                directives
+                    .message
+                    .as_deref()
+                    .map(|message| format!(": {message}"))
+                    .unwrap_or_default()
+            ),
+        ));
+    }
+    if let Some(next_check) =
+        update_pending_approval(app_handle, db, directives.pending_approval, now)?
+    {
+        *retry_at = (*retry_at).max(Some(next_check));
+    }
+    update_upgrade_required(app_handle, db, directives.upgrade_required.as_deref())?;
+    show_server_message(app_handle, db, directives)?;
+    Ok(())
+}
+
+pub fn upgrade_required(db: &crate::database::Database) -> Result<Option<String>, AppError> {
+    Ok(db
+        .get_setting(&setting_key(SETTINGS_UPGRADE_REQUIRED))?
+        .filter(|value| !value.is_empty()))
+}
+
+pub fn get_blocked(db: &crate::database::Database) -> Option<DeviceBlocked> {
+    db.get_setting(&setting_key(SETTINGS_BLOCKED))
+        .ok()
+        .flatten()
+        .and_then(|text| serde_json::from_str(&text).ok())
+}
+
+/// 按服务器指令记录或解除封禁；状态变化时通知界面显示或撤下常驻提示
+fn update_blocked<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    db: &crate::database::Database,
+    directives: &ServerDirectives,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let previous = get_blocked(db);
+    let blocked = directives.blocked.then(|| DeviceBlocked {
+        message: directives.message.clone(),
+        since: previous
+            .as_ref()
+            .map(|blocked| blocked.since.clone())
+            .unwrap_or_else(|| now.to_rfc3339()),
+    });
+    if blocked == previous {
+        return Ok(());
+    }
+    let text = blocked
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|source| AppError::JsonSerialize { source })?
+        .unwrap_or_default();
+    db.set_setting(&setting_key(SETTINGS_BLOCKED), &text)?;
+    match &blocked {
+        Some(_) => log::warn!("Device is blocked by the management server; automatic sync is off"),
+        None => log::info!("Device is no longer blocked by the management server"),
+    }
+    if let Err(err) = app_handle.emit(EVENT_BLOCKED_CHANGED, &blocked) {
+        log::warn!("Failed to emit management blocked event: {err}");
+    }
+    Ok(())
+}
+
+pub fn get_pending_approval(db: &crate::database::Database) -> Option<PendingApproval> {
+    db.get_setting(&setting_key(SETTINGS_PENDING_APPROVAL))
+        .ok()
+        .flatten()
+        .and_then(|text| serde_json::from_str(&text).ok())
+}
+
+/// 记录是否仍在等待批准，返回下一次复查时间：从 [`PENDING_APPROVAL_RETRY_BASE`] 起每次加倍，
+/// 最长 [`PENDING_APPROVAL_RETRY_MAX`]；开始与结束等待时通知界面
+fn update_pending_approval<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    db: &crate::database::Database,
+    pending: bool,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let previous = get_pending_approval(db);
+    if !pending {
+        if previous.is_some() {
+            db.set_setting(&setting_key(SETTINGS_PENDING_APPROVAL), "")?;
+            log::info!("Device was approved by the management server");
+            if let Err(err) = app_handle.emit(EVENT_APPROVAL_CHANGED, None::<PendingApproval>) {
+                log::warn!("Failed to emit management approval event: {err}");
+            }
+        }
+        return Ok(None);
+    }
+
+    let approval = match &previous {
+        Some(previous) => PendingApproval {
+            since: previous.since.clone(),
+            checks: previous.checks.saturating_add(1),
+        },
+        None => PendingApproval {
+            since: now.to_rfc3339(),
+            checks: 0,
+        },
+    };
+    let text =
+        serde_json::to_string(&approval).map_err(|source| AppError::JsonSerialize { source })?;
+    db.set_setting(&setting_key(SETTINGS_PENDING_APPROVAL), &text)?;
+    if previous.is_none() {
+        log::info!("Device is waiting for approval by the management server");
+        if let Err(err) = app_handle.emit(EVENT_APPROVAL_CHANGED, Some(&approval)) {
+            log::warn!("Failed to emit management approval event: {err}");
+        }
+    }
+
+    let delay = PENDING_APPROVAL_RETRY_BASE * 2i32.saturating_pow(approval.checks.min(16));
+    Ok(Some(now + delay.min(PENDING_APPROVAL_RETRY_MAX)))
+}
+
+/// 记录服务器要求的最低版本；出现新的版本要求时通知用户升级
+fn update_upgrade_required<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    db: &crate::database::Database,
+    version: Option<&str>,
+) -> Result<(), AppError> {
+    let key = setting_key(SETTINGS_UPGRADE_REQUIRED);
+    let previous = db.get_setting(&key)?.unwrap_or_default();
+    let version = version.unwrap_or_default();
+    if version == previous {
+        return Ok(());
+    }
+    db.set_setting(&key, version)?;
+    if version.is_empty() {
+        return Ok(());
+    }
+
+    log::warn!("Management server requires app version {version} or newer");
+    if let Err(err) = app_handle.emit(
+        EVENT_UPGRADE_REQUIRED,
+        serde_json::json!({ "version": version }),
+    ) {
+        log::warn!("Failed to emit upgrade required event: {err}");
+    }
+    show_notification(app_handle, db, |language| {
+        match language {
+        "en" => (
+            "Update required",
+            format!("Your administrator requires AI Code With {version} or newer. Please update."),
+        ),
+        "ja" => (
+            "アップデートが必要です",
+            format!("管理者は AI Code With {version} 以降を必要としています。アップデートしてください。"),
+        ),
+        _ => (
+            "需要更新",
+            format!("管理员要求使用 AI Code With {version} 或更新版本，请尽快更新。"),
+        ),
+    }
+    });
+    Ok(())
+}
+
+/// 服务器消息按 ID 只展示一次；没有 ID 时以消息内容的哈希作为 ID
+fn show_server_message<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    db: &crate::database::Database,
+    directives: &ServerDirectives,
+) -> Result<(), AppError> {
+    let Some(message) = directives
+        .message
+        .as_deref()
+        .filter(|text| !text.is_empty())
+    else {
+        return Ok(());
+    };
+    let id = directives
+        .message_id
+        .clone()
+        .unwrap_or_else(|| Sha256::digest(message.as_bytes()).encode_hex());
+    let key = setting_key(SETTINGS_SHOWN_MESSAGES);
+    let mut shown: Vec<String> = db
+        .get_setting(&key)?
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    if shown.contains(&id) {
+        return Ok(());
+    }
+    shown.push(id.clone());
+    let excess = shown.len().saturating_sub(SHOWN_MESSAGES_LIMIT);
+    shown.drain(..excess);
+    let text =
+        serde_json::to_string(&shown).map_err(|source| AppError::JsonSerialize { source })?;
+    db.set_setting(&key, &text)?;
+
+    log::info!("Management server message: {message}");
+    let event = ServerMessageEvent {
+        id,
+        message: message.to_string(),
+    };
+    if let Err(err) = app_handle.emit(EVENT_SERVER_MESSAGE, &event) {
+        log::warn!("Failed to emit server message event: {err}");
+    }
+    show_notification(app_handle, db, |language| {
+        let title = match language {
+            "en" => "Message from your administrator",
+            "ja" => "管理者からのお知らせ",
+            _ => "管理员消息",
+        };
+        (title, event.message.clone())
+    });
+    Ok(())
+}
+
+/// 服务器指令中的 `retryAfterSecs`，与 `Retry-After` 一样限制在合理范围内
+fn directive_retry_at(secs: u64, now: DateTime<Utc>) -> DateTime<Utc> {
+    let delay = i64::try_from(secs)
+        .ok()
+        .and_then(ChronoDuration::try_seconds)
+        .unwrap_or(MAX_RETRY_AFTER);
+    now + delay.clamp(MIN_RETRY_AFTER, MAX_RETRY_AFTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tauri::Manager;
+
+    use crate::store::AppState;
+
+    fn mock_app() -> tauri::App<tauri::test::MockRuntime> {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::new(Arc::new(
+            crate::database::Database::memory().expect("memory db"),
+        )));
+        app
+    }
+
+    #[test]
+    fn directive_retry_is_clamped_like_retry_after() {
+        let now = Utc::now();
+        assert_eq!(directive_retry_at(1, now), now + MIN_RETRY_AFTER);
+        assert_eq!(
+            directive_retry_at(120, now),
+            now + ChronoDuration::seconds(120)
+        );
+        assert_eq!(directive_retry_at(u64::MAX, now), now + MAX_RETRY_AFTER);
+    }
+
+    #[test]
+    fn pending_approval_checks_back_off_until_approved() {
+        let app = mock_app();
+        let db = &app.state::<AppState>().db;
+        let now = Utc::now();
+
+        let delays: Vec<_> = (0..3)
+            .map(|_| {
+                update_pending_approval(app.handle(), db, true, now)
+                    .unwrap()
+                    .map(|at| at - now)
+            })
+            .collect();
+        assert_eq!(
+            delays,
+            [1, 2, 4].map(|factor| Some(PENDING_APPROVAL_RETRY_BASE * factor))
+        );
+        let pending = get_pending_approval(db).expect("pending");
+        assert_eq!(pending.since, now.to_rfc3339());
+        assert_eq!(pending.checks, 2);
+
+        for _ in 0..20 {
+            update_pending_approval(app.handle(), db, true, now).unwrap();
+        }
+        assert_eq!(
+            update_pending_approval(app.handle(), db, true, now).unwrap(),
+            Some(now + PENDING_APPROVAL_RETRY_MAX)
+        );
+
+        assert_eq!(
+            update_pending_approval(app.handle(), db, false, now).unwrap(),
+            None
+        );
+        assert!(get_pending_approval(db).is_none());
+    }
+
+    #[test]
+    fn blocked_devices_keep_their_original_block_time() {
+        let app = mock_app();
+        let db = &app.state::<AppState>().db;
+        let first = Utc::now();
+        let blocked = ServerDirectives {
+            blocked: true,
+            message: Some("lost laptop".to_string()),
+            ..ServerDirectives::default()
+        };
+        let mut retry_at = None;
+
+        let err = handle(app.handle(), db, &blocked, first, &mut retry_at).unwrap_err();
+        assert!(err.to_string().contains("lost laptop"), "{err}");
+        let later = first + ChronoDuration::hours(1);
+        handle(app.handle(), db, &blocked, later, &mut retry_at).unwrap_err();
+        assert_eq!(
+            get_blocked(db),
+            Some(DeviceBlocked {
+                message: Some("lost laptop".to_string()),
+                since: first.to_rfc3339(),
+            })
+        );
+
+        handle(
+            app.handle(),
+            db,
+            &ServerDirectives::default(),
+            later,
+            &mut retry_at,
+        )
+        .unwrap();
+        assert!(get_blocked(db).is_none());
+        assert_eq!(retry_at, None);
+    }
+}
@@ -0,0 +1,161 @@
+//! 管理同步的结构化错误
+//!
+//! 每个错误带一个稳定的类别，前端按 `i18nKey` 本地化提示；`detail` 保留原始英文信息，
+//! 写入诊断日志与同步历史，便于支持人员排查。
+
+use std::fmt;
+
+use crate::error::AppError;
+use crate::services::management_tls;
+
+/// 同步失败的类别
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(
+    tag = "code",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SyncErrorKind {
+    /// 连接失败，包括 DNS、TLS 与证书固定校验失败
+    Unreachable,
+    /// 连接或请求超时
+    Timeout,
+    /// 令牌被拒绝或设备被封禁
+    Unauthorized,
+    /// 服务器返回非成功状态，含限流与维护
+    ServerError { status: u16 },
+    /// 服务器响应无法解析
+    ParseError,
+    /// 管理员配置应用到某个应用时失败
+    ApplyFailed { app: String, reason: String },
+    /// 管理员配置的签名无效，或要求签名时缺少签名，已拒绝应用
+    InvalidSignature,
+    /// 用户关闭了管理同步
+    Disabled,
+    /// 应用退出，同步中途取消
+    Cancelled,
+    /// 距上次手动同步太近，需等待后再试
+    TooSoon { retry_after_secs: u64 },
+    /// 本地读写等其他错误
+    Other,
+}
+
+impl SyncErrorKind {
+    pub fn i18n_key(&self) -> &'static str {
+        match self {
+            Self::Unreachable => "managementSync.errors.unreachable",
+            Self::Timeout => "managementSync.errors.timeout",
+            Self::Unauthorized => "managementSync.errors.unauthorized",
+            Self::ServerError { .. } => "managementSync.errors.serverError",
+            Self::ParseError => "managementSync.errors.parseError",
+            Self::ApplyFailed { .. } => "managementSync.errors.applyFailed",
+            Self::InvalidSignature => "managementSync.errors.invalidSignature",
+            Self::Disabled => "managementSync.errors.disabled",
+            Self::Cancelled => "managementSync.errors.cancelled",
+            Self::TooSoon { .. } => "managementSync.errors.tooSoon",
+            Self::Other => "managementSync.errors.other",
+        }
+    }
+}
+
+/// 一次同步失败：类别、对应的 i18n 键与原始信息
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncError {
+    #[serde(flatten)]
+    pub kind: SyncErrorKind,
+    pub i18n_key: String,
+    pub detail: String,
+}
+
+impl SyncError {
+    pub fn new(kind: SyncErrorKind, detail: impl Into<String>) -> Self {
+        Self {
+            i18n_key: kind.i18n_key().to_string(),
+            kind,
+            detail: detail.into(),
+        }
+    }
+
+    /// 证书固定不匹配、TLS 失败、超时和连接失败分别提示，便于状态页与其他失败区分
+    pub fn from_request(err: reqwest::Error) -> Self {
+        if management_tls::is_pin_mismatch(&err) {
+            Self::new(
+                SyncErrorKind::Unreachable,
+                format!("Management server certificate does not match the pinned key: {err}"),
+            )
+        } else if err.is_connect() && management_tls::is_tls_error(&err) {
+            Self::new(
+                SyncErrorKind::Unreachable,
+                format!("Management server TLS verification failed: {err}"),
+            )
+        } else if err.is_timeout() {
+            Self::new(
+                SyncErrorKind::Timeout,
+                format!("Management server unreachable (timed out): {err}"),
+            )
+        } else if err.is_connect() {
+            Self::new(
+                SyncErrorKind::Unreachable,
+                format!("Management server unreachable: {err}"),
+            )
+        } else {
+            Self::new(SyncErrorKind::Other, format!("Sync request failed: {err}"))
+        }
+    }
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.detail)
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+impl From<AppError> for SyncError {
+    fn from(err: AppError) -> Self {
+        Self::new(SyncErrorKind::Other, err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_is_flattened_next_to_the_detail() {
+        let err = SyncError::new(SyncErrorKind::ServerError { status: 502 }, "Bad Gateway");
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "serverError",
+                "status": 502,
+                "i18nKey": "managementSync.errors.serverError",
+                "detail": "Bad Gateway",
+            })
+        );
+        assert_eq!(serde_json::from_value::<SyncError>(value).unwrap(), err);
+        assert_eq!(err.to_string(), "Bad Gateway");
+
+        // 变体内的字段同样改为驼峰，与前端的插值参数一致
+        let err = SyncError::new(
+            SyncErrorKind::TooSoon {
+                retry_after_secs: 3,
+            },
+            "Too soon",
+        );
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "tooSoon",
+                "retryAfterSecs": 3,
+                "i18nKey": "managementSync.errors.tooSoon",
+                "detail": "Too soon",
+            })
+        );
+        assert_eq!(serde_json::from_value::<SyncError>(value).unwrap(), err);
+    }
+}
@@ -0,0 +1,369 @@
+//! 管理同步中供应商以外的配置：MCP 服务器与通用配置片段
+//!
+//! 上传需要在设置中开启（默认关闭）；管理员配置中带有这部分时总会应用。与供应商一样，
+//! 写入前先校验整份配置，只改动有差异的条目，替换模式下删除本地多出的条目。
+
+use std::str::FromStr;
+
+use cc_switch_protocol::{ApplyMode, ConfigExtras};
+use indexmap::IndexMap;
+
+use crate::app_config::{AppType, McpServer};
+use crate::error::AppError;
+use crate::services::management_privacy::{self, Protector};
+use crate::services::McpService;
+use crate::store::AppState;
+
+/// 应用结果与已应用版本中这部分配置使用的名称
+pub const SECTION: &str = "extras";
+
+const SNIPPET_APPS: [AppType; 3] = [AppType::Claude, AppType::Codex, AppType::Gemini];
+
+/// 当前的全部 MCP 服务器与非空的通用配置片段
+pub fn collect(state: &AppState) -> Result<ConfigExtras, AppError> {
+    let mut servers = IndexMap::new();
+    for (id, server) in McpService::get_all_servers(state)? {
+        let value =
+            serde_json::to_value(&server).map_err(|source| AppError::JsonSerialize { source })?;
+        servers.insert(id, value);
+    }
+    let mut snippets = IndexMap::new();
+    for app_type in SNIPPET_APPS {
+        if let Some(snippet) = state
+            .db
+            .get_config_snippet(app_type.as_str())?
+            .filter(|snippet| !snippet.trim().is_empty())
+        {
+            snippets.insert(app_type.as_str().to_string(), snippet);
+        }
+    }
+    Ok(ConfigExtras {
+        mcp_servers: Some(servers),
+        common_config_snippets: Some(snippets),
+    })
+}
+
+/// 按隐私级别处理上传内容中的密钥
+pub fn protect(extras: &mut ConfigExtras, protector: &Protector<'_>) -> Result<(), AppError> {
+    for server in extras
+        .mcp_servers
+        .iter_mut()
+        .flat_map(|servers| servers.values_mut())
+    {
+        management_privacy::protect_mcp_server(server, protector)?;
+    }
+    for (app, snippet) in extras
+        .common_config_snippets
+        .iter_mut()
+        .flat_map(|snippets| snippets.iter_mut())
+    {
+        if let Ok(app_type) = AppType::from_str(app) {
+            *snippet = management_privacy::protect_config_snippet(&app_type, snippet, protector)?;
+        }
+    }
+    Ok(())
+}
+
+/// 下发的 MCP 服务器数量
+pub fn server_count(extras: &ConfigExtras) -> usize {
+    extras.mcp_servers.as_ref().map_or(0, IndexMap::len)
+}
+
+/// 应用下发的配置；`written` 记录是否已改动本地，中途失败时据此判断是否处于半应用状态
+pub fn apply(
+    state: &AppState,
+    extras: ConfigExtras,
+    mode: ApplyMode,
+    written: &mut bool,
+) -> Result<(), AppError> {
+    // 必须在动本地状态之前确认整份配置都能写入
+    let servers = extras.mcp_servers.map(validate_servers).transpose()?;
+    let snippets = extras
+        .common_config_snippets
+        .map(validate_snippets)
+        .transpose()?;
+
+    if let Some(servers) = servers {
+        apply_servers(state, servers, mode, written)?;
+    }
+    if let Some(snippets) = snippets {
+        apply_snippets(state, snippets, mode, written)?;
+    }
+    Ok(())
+}
+
+fn validate_servers(
+    servers: IndexMap<String, serde_json::Value>,
+) -> Result<IndexMap<String, McpServer>, AppError> {
+    servers
+        .into_iter()
+        .map(|(id, value)| {
+            let server: McpServer = serde_json::from_value(value)
+                .map_err(|err| AppError::InvalidInput(format!("Invalid MCP server {id}: {err}")))?;
+            if server.id != id {
+                return Err(AppError::InvalidInput(format!(
+                    "MCP server {id} has mismatched id {}",
+                    server.id
+                )));
+            }
+            if !server.server.is_object() {
+                return Err(AppError::InvalidInput(format!(
+                    "MCP server {id} has no server definition"
+                )));
+            }
+            Ok((id, server))
+        })
+        .collect()
+}
+
+/// 空字符串表示清除该应用的片段；不认识的应用跳过
+fn validate_snippets(
+    snippets: IndexMap<String, String>,
+) -> Result<Vec<(AppType, Option<String>)>, AppError> {
+    let mut valid = Vec::new();
+    for (app, snippet) in snippets {
+        let Ok(app_type) = AppType::from_str(&app) else {
+            log::warn!("Skipping common config snippet for unrecognized app {app}");
+            continue;
+        };
+        if snippet.trim().is_empty() {
+            valid.push((app_type, None));
+            continue;
+        }
+        let parsed = match app_type {
+            AppType::Claude | AppType::Gemini => {
+                serde_json::from_str::<serde_json::Value>(&snippet)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }
+            AppType::Codex => toml::from_str::<toml::Table>(&snippet)
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+        };
+        if let Err(err) = parsed {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid common config snippet for {app}: {err}"
+            )));
+        }
+        valid.push((app_type, Some(snippet)));
+    }
+    Ok(valid)
+}
+
+fn apply_servers(
+    state: &AppState,
+    servers: IndexMap<String, McpServer>,
+    mode: ApplyMode,
+    written: &mut bool,
+) -> Result<(), AppError> {
+    let local = McpService::get_all_servers(state)?;
+    let same = |id: &str, server: &McpServer| {
+        local.get(id).is_some_and(|existing| {
+            serde_json::to_value(existing).ok() == serde_json::to_value(server).ok()
+        })
+    };
+    if mode == ApplyMode::Replace {
+        for id in local.keys().filter(|id| !servers.contains_key(*id)) {
+            *written = true;
+            McpService::delete_server(state, id)?;
+        }
+    }
+    for (id, server) in servers {
+        if same(&id, &server) {
+            continue;
+        }
+        if mode == ApplyMode::Merge && local.contains_key(&id) {
+            log::warn!("Admin config overwrites local MCP server {id}");
+        }
+        *written = true;
+        McpService::upsert_server(state, server)?;
+    }
+    Ok(())
+}
+
+fn apply_snippets(
+    state: &AppState,
+    snippets: Vec<(AppType, Option<String>)>,
+    mode: ApplyMode,
+    written: &mut bool,
+) -> Result<(), AppError> {
+    let mut wanted: Vec<(AppType, Option<String>)> = Vec::new();
+    if mode == ApplyMode::Replace {
+        // 替换模式下未下发的应用视为清除
+        for app_type in SNIPPET_APPS {
+            if !snippets.iter().any(|(app, _)| *app == app_type) {
+                wanted.push((app_type, None));
+            }
+        }
+    }
+    wanted.extend(snippets);
+
+    for (app_type, snippet) in wanted {
+        let local = state
+            .db
+            .get_config_snippet(app_type.as_str())?
+            .filter(|value| !value.trim().is_empty());
+        if local == snippet {
+            continue;
+        }
+        *written = true;
+        state.db.set_config_snippet(app_type.as_str(), snippet)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    /// 各应用都未启用，不会写入各 CLI 的 live 配置
+    fn server(id: &str, command: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "name": id,
+            "server": { "type": "stdio", "command": command },
+            "apps": { "claude": false, "codex": false, "gemini": false }
+        })
+    }
+
+    fn state() -> AppState {
+        AppState::new(Arc::new(
+            crate::database::Database::memory().expect("init db"),
+        ))
+    }
+
+    fn extras(servers: &[(&str, &str)], snippets: &[(&str, &str)]) -> ConfigExtras {
+        ConfigExtras {
+            mcp_servers: Some(
+                servers
+                    .iter()
+                    .map(|(id, command)| (id.to_string(), server(id, command)))
+                    .collect(),
+            ),
+            common_config_snippets: Some(
+                snippets
+                    .iter()
+                    .map(|(app, snippet)| (app.to_string(), snippet.to_string()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn server_ids(state: &AppState) -> Vec<String> {
+        McpService::get_all_servers(state)
+            .unwrap()
+            .into_keys()
+            .collect()
+    }
+
+    #[test]
+    fn applies_replaces_and_merges_extras() {
+        let state = state();
+        let mut written = false;
+        apply(
+            &state,
+            extras(
+                &[("fetch", "uvx"), ("local", "node")],
+                &[("codex", "model = \"gpt-5\"\n")],
+            ),
+            ApplyMode::Replace,
+            &mut written,
+        )
+        .unwrap();
+        assert!(written);
+        assert_eq!(server_ids(&state), ["fetch", "local"]);
+        let collected = collect(&state).unwrap();
+        assert_eq!(server_count(&collected), 2);
+        assert_eq!(
+            collected.common_config_snippets,
+            Some(IndexMap::from([(
+                "codex".to_string(),
+                "model = \"gpt-5\"\n".to_string()
+            )]))
+        );
+
+        // 内容一致时不改动本地
+        let mut written = false;
+        apply(&state, collected, ApplyMode::Replace, &mut written).unwrap();
+        assert!(!written);
+
+        // 合并只增改下发的条目
+        apply(
+            &state,
+            extras(&[("fetch", "npx")], &[("claude", r#"{"model":"opus"}"#)]),
+            ApplyMode::Merge,
+            &mut written,
+        )
+        .unwrap();
+        assert_eq!(server_ids(&state), ["fetch", "local"]);
+        assert_eq!(
+            McpService::get_all_servers(&state).unwrap()["fetch"].server["command"],
+            json!("npx")
+        );
+        assert!(state.db.get_config_snippet("codex").unwrap().is_some());
+
+        // 替换删除本地多出的服务器与片段
+        apply(
+            &state,
+            extras(&[("fetch", "npx")], &[]),
+            ApplyMode::Replace,
+            &mut written,
+        )
+        .unwrap();
+        assert_eq!(server_ids(&state), ["fetch"]);
+        assert_eq!(
+            collect(&state).unwrap().common_config_snippets,
+            Some(IndexMap::new())
+        );
+    }
+
+    #[test]
+    fn invalid_extras_change_nothing() {
+        let state = state();
+        let mut written = false;
+        apply(
+            &state,
+            extras(&[("fetch", "uvx")], &[]),
+            ApplyMode::Replace,
+            &mut written,
+        )
+        .unwrap();
+
+        let mut mismatched = extras(&[("other", "node")], &[]);
+        mismatched
+            .mcp_servers
+            .as_mut()
+            .unwrap()
+            .insert("renamed".to_string(), server("fetch", "node"));
+        let mut written = false;
+        let err = apply(&state, mismatched, ApplyMode::Replace, &mut written).unwrap_err();
+        assert!(err.to_string().contains("mismatched id"), "{err}");
+
+        let err = apply(
+            &state,
+            extras(&[], &[("claude", "{not json")]),
+            ApplyMode::Replace,
+            &mut written,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("claude"), "{err}");
+        assert!(!written);
+        assert_eq!(server_ids(&state), ["fetch"]);
+    }
+
+    #[test]
+    fn protect_masks_uploaded_extras() {
+        let mut extras = extras(&[], &[("claude", r#"{"env":{"API_KEY":"sk-0123456789"}}"#)]);
+        extras.mcp_servers.as_mut().unwrap().insert(
+            "github".to_string(),
+            json!({ "id": "github", "server": { "env": { "GITHUB_TOKEN": "ghp_0123456789" } } }),
+        );
+        protect(&mut extras, &Protector::Mask).unwrap();
+
+        let uploaded = serde_json::to_string(&extras).unwrap();
+        assert!(!uploaded.contains("0123456789"), "{uploaded}");
+        assert!(uploaded.contains("****6789"));
+    }
+}
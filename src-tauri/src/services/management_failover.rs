@@ -0,0 +1,401 @@
+//! 管理服务器的地址与令牌故障转移
+//!
+//! 主地址不可用时依次尝试设置中的备用地址，并记住最近可用的地址；令牌被拒时换下一个令牌，
+//! 服务器轮换令牌期间新旧客户端都能继续同步。
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+use crate::services::management_profiles::setting_key;
+use crate::services::management_sync::{
+    allow_insecure, compiled_tokens, flush_offline_queue, management_base_url, send_sync_body,
+    validate_management_url, SETTINGS_ACTIVE_ENDPOINT, SETTINGS_FALLBACK_URLS,
+    SETTINGS_TOKEN_OVERRIDE,
+};
+
+const SETTINGS_PRIMARY_PROBED_AT: &str = "management_primary_probed_at";
+/// 最近被服务器接受的同步令牌的 SHA-256（不保存令牌本身）
+const SETTINGS_ACTIVE_TOKEN: &str = "management_active_token";
+
+/// 备用地址在主地址连续不可用时才会被优先使用，期间每隔这么久重新先试一次主地址
+const PRIMARY_REPROBE: ChronoDuration = ChronoDuration::hours(6);
+
+pub(crate) fn fallback_urls(db: &crate::database::Database) -> Vec<String> {
+    db.get_setting(&setting_key(SETTINGS_FALLBACK_URLS))
+        .ok()
+        .flatten()
+        .filter(|value| !value.is_empty())
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// 主地址在前，随后是设置中的备用地址（去重，跳过已失效的条目）
+fn management_endpoints(db: &crate::database::Database) -> Result<Vec<String>, AppError> {
+    let primary = management_base_url(db)?
+        .url
+        .trim_end_matches('/')
+        .to_string();
+    let allow_insecure = allow_insecure(db);
+    let mut endpoints = vec![primary];
+    for url in fallback_urls(db) {
+        if endpoints.contains(&url) {
+            continue;
+        }
+        match validate_management_url(&url, allow_insecure) {
+            Ok(()) => endpoints.push(url),
+            Err(err) => log::warn!("Skipping management fallback URL: {err}"),
+        }
+    }
+    Ok(endpoints)
+}
+
+/// 上次可用的备用地址排到最前；到了重新探测主地址的时间则保持原顺序
+fn order_endpoints(
+    mut endpoints: Vec<String>,
+    active: Option<&str>,
+    probe_due: bool,
+) -> Vec<String> {
+    if probe_due {
+        return endpoints;
+    }
+    if let Some(index) = active
+        .and_then(|active| endpoints.iter().position(|url| url == active))
+        .filter(|&index| index > 0)
+    {
+        let preferred = endpoints.remove(index);
+        endpoints.insert(0, preferred);
+    }
+    endpoints
+}
+
+pub(crate) fn ordered_endpoints(db: &crate::database::Database) -> Result<Vec<String>, AppError> {
+    let endpoints = management_endpoints(db)?;
+    let active = db
+        .get_setting(&setting_key(SETTINGS_ACTIVE_ENDPOINT))
+        .ok()
+        .flatten()
+        .filter(|url| endpoints.iter().skip(1).any(|fallback| fallback == url));
+    let Some(active) = active else {
+        return Ok(endpoints);
+    };
+    let probe_due = primary_probed_at(db).is_none_or(|at| Utc::now() - at >= PRIMARY_REPROBE);
+    if probe_due {
+        log::info!("Re-probing the primary management endpoint before fallback {active}");
+        if let Err(err) = db.set_setting(
+            &setting_key(SETTINGS_PRIMARY_PROBED_AT),
+            &Utc::now().to_rfc3339(),
+        ) {
+            log::warn!("Failed to save management primary probe time: {err}");
+        }
+    }
+    Ok(order_endpoints(endpoints, Some(&active), probe_due))
+}
+
+fn primary_probed_at(db: &crate::database::Database) -> Option<DateTime<Utc>> {
+    db.get_setting(&setting_key(SETTINGS_PRIMARY_PROBED_AT))
+        .ok()
+        .flatten()
+        .and_then(|text| DateTime::parse_from_rfc3339(&text).ok())
+        .map(|value| value.with_timezone(&Utc))
+}
+
+/// 记住可用的地址；刚切换到备用地址时从现在开始计算重新探测主地址的间隔
+fn remember_endpoint(db: &crate::database::Database, url: &str, is_primary: bool) {
+    let previous = db
+        .get_setting(&setting_key(SETTINGS_ACTIVE_ENDPOINT))
+        .ok()
+        .flatten();
+    if previous.as_deref() == Some(url) {
+        return;
+    }
+    if !is_primary {
+        log::warn!("Management sync switched to fallback endpoint {url}");
+        if let Err(err) = db.set_setting(
+            &setting_key(SETTINGS_PRIMARY_PROBED_AT),
+            &Utc::now().to_rfc3339(),
+        ) {
+            log::warn!("Failed to save management primary probe time: {err}");
+        }
+    }
+    if let Err(err) = db.set_setting(&setting_key(SETTINGS_ACTIVE_ENDPOINT), url) {
+        log::warn!("Failed to save management active endpoint: {err}");
+    }
+}
+
+/// 依次用各个令牌发送，令牌被拒（401）时换下一个；返回最终使用的令牌与发送结果
+///
+/// 服务器轮换令牌前后，新旧客户端都能继续同步；被接受的令牌记下来，之后优先使用。
+pub(crate) async fn send_with_tokens<'a>(
+    db: &crate::database::Database,
+    client: &reqwest::Client,
+    endpoints: &[String],
+    tokens: &[&'a str],
+    body: &[u8],
+) -> Result<
+    (
+        &'a str,
+        (
+            String,
+            Result<(reqwest::Response, Option<usize>), reqwest::Error>,
+        ),
+    ),
+    AppError,
+> {
+    for (index, token) in tokens.iter().enumerate() {
+        let sent = send_with_failover(db, client, endpoints, token, body).await?;
+        let rejected = matches!(
+            &sent.1,
+            Ok((response, _)) if response.status() == reqwest::StatusCode::UNAUTHORIZED
+        );
+        if rejected && index + 1 < tokens.len() {
+            log::warn!(
+                "Management server rejected sync token #{}; retrying with the next one",
+                index + 1
+            );
+            continue;
+        }
+        if !rejected {
+            remember_token(db, token);
+        }
+        return Ok((token, sent));
+    }
+    Err(AppError::Message(
+        "Management token is empty at build time".to_string(),
+    ))
+}
+
+/// 配置档设置了令牌时只用它；否则用构建时编入的令牌，最近被接受的排在最前
+pub(crate) fn ordered_tokens(db: &crate::database::Database) -> Vec<String> {
+    if let Some(token) = token_override(db) {
+        return vec![token];
+    }
+    let tokens = compiled_tokens().iter().map(String::as_str).collect();
+    let active = db
+        .get_setting(&setting_key(SETTINGS_ACTIVE_TOKEN))
+        .ok()
+        .flatten();
+    order_tokens(tokens, active.as_deref())
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+pub(crate) fn token_override(db: &crate::database::Database) -> Option<String> {
+    db.get_setting(&setting_key(SETTINGS_TOKEN_OVERRIDE))
+        .ok()
+        .flatten()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+fn order_tokens<'a>(mut tokens: Vec<&'a str>, active: Option<&str>) -> Vec<&'a str> {
+    let position = active.and_then(|active| {
+        tokens
+            .iter()
+            .position(|token| token_fingerprint(token) == active)
+    });
+    if let Some(position) = position {
+        let token = tokens.remove(position);
+        tokens.insert(0, token);
+    }
+    tokens
+}
+
+fn token_fingerprint(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).encode_hex()
+}
+
+fn remember_token(db: &crate::database::Database, token: &str) {
+    let fingerprint = token_fingerprint(token);
+    if db
+        .get_setting(&setting_key(SETTINGS_ACTIVE_TOKEN))
+        .ok()
+        .flatten()
+        .as_deref()
+        == Some(&fingerprint)
+    {
+        return;
+    }
+    if let Err(err) = db.set_setting(&setting_key(SETTINGS_ACTIVE_TOKEN), &fingerprint) {
+        log::warn!("Failed to save the accepted management token: {err}");
+    }
+}
+
+/// 连接失败（含 TLS 与超时）或 5xx 时换下一个地址
+fn should_fail_over(result: &Result<(reqwest::Response, Option<usize>), reqwest::Error>) -> bool {
+    match result {
+        Ok((response, _)) => response.status().is_server_error(),
+        Err(err) => err.is_connect() || err.is_timeout(),
+    }
+}
+
+/// 依次在各地址上补发离线队列并发送本次同步，返回最终使用的地址与结果
+///
+/// 令牌与请求体在各地址间完全相同；最后一个地址的失败原样返回给调用方处理。
+async fn send_with_failover(
+    db: &crate::database::Database,
+    client: &reqwest::Client,
+    endpoints: &[String],
+    token: &str,
+    body: &[u8],
+) -> Result<
+    (
+        String,
+        Result<(reqwest::Response, Option<usize>), reqwest::Error>,
+    ),
+    AppError,
+> {
+    let primary = endpoints.first().cloned();
+    let mut last = None;
+    for base_url in endpoints {
+        let endpoint = format!("{base_url}/api/v1/devices/sync");
+        // 先补发离线期间积压的快照（最旧在前）
+        let result = match flush_offline_queue(db, client, &endpoint, token).await {
+            Ok(()) => send_sync_body(db, client, &endpoint, token, body).await?,
+            Err(err) => Err(err),
+        };
+        if !should_fail_over(&result) {
+            remember_endpoint(db, base_url, primary.as_deref() == Some(base_url.as_str()));
+            return Ok((base_url.clone(), result));
+        }
+        match &result {
+            Ok((response, _)) => log::warn!(
+                "Management endpoint {base_url} returned {}",
+                response.status()
+            ),
+            Err(err) => {
+                log::warn!("Management endpoint {base_url} unreachable: {err}")
+            }
+        }
+        last = Some((base_url.clone(), result));
+    }
+    last.ok_or_else(|| AppError::Message("No management endpoint configured".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const UNAUTHORIZED: &str =
+        "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    /// 依次为每个连接返回一个固定的 HTTP 响应，返回服务器的根地址
+    fn mock_server(responses: Vec<&'static str>) -> String {
+        use std::io::Read;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for response in responses {
+                if let Ok((mut stream, _)) = listener.accept() {
+                    let mut buffer = [0u8; 4096];
+                    let _ = stream.read(&mut buffer);
+                    let _ = stream.write_all(response.as_bytes());
+                }
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn fallback_endpoint_is_preferred_until_primary_reprobe() {
+        let endpoints = vec![
+            "https://primary.example.com".to_string(),
+            "https://backup-1.example.com".to_string(),
+            "https://backup-2.example.com".to_string(),
+        ];
+        assert_eq!(
+            order_endpoints(
+                endpoints.clone(),
+                Some("https://backup-2.example.com"),
+                false
+            ),
+            vec![
+                "https://backup-2.example.com".to_string(),
+                "https://primary.example.com".to_string(),
+                "https://backup-1.example.com".to_string(),
+            ]
+        );
+        assert_eq!(
+            order_endpoints(
+                endpoints.clone(),
+                Some("https://backup-2.example.com"),
+                true
+            ),
+            endpoints
+        );
+        assert_eq!(
+            order_endpoints(
+                endpoints.clone(),
+                Some("https://removed.example.com"),
+                false
+            ),
+            endpoints
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_token_is_retried_with_the_next_one_and_remembered() {
+        let db = crate::database::Database::memory().expect("memory db");
+        let client = reqwest::Client::new();
+        let base = mock_server(vec![UNAUTHORIZED, OK]);
+        let tokens = ["current-token", "previous-token"];
+        assert_eq!(order_tokens(tokens.to_vec(), None), tokens);
+
+        let (token, (_, result)) = send_with_tokens(&db, &client, &[base], &tokens, b"{}")
+            .await
+            .unwrap();
+        assert_eq!(token, "previous-token");
+        assert!(result.unwrap().0.status().is_success());
+        // 之后先用被接受的令牌
+        let active = db.get_setting(SETTINGS_ACTIVE_TOKEN).unwrap();
+        assert_eq!(
+            order_tokens(tokens.to_vec(), active.as_deref()),
+            ["previous-token", "current-token"]
+        );
+
+        // 最后一个令牌也被拒时原样返回 401，不记住它
+        let base = mock_server(vec![UNAUTHORIZED]);
+        let (token, (_, result)) = send_with_tokens(&db, &client, &[base], &["other-token"], b"{}")
+            .await
+            .unwrap();
+        assert_eq!(token, "other-token");
+        assert_eq!(
+            result.unwrap().0.status(),
+            reqwest::StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(db.get_setting(SETTINGS_ACTIVE_TOKEN).unwrap(), active);
+    }
+
+    #[tokio::test]
+    async fn unavailable_primary_fails_over_to_the_next_endpoint() {
+        let db = crate::database::Database::memory().expect("memory db");
+        let client = reqwest::Client::new();
+        let primary = mock_server(vec![
+            "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let fallback = mock_server(vec![OK]);
+
+        let (endpoint, result) = send_with_failover(
+            &db,
+            &client,
+            &[primary.clone(), fallback.clone()],
+            "token",
+            b"{}",
+        )
+        .await
+        .expect("send");
+        assert_eq!(endpoint, fallback);
+        assert!(result.expect("response").0.status().is_success());
+        assert_eq!(
+            db.get_setting(SETTINGS_ACTIVE_ENDPOINT).unwrap(),
+            Some(fallback.clone())
+        );
+        // 刚切到备用地址，重新探测主地址的计时从现在开始
+        assert!(primary_probed_at(&db).is_some());
+    }
+}
@@ -0,0 +1,181 @@
+//! 管理员远程收集日志
+//!
+//! 收到 `collect-logs` 指令时读取本机日志目录中最近的日志末尾，按规则去掉明显的密钥后上传。
+//! 只读取 `.log` 文件；较新的文件优先，总量不超过指令给出的上限。
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::error::AppError;
+
+/// 指令未指定大小时上传的日志量
+pub const DEFAULT_MAX_BYTES: usize = 256 * 1024;
+/// 单次上传的日志量上限，服务器拒绝超过 1 MiB 的上传
+pub const MAX_BYTES: usize = 512 * 1024;
+const REDACTED: &str = "[REDACTED]";
+
+/// `Authorization: Bearer xxx` 与裸露的 `Bearer xxx`
+static BEARER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(bearer\s+)[A-Za-z0-9._~+/=-]+").expect("valid bearer regex"));
+/// 常见的供应商密钥前缀
+static API_KEY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(sk-|sk_|ghp_|gho_|xox[abp]-|AIza)[A-Za-z0-9_-]{8,}")
+        .expect("valid api key regex")
+});
+/// `token=xxx`、`"apiKey": "xxx"`、`password: xxx` 等键值对
+static SECRET_FIELD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)("?[A-Za-z0-9_.-]*(?:api[_-]?key|token|secret|password|passwd|authorization)"?\s*[:=]\s*"?)[^\s",;&}]+"#,
+    )
+    .expect("valid secret field regex")
+});
+
+/// 收集到的日志
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectedLogs {
+    pub content: String,
+    /// 超过上限，较旧的部分没有上传
+    pub truncated: bool,
+}
+
+/// 读取 `dir` 中最近的日志，最多 `max_bytes` 字节（按文件从旧到新拼接）；没有日志时返回 `None`
+pub fn collect(dir: &Path, max_bytes: usize) -> Result<Option<CollectedLogs>, AppError> {
+    let mut files = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            .filter_map(|path| {
+                let modified = path.metadata().ok()?.modified().ok()?;
+                Some((modified, path))
+            })
+            .collect::<Vec<(SystemTime, PathBuf)>>(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(AppError::io(dir, err)),
+    };
+    // 从最新的文件开始取，直到用完额度
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    let mut remaining = max_bytes;
+    let mut truncated = false;
+    let mut sections = Vec::new();
+    for (_, path) in files {
+        if remaining == 0 {
+            truncated = true;
+            break;
+        }
+        let bytes = std::fs::read(&path).map_err(|err| AppError::io(&path, err))?;
+        if bytes.is_empty() {
+            continue;
+        }
+        let (tail, cut) = tail(&bytes, remaining);
+        truncated |= cut;
+        if tail.is_empty() {
+            break;
+        }
+        remaining -= tail.len();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        sections.push(format!("==> {name} <==\n{}", String::from_utf8_lossy(tail)));
+    }
+    if sections.is_empty() {
+        return Ok(None);
+    }
+    sections.reverse();
+    Ok(Some(CollectedLogs {
+        content: sections.join("\n"),
+        truncated,
+    }))
+}
+
+/// 去掉日志中明显的密钥；`secrets` 为已知的原文（如同步令牌），出现即替换
+pub fn redact(text: &str, secrets: &[&str]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets.iter().filter(|secret| secret.len() >= 8) {
+        text = text.replace(secret, REDACTED);
+    }
+    let text = BEARER_RE.replace_all(&text, format!("${{1}}{REDACTED}"));
+    let text = API_KEY_RE.replace_all(&text, format!("${{1}}{REDACTED}"));
+    SECRET_FIELD_RE
+        .replace_all(&text, format!("${{1}}{REDACTED}"))
+        .into_owned()
+}
+
+/// 末尾最多 `max` 字节；截断时丢弃第一行残缺的部分
+fn tail(bytes: &[u8], max: usize) -> (&[u8], bool) {
+    if bytes.len() <= max {
+        return (bytes, false);
+    }
+    let tail = &bytes[bytes.len() - max..];
+    let tail = match tail.iter().position(|byte| *byte == b'\n') {
+        Some(newline) => &tail[newline + 1..],
+        None => tail,
+    };
+    (tail, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_tokens_keys_and_secret_fields() {
+        let line = concat!(
+            "POST https://example.com Authorization: Bearer abc.def-123 ",
+            "env ANTHROPIC_AUTH_TOKEN=sk-ant-0123456789abcdef ",
+            r#"{"apiKey": "plain-value", "model": "claude"} "#,
+            "sync-token-0123456789 password: hunter22",
+        );
+        let redacted = redact(line, &["sync-token-0123456789"]);
+        for secret in [
+            "abc.def-123",
+            "sk-ant-0123456789abcdef",
+            "plain-value",
+            "sync-token-0123456789",
+            "hunter22",
+        ] {
+            assert!(!redacted.contains(secret), "{secret} leaked: {redacted}");
+        }
+        assert!(
+            redacted.starts_with("POST https://example.com"),
+            "{redacted}"
+        );
+        assert!(redacted.contains(r#""model": "claude""#), "{redacted}");
+    }
+
+    #[test]
+    fn collects_the_newest_logs_up_to_the_limit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert_eq!(collect(dir.path(), 1024).unwrap(), None);
+        assert_eq!(collect(&dir.path().join("missing"), 1024).unwrap(), None);
+
+        let older = dir.path().join("app_2025-01-01.log");
+        std::fs::write(&older, "old line\n").unwrap();
+        let previous = SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options()
+            .write(true)
+            .open(&older)
+            .unwrap()
+            .set_modified(previous)
+            .unwrap();
+        std::fs::write(dir.path().join("app.log"), "first\nsecond\nthird\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored\n").unwrap();
+
+        let all = collect(dir.path(), 1024).unwrap().unwrap();
+        assert!(!all.truncated);
+        assert_eq!(
+            all.content,
+            "==> app_2025-01-01.log <==\nold line\n\n==> app.log <==\nfirst\nsecond\nthird\n"
+        );
+
+        // 额度只够最新文件的末尾：丢掉残缺的行和更旧的文件
+        let tail = collect(dir.path(), 10).unwrap().unwrap();
+        assert!(tail.truncated);
+        assert_eq!(tail.content, "==> app.log <==\nthird\n");
+    }
+}
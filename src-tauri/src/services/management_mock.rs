@@ -0,0 +1,236 @@
+//! 测试用的进程内管理服务器
+//!
+//! 按预设依次回复同步请求（有无管理员配置、指定版本、401/429/503、慢响应），
+//! 并记录收到的每个请求供断言。同步地址通过设置中的覆盖地址指向它。
+
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use cc_switch_protocol::PROTOCOL_VERSION;
+use serde_json::{json, Value};
+
+const SYNC_PATH: &str = "/api/v1/devices/sync";
+
+/// 一次预设的回复
+#[derive(Clone, Debug)]
+pub struct MockReply {
+    status: StatusCode,
+    headers: Vec<(&'static str, String)>,
+    body: Value,
+    delay: Option<Duration>,
+}
+
+impl MockReply {
+    /// 成功但没有下发任何内容
+    pub fn ok() -> Self {
+        Self::json(
+            StatusCode::OK,
+            json!({ "ok": true, "protocolVersion": PROTOCOL_VERSION }),
+        )
+    }
+
+    /// 下发指定版本的管理员配置
+    pub fn admin_config(config: Value, version: i64) -> Self {
+        Self::json(
+            StatusCode::OK,
+            json!({
+                "ok": true,
+                "protocolVersion": PROTOCOL_VERSION,
+                "adminConfig": config,
+                "adminVersion": version,
+            }),
+        )
+    }
+
+    /// 状态码为 200，但 `ok` 为 false 并附带原因
+    pub fn rejected(error: &str) -> Self {
+        Self::json(
+            StatusCode::OK,
+            json!({ "ok": false, "protocolVersion": PROTOCOL_VERSION, "error": error }),
+        )
+    }
+
+    /// 按服务器的错误格式返回指定状态码
+    pub fn status(status: u16) -> Self {
+        let status = StatusCode::from_u16(status).expect("valid status");
+        Self::json(
+            status,
+            serde_json::to_value(cc_switch_protocol::ErrorBody::new(
+                status.as_u16(),
+                status.canonical_reason().unwrap_or("error"),
+            ))
+            .expect("serialize error body"),
+        )
+    }
+
+    pub fn json(status: StatusCode, body: Value) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body,
+            delay: None,
+        }
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// 在回复中附带服务器指令
+    pub fn directives(mut self, directives: Value) -> Self {
+        self.body["serverDirectives"] = directives;
+        self
+    }
+
+    /// 等待 `delay` 后再回复，用于触发客户端超时
+    pub fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// 服务器收到的一个请求；请求体已解压并按 JSON 解析（不是 JSON 时为 `Null`）
+#[derive(Clone, Debug)]
+pub struct ReceivedRequest {
+    pub path: String,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    replies: Mutex<VecDeque<MockReply>>,
+    requests: Mutex<Vec<ReceivedRequest>>,
+}
+
+pub struct MockManagementServer {
+    base_url: String,
+    state: Arc<MockState>,
+}
+
+impl MockManagementServer {
+    /// 在本机随机端口上启动；预设回复用完后同步请求一律返回 [`MockReply::ok`]
+    pub async fn start() -> Self {
+        let state = Arc::new(MockState::default());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock management server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().fallback(handle).with_state(state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Self { base_url, state }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 追加一个同步请求的回复
+    pub fn reply(&self, reply: MockReply) -> &Self {
+        lock(&self.state.replies).push_back(reply);
+        self
+    }
+
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        lock(&self.state.requests).clone()
+    }
+
+    /// 收到的同步请求体，按到达顺序
+    pub fn sync_requests(&self) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.path == SYNC_PATH)
+            .map(|request| request.body)
+            .collect()
+    }
+}
+
+async fn handle(
+    State(state): State<Arc<MockState>>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri.path().to_string();
+    let gzip = headers
+        .get(axum::http::header::CONTENT_ENCODING)
+        .is_some_and(|value| value == "gzip");
+    let body = if gzip {
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(body.as_ref())
+            .read_to_end(&mut decoded)
+            .expect("mock server received invalid gzip");
+        decoded
+    } else {
+        body.to_vec()
+    };
+    lock(&state.requests).push(ReceivedRequest {
+        path: path.clone(),
+        headers,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    });
+
+    // 只有同步请求消耗预设回复；命令回执、日志上传等一律成功
+    let reply = if path == SYNC_PATH {
+        lock(&state.replies)
+            .pop_front()
+            .unwrap_or_else(MockReply::ok)
+    } else {
+        MockReply::json(StatusCode::OK, json!({ "ok": true }))
+    };
+    if let Some(delay) = reply.delay {
+        tokio::time::sleep(delay).await;
+    }
+    let mut response = (reply.status, axum::Json(reply.body)).into_response();
+    for (name, value) in reply.headers {
+        response
+            .headers_mut()
+            .insert(name, value.parse().expect("valid header value"));
+    }
+    response
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 收集所有测试的日志输出，用于检查日志中没有泄露密钥
+struct CapturingLogger {
+    lines: Mutex<Vec<String>>,
+}
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        lock(&self.lines).push(format!("{} {}", record.target(), record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: OnceLock<&'static CapturingLogger> = OnceLock::new();
+
+/// 安装捕获日志（只生效一次），返回目前为止的全部日志行
+pub fn captured_logs() -> Vec<String> {
+    let logger = LOGGER.get_or_init(|| {
+        let logger: &'static CapturingLogger = Box::leak(Box::new(CapturingLogger {
+            lines: Mutex::new(Vec::new()),
+        }));
+        if log::set_logger(logger).is_ok() {
+            log::set_max_level(log::LevelFilter::Trace);
+        }
+        logger
+    });
+    lock(&logger.lines).clone()
+}
@@ -0,0 +1,191 @@
+//! 检测当前网络是否按流量计费
+//!
+//! 计费网络（手机热点、蜂窝、开启了低数据模式的网络）上同步只发心跳，不上传完整快照。
+//! Windows 读取 WinRT 的连接成本，macOS 读取 Network.framework 的路径标记
+//! （`expensive` / `constrained`）；其他平台无法判断，返回 `Unknown`，按不计费处理。
+
+/// 当前网络的计费情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkCost {
+    /// 只有 Windows 与 macOS 能确认网络不计费
+    #[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+    Unmetered,
+    Metered,
+    Unknown,
+}
+
+impl NetworkCost {
+    pub fn is_metered(self) -> bool {
+        self == Self::Metered
+    }
+}
+
+/// 检测当前网络；可能阻塞数百毫秒，需在阻塞线程中调用
+#[cfg(target_os = "windows")]
+pub fn current() -> NetworkCost {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    const SCRIPT: &str = "$t=[Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime];\
+        $p=[Windows.Networking.Connectivity.NetworkInformation]::GetInternetConnectionProfile();\
+        if ($p) { $c=$p.GetConnectionCost(); \"$($c.NetworkCostType) $($c.Roaming) $($c.OverDataLimit)\" }";
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_windows_cost(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => NetworkCost::Unknown,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn current() -> NetworkCost {
+    macos::current()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn current() -> NetworkCost {
+    NetworkCost::Unknown
+}
+
+/// 解析形如 `Fixed False False` 的输出：成本类型、是否漫游、是否超出流量上限
+#[cfg(any(target_os = "windows", test))]
+fn parse_windows_cost(output: &str) -> NetworkCost {
+    let mut fields = output.split_whitespace();
+    let cost_type = fields.next();
+    let flagged = fields.any(|field| field.eq_ignore_ascii_case("true"));
+    match cost_type {
+        Some("Fixed" | "Variable") => NetworkCost::Metered,
+        Some("Unrestricted") if flagged => NetworkCost::Metered,
+        Some("Unrestricted") => NetworkCost::Unmetered,
+        _ => NetworkCost::Unknown,
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    //! `nw_path_monitor` 只通过回调报告路径变化：首次调用时启动一个常驻监视器，
+    //! 回调把最新结果写入原子变量，检测时读取它。
+
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::Once;
+    use std::time::Duration;
+
+    use super::NetworkCost;
+
+    type NwObject = *mut c_void;
+
+    #[link(name = "Network", kind = "framework")]
+    extern "C" {
+        fn nw_path_monitor_create() -> NwObject;
+        fn nw_path_monitor_set_queue(monitor: NwObject, queue: *mut c_void);
+        fn nw_path_monitor_set_update_handler(monitor: NwObject, handler: *const c_void);
+        fn nw_path_monitor_start(monitor: NwObject);
+        fn nw_path_is_expensive(path: NwObject) -> bool;
+        fn nw_path_is_constrained(path: NwObject) -> bool;
+    }
+
+    extern "C" {
+        static _NSConcreteGlobalBlock: c_void;
+        fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut c_void;
+    }
+
+    /// Objective-C block 的内存布局；不捕获变量的全局 block，不会被复制或释放
+    #[repr(C)]
+    struct Block {
+        isa: *const c_void,
+        flags: i32,
+        reserved: i32,
+        invoke: unsafe extern "C" fn(*const Block, NwObject),
+        descriptor: *const BlockDescriptor,
+    }
+
+    #[repr(C)]
+    struct BlockDescriptor {
+        reserved: usize,
+        size: usize,
+    }
+
+    const BLOCK_IS_GLOBAL: i32 = 1 << 28;
+    static DESCRIPTOR: BlockDescriptor = BlockDescriptor {
+        reserved: 0,
+        size: std::mem::size_of::<Block>(),
+    };
+
+    const UNKNOWN: u8 = 0;
+    const UNMETERED: u8 = 1;
+    const METERED: u8 = 2;
+    static STATE: AtomicU8 = AtomicU8::new(UNKNOWN);
+    static START: Once = Once::new();
+
+    unsafe extern "C" fn on_update(_block: *const Block, path: NwObject) {
+        let metered = nw_path_is_expensive(path) || nw_path_is_constrained(path);
+        STATE.store(if metered { METERED } else { UNMETERED }, Ordering::Relaxed);
+    }
+
+    pub(super) fn current() -> NetworkCost {
+        START.call_once(|| {
+            let block: &'static Block = Box::leak(Box::new(Block {
+                isa: std::ptr::addr_of!(_NSConcreteGlobalBlock),
+                flags: BLOCK_IS_GLOBAL,
+                reserved: 0,
+                invoke: on_update,
+                descriptor: &DESCRIPTOR,
+            }));
+            unsafe {
+                let monitor = nw_path_monitor_create();
+                nw_path_monitor_set_queue(monitor, dispatch_get_global_queue(0, 0));
+                nw_path_monitor_set_update_handler(monitor, (block as *const Block).cast());
+                nw_path_monitor_start(monitor);
+            }
+        });
+        // 监视器启动后很快回报首个路径，最多等半秒
+        for _ in 0..10 {
+            if STATE.load(Ordering::Relaxed) != UNKNOWN {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        match STATE.load(Ordering::Relaxed) {
+            METERED => NetworkCost::Metered,
+            UNMETERED => NetworkCost::Unmetered,
+            _ => NetworkCost::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_cost_types_map_to_metering() {
+        assert_eq!(
+            parse_windows_cost("Unrestricted False False\r\n"),
+            NetworkCost::Unmetered
+        );
+        assert_eq!(
+            parse_windows_cost("Fixed False False"),
+            NetworkCost::Metered
+        );
+        assert_eq!(
+            parse_windows_cost("Variable False False"),
+            NetworkCost::Metered
+        );
+        // 不限流量但正在漫游或已超出上限
+        assert_eq!(
+            parse_windows_cost("Unrestricted True False"),
+            NetworkCost::Metered
+        );
+        assert_eq!(
+            parse_windows_cost("Unknown False False"),
+            NetworkCost::Unknown
+        );
+        assert_eq!(parse_windows_cost(""), NetworkCost::Unknown);
+    }
+}
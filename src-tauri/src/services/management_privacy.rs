@@ -0,0 +1,441 @@
+//! 上传快照前处理供应商中的密钥
+//!
+//! 按各应用的配置结构定位密钥（Claude / Gemini 的 `env`、Codex 的 `auth` 与 `config.toml`、
+//! 用量脚本的凭据），再以字段名规则兜底；MCP 服务器与通用配置片段同样处理。
+//! 只影响上传的快照，本地数据与管理员配置的应用不受影响。
+//!
+//! 加密格式：`enc:v1:` + base64(临时 X25519 公钥 32 字节 || nonce 12 字节 || 密文与 tag)。
+//! 密钥为 HKDF-SHA256(共享密钥, salt = 临时公钥 || 接收方公钥, info = [`ENCRYPTION_INFO`])，
+//! 算法为 ChaCha20-Poly1305，持有对应私钥的管理员即可解密。
+
+use base64::Engine;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{aead, agreement, hkdf};
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+
+const SETTINGS_SNAPSHOT_PRIVACY: &str = "management_snapshot_privacy";
+
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+pub const MASK_PREFIX: &str = "****";
+const MASK_TAIL_CHARS: usize = 4;
+const ENCRYPTION_INFO: &[u8] = b"cc-switch management snapshot v1";
+
+/// Codex `config.toml` 中以 api_key / token / secret 结尾的字符串键
+static TOML_SECRET_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?mi)^(\s*[A-Za-z0-9_.-]*(?:api_key|token|secret)\s*=\s*)"([^"]*)""#)
+        .expect("valid toml secret regex")
+});
+
+/// 快照上传时对密钥的处理级别
+pub use cc_switch_protocol::SnapshotPrivacy;
+
+/// [`SnapshotPrivacy`] 的本地设置读写
+pub trait SnapshotPrivacySetting: Sized {
+    fn load(db: &Database) -> Self;
+
+    /// 构建时未编入公钥时不允许选择加密
+    fn save(self, db: &Database, public_key: &str) -> Result<(), AppError>;
+}
+
+impl SnapshotPrivacySetting for SnapshotPrivacy {
+    fn load(db: &Database) -> Self {
+        match db
+            .get_setting(SETTINGS_SNAPSHOT_PRIVACY)
+            .ok()
+            .flatten()
+            .as_deref()
+        {
+            Some("redacted") => SnapshotPrivacy::Redacted,
+            Some("encrypted") => SnapshotPrivacy::Encrypted,
+            _ => SnapshotPrivacy::Full,
+        }
+    }
+
+    fn save(self, db: &Database, public_key: &str) -> Result<(), AppError> {
+        if self == SnapshotPrivacy::Encrypted
+            && SnapshotEncryptor::from_base64(public_key)?.is_none()
+        {
+            return Err(AppError::InvalidInput(
+                "This build has no snapshot encryption key".to_string(),
+            ));
+        }
+        db.set_setting(SETTINGS_SNAPSHOT_PRIVACY, self.as_str())?;
+        Ok(())
+    }
+}
+
+/// 密钥的处理方式
+pub enum Protector<'a> {
+    Mask,
+    Encrypt(&'a SnapshotEncryptor),
+}
+
+impl Protector<'_> {
+    fn apply(&self, value: &str) -> Result<String, AppError> {
+        if value.is_empty() {
+            return Ok(String::new());
+        }
+        match self {
+            Protector::Mask => Ok(mask(value)),
+            Protector::Encrypt(encryptor) => encryptor.encrypt(value),
+        }
+    }
+}
+
+/// 保留末尾 4 位，过短的值全部隐藏
+pub fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= MASK_TAIL_CHARS * 2 {
+        return MASK_PREFIX.to_string();
+    }
+    let tail: String = chars[chars.len() - MASK_TAIL_CHARS..].iter().collect();
+    format!("{MASK_PREFIX}{tail}")
+}
+
+/// 用接收方 X25519 公钥加密单个值
+pub struct SnapshotEncryptor {
+    recipient: [u8; 32],
+    rng: SystemRandom,
+}
+
+impl SnapshotEncryptor {
+    /// 公钥为空时返回 `None`
+    pub fn from_base64(public_key: &str) -> Result<Option<Self>, AppError> {
+        let public_key = public_key.trim();
+        if public_key.is_empty() {
+            return Ok(None);
+        }
+        let recipient = base64::engine::general_purpose::STANDARD
+            .decode(public_key)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                AppError::Message("Invalid snapshot encryption public key".to_string())
+            })?;
+        Ok(Some(Self {
+            recipient,
+            rng: SystemRandom::new(),
+        }))
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, AppError> {
+        let failed = |_| AppError::Message("Snapshot encryption failed".to_string());
+
+        let ephemeral = agreement::EphemeralPrivateKey::generate(&agreement::X25519, &self.rng)
+            .map_err(failed)?;
+        let ephemeral_public = ephemeral.compute_public_key().map_err(failed)?;
+        let peer = agreement::UnparsedPublicKey::new(&agreement::X25519, self.recipient);
+        let salt = [ephemeral_public.as_ref(), &self.recipient[..]].concat();
+        let key = agreement::agree_ephemeral(ephemeral, &peer, |shared| {
+            hkdf::Salt::new(hkdf::HKDF_SHA256, &salt)
+                .extract(shared)
+                .expand(&[ENCRYPTION_INFO], &aead::CHACHA20_POLY1305)
+                .map(aead::UnboundKey::from)
+        })
+        .map_err(failed)?
+        .map_err(failed)?;
+
+        let mut nonce = [0u8; aead::NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(failed)?;
+        let mut sealed = plaintext.as_bytes().to_vec();
+        aead::LessSafeKey::new(key)
+            .seal_in_place_append_tag(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::empty(),
+                &mut sealed,
+            )
+            .map_err(failed)?;
+
+        let mut payload = ephemeral_public.as_ref().to_vec();
+        payload.extend_from_slice(&nonce);
+        payload.extend_from_slice(&sealed);
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{}",
+            base64::engine::general_purpose::STANDARD.encode(payload)
+        ))
+    }
+}
+
+/// 处理单个供应商中的全部密钥
+pub fn protect_provider(
+    app_type: &AppType,
+    provider: &mut Provider,
+    protector: &Protector<'_>,
+) -> Result<(), AppError> {
+    let settings = &mut provider.settings_config;
+    match app_type {
+        AppType::Claude | AppType::Gemini => {
+            if let Some(env) = settings.get_mut("env").and_then(Value::as_object_mut) {
+                for (key, value) in env.iter_mut() {
+                    if is_secret_key(key) {
+                        protect_string(value, protector)?;
+                    }
+                }
+            }
+        }
+        AppType::Codex => {
+            // auth.json 里的内容（API Key 或登录令牌）全部视为密钥
+            if let Some(auth) = settings.get_mut("auth") {
+                protect_all_strings(auth, protector)?;
+            }
+            if let Some(Value::String(config)) = settings.get_mut("config") {
+                *config = protect_toml(config, protector)?;
+            }
+        }
+    }
+    // 兜底：其余位置按字段名识别
+    protect_by_key_name(&mut provider.settings_config, protector)?;
+
+    if let Some(script) = provider
+        .meta
+        .as_mut()
+        .and_then(|meta| meta.usage_script.as_mut())
+    {
+        for secret in [&mut script.api_key, &mut script.access_token]
+            .into_iter()
+            .flatten()
+        {
+            *secret = protector.apply(secret)?;
+        }
+    }
+    Ok(())
+}
+
+/// 处理单个 MCP 服务器定义：`env` 与 `headers` 中的值全部视为密钥
+pub fn protect_mcp_server(server: &mut Value, protector: &Protector<'_>) -> Result<(), AppError> {
+    if let Some(spec) = server.get_mut("server") {
+        for field in ["env", "headers"] {
+            if let Some(values) = spec.get_mut(field) {
+                protect_all_strings(values, protector)?;
+            }
+        }
+    }
+    protect_by_key_name(server, protector)
+}
+
+/// 处理通用配置片段；Claude / Gemini 的片段无法解析为 JSON 时整段视为密钥
+pub fn protect_config_snippet(
+    app_type: &AppType,
+    snippet: &str,
+    protector: &Protector<'_>,
+) -> Result<String, AppError> {
+    match app_type {
+        AppType::Claude | AppType::Gemini => {
+            let Ok(mut value) = serde_json::from_str::<Value>(snippet) else {
+                return protector.apply(snippet);
+            };
+            protect_by_key_name(&mut value, protector)?;
+            serde_json::to_string(&value).map_err(|source| AppError::JsonSerialize { source })
+        }
+        AppType::Codex => protect_toml(snippet, protector),
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    [
+        "api_key",
+        "apikey",
+        "auth_token",
+        "access_token",
+        "refresh_token",
+        "id_token",
+        "secret",
+    ]
+    .iter()
+    .any(|needle| key.contains(needle))
+        || key.ends_with("_token")
+        || key == "token"
+        || key == "password"
+}
+
+fn protect_string(value: &mut Value, protector: &Protector<'_>) -> Result<(), AppError> {
+    if let Value::String(text) = value {
+        if !is_protected(text) {
+            *text = protector.apply(text)?;
+        }
+    }
+    Ok(())
+}
+
+fn protect_all_strings(value: &mut Value, protector: &Protector<'_>) -> Result<(), AppError> {
+    match value {
+        Value::String(_) => protect_string(value, protector),
+        Value::Array(items) => items
+            .iter_mut()
+            .try_for_each(|item| protect_all_strings(item, protector)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .try_for_each(|item| protect_all_strings(item, protector)),
+        _ => Ok(()),
+    }
+}
+
+fn protect_by_key_name(value: &mut Value, protector: &Protector<'_>) -> Result<(), AppError> {
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .try_for_each(|item| protect_by_key_name(item, protector)),
+        Value::Object(fields) => fields.iter_mut().try_for_each(|(key, item)| {
+            if is_secret_key(key) && item.is_string() {
+                protect_string(item, protector)
+            } else {
+                protect_by_key_name(item, protector)
+            }
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn protect_toml(config: &str, protector: &Protector<'_>) -> Result<String, AppError> {
+    let mut result = String::with_capacity(config.len());
+    let mut last = 0;
+    for caps in TOML_SECRET_RE.captures_iter(config) {
+        let (Some(whole), Some(prefix), Some(secret)) = (caps.get(0), caps.get(1), caps.get(2))
+        else {
+            continue;
+        };
+        result.push_str(&config[last..whole.start()]);
+        result.push_str(prefix.as_str());
+        result.push('"');
+        if is_protected(secret.as_str()) {
+            result.push_str(secret.as_str());
+        } else {
+            result.push_str(&protector.apply(secret.as_str())?);
+        }
+        result.push('"');
+        last = whole.end();
+    }
+    result.push_str(&config[last..]);
+    Ok(result)
+}
+
+fn is_protected(value: &str) -> bool {
+    value.starts_with(MASK_PREFIX) || value.starts_with(ENCRYPTED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn provider(settings: Value) -> Provider {
+        Provider::with_id("p".to_string(), "P".to_string(), settings, None)
+    }
+
+    #[test]
+    fn mask_keeps_short_tail() {
+        assert_eq!(mask("sk-ant-1234567890"), "****7890");
+        assert_eq!(mask("short"), "****");
+    }
+
+    #[test]
+    fn redacts_claude_env_secrets_only() {
+        let mut provider = provider(json!({
+            "env": {
+                "ANTHROPIC_AUTH_TOKEN": "sk-ant-abcdefghijkl",
+                "ANTHROPIC_BASE_URL": "https://api.example.com"
+            }
+        }));
+        protect_provider(&AppType::Claude, &mut provider, &Protector::Mask).unwrap();
+        assert_eq!(
+            provider.settings_config["env"]["ANTHROPIC_AUTH_TOKEN"],
+            "****ijkl"
+        );
+        assert_eq!(
+            provider.settings_config["env"]["ANTHROPIC_BASE_URL"],
+            "https://api.example.com"
+        );
+    }
+
+    #[test]
+    fn redacts_codex_auth_and_config_toml() {
+        let mut provider = provider(json!({
+            "auth": { "OPENAI_API_KEY": "sk-proj-0123456789", "tokens": { "refresh": "rt-0123456789" } },
+            "config": "model = \"gpt-5\"\nexperimental_bearer_token = \"tok-0123456789\"\nenv_key = \"OPENAI_API_KEY\"\n"
+        }));
+        protect_provider(&AppType::Codex, &mut provider, &Protector::Mask).unwrap();
+        assert_eq!(
+            provider.settings_config["auth"]["OPENAI_API_KEY"],
+            "****6789"
+        );
+        assert_eq!(
+            provider.settings_config["auth"]["tokens"]["refresh"],
+            "****6789"
+        );
+        let config = provider.settings_config["config"].as_str().unwrap();
+        assert!(config.contains("experimental_bearer_token = \"****6789\""));
+        assert!(config.contains("model = \"gpt-5\""));
+        assert!(config.contains("env_key = \"OPENAI_API_KEY\""));
+    }
+
+    #[test]
+    fn redacts_mcp_server_env_and_snippet_secrets() {
+        let mut server = json!({
+            "id": "github",
+            "name": "GitHub",
+            "server": {
+                "command": "npx",
+                "env": { "GITHUB_PERSONAL_ACCESS_TOKEN": "ghp_0123456789", "GH_HOST": "github.example.com" },
+                "headers": { "Authorization": "Bearer 0123456789" }
+            }
+        });
+        protect_mcp_server(&mut server, &Protector::Mask).unwrap();
+        assert_eq!(
+            server["server"]["env"]["GITHUB_PERSONAL_ACCESS_TOKEN"],
+            "****6789"
+        );
+        assert_eq!(server["server"]["env"]["GH_HOST"], "****.com");
+        assert_eq!(server["server"]["headers"]["Authorization"], "****6789");
+        assert_eq!(server["server"]["command"], "npx");
+
+        let snippet = protect_config_snippet(
+            &AppType::Claude,
+            r#"{"env":{"ANTHROPIC_AUTH_TOKEN":"sk-ant-abcdefghijkl"},"includeCoAuthoredBy":false}"#,
+            &Protector::Mask,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&snippet).unwrap(),
+            json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "****ijkl" }, "includeCoAuthoredBy": false })
+        );
+        assert_eq!(
+            protect_config_snippet(&AppType::Gemini, "not json", &Protector::Mask).unwrap(),
+            "****"
+        );
+        assert_eq!(
+            protect_config_snippet(
+                &AppType::Codex,
+                "model = \"gpt-5\"\nexperimental_bearer_token = \"tok-0123456789\"\n",
+                &Protector::Mask
+            )
+            .unwrap(),
+            "model = \"gpt-5\"\nexperimental_bearer_token = \"****6789\"\n"
+        );
+    }
+
+    #[test]
+    fn encrypts_with_fresh_ephemeral_key() {
+        let public_key = base64::engine::general_purpose::STANDARD.encode([9u8; 32]);
+        let encryptor = SnapshotEncryptor::from_base64(&public_key)
+            .unwrap()
+            .unwrap();
+        let first = encryptor.encrypt("sk-secret").unwrap();
+        let second = encryptor.encrypt("sk-secret").unwrap();
+        assert!(first.starts_with(ENCRYPTED_PREFIX));
+        assert_ne!(first, second);
+
+        let payload = base64::engine::general_purpose::STANDARD
+            .decode(&first[ENCRYPTED_PREFIX.len()..])
+            .unwrap();
+        assert_eq!(payload.len(), 32 + aead::NONCE_LEN + "sk-secret".len() + 16);
+        assert!(SnapshotEncryptor::from_base64("").unwrap().is_none());
+    }
+}
@@ -0,0 +1,165 @@
+//! 管理同步的配置档：同一台机器可以分别登记到多个管理服务器
+//!
+//! 每个配置档有自己的设备 ID、服务器地址、令牌、已应用版本、开关与同步状态，上传的都是同一份
+//! 本地配置；计划、隐私、代理等本地偏好所有配置档共用。原来的单一配置就是 0 号配置档，
+//! 设置键名保持不变，升级后无需迁移；其余配置档的键改为 `management_profile_<id>_` 前缀。
+//! 同步代码通过 [`scope`] 得知当前处理的配置档，不在任何作用域内时为 0 号。
+
+use std::borrow::Cow;
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+
+/// 0 号以外的配置档（JSON 数组）
+const SETTINGS_PROFILES: &str = "management_profiles";
+
+pub const DEFAULT_PROFILE: u32 = 0;
+const DEFAULT_PROFILE_NAME: &str = "default";
+
+tokio::task_local! {
+    static ACTIVE_PROFILE: u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProfile {
+    pub id: u32,
+    pub name: String,
+}
+
+/// 当前处理的配置档
+pub fn active() -> u32 {
+    ACTIVE_PROFILE.try_with(|id| *id).unwrap_or(DEFAULT_PROFILE)
+}
+
+/// 在指定配置档下运行；其中新起的任务不继承配置档，需要自行再次进入
+pub async fn scope<F: Future>(id: u32, future: F) -> F::Output {
+    ACTIVE_PROFILE.scope(id, future).await
+}
+
+pub fn sync_scope<T>(id: u32, f: impl FnOnce() -> T) -> T {
+    ACTIVE_PROFILE.sync_scope(id, f)
+}
+
+/// 当前配置档下的设置键
+pub fn setting_key(key: &str) -> Cow<'_, str> {
+    key_for(active(), key)
+}
+
+fn key_for(id: u32, key: &str) -> Cow<'_, str> {
+    if id == DEFAULT_PROFILE {
+        return Cow::Borrowed(key);
+    }
+    let rest = key.strip_prefix("management_").unwrap_or(key);
+    Cow::Owned(format!("management_profile_{id}_{rest}"))
+}
+
+/// 全部配置档，0 号在最前
+pub fn list(db: &Database) -> Vec<SyncProfile> {
+    let mut profiles = vec![SyncProfile {
+        id: DEFAULT_PROFILE,
+        name: DEFAULT_PROFILE_NAME.to_string(),
+    }];
+    profiles.extend(extra_profiles(db));
+    profiles
+}
+
+pub fn ids(db: &Database) -> Vec<u32> {
+    list(db).into_iter().map(|profile| profile.id).collect()
+}
+
+pub fn get(db: &Database, id: u32) -> Result<SyncProfile, AppError> {
+    list(db)
+        .into_iter()
+        .find(|profile| profile.id == id)
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown sync profile {id}")))
+}
+
+/// 新建配置档，编号为现有最大编号加一；名称不能为空，也不能与已有的重复
+pub fn create(db: &Database, name: &str) -> Result<SyncProfile, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Sync profile name is required".to_string(),
+        ));
+    }
+    if list(db)
+        .iter()
+        .any(|profile| profile.name.eq_ignore_ascii_case(name))
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Sync profile {name} already exists"
+        )));
+    }
+
+    let mut profiles = extra_profiles(db);
+    let id = profiles
+        .iter()
+        .map(|profile| profile.id)
+        .max()
+        .unwrap_or(DEFAULT_PROFILE)
+        + 1;
+    let profile = SyncProfile {
+        id,
+        name: name.to_string(),
+    };
+    profiles.push(profile.clone());
+    let text =
+        serde_json::to_string(&profiles).map_err(|source| AppError::JsonSerialize { source })?;
+    db.set_setting(SETTINGS_PROFILES, &text)?;
+    Ok(profile)
+}
+
+fn extra_profiles(db: &Database) -> Vec<SyncProfile> {
+    db.get_setting(SETTINGS_PROFILES)
+        .ok()
+        .flatten()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_profile_keeps_the_original_keys() {
+        assert_eq!(setting_key("management_device_id"), "management_device_id");
+        assert_eq!(
+            sync_scope(2, || setting_key("management_device_id").into_owned()),
+            "management_profile_2_device_id"
+        );
+        assert_eq!(active(), DEFAULT_PROFILE);
+    }
+
+    #[tokio::test]
+    async fn scope_applies_across_awaits() {
+        let key = scope(3, async {
+            tokio::task::yield_now().await;
+            setting_key("management_sync_enabled").into_owned()
+        })
+        .await;
+        assert_eq!(key, "management_profile_3_sync_enabled");
+    }
+
+    #[test]
+    fn created_profiles_get_increasing_ids_and_unique_names() {
+        let db = Database::memory().expect("init db");
+        assert_eq!(ids(&db), [DEFAULT_PROFILE]);
+
+        let client = create(&db, " Client A ").unwrap();
+        assert_eq!(client.id, 1);
+        assert_eq!(client.name, "Client A");
+        assert_eq!(create(&db, "Client B").unwrap().id, 2);
+        assert!(create(&db, "client a").is_err());
+        assert!(create(&db, "default").is_err());
+        assert!(create(&db, "  ").is_err());
+
+        assert_eq!(ids(&db), [0, 1, 2]);
+        assert_eq!(get(&db, 2).unwrap().name, "Client B");
+        assert!(get(&db, 3).is_err());
+    }
+}
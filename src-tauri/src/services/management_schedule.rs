@@ -0,0 +1,641 @@
+//! 管理同步计划
+//!
+//! 计划存于 settings 表；未设置时为每天北京时间 4 点，并按设备 ID 在其后 0-120 分钟内错开。
+//! 另可设置暂停时段，时段内到点的后台同步推迟到时段结束。
+
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, FixedOffset, Local, NaiveDate, Offset,
+    TimeZone, Timelike, Utc, Weekday,
+};
+use sha2::{Digest, Sha256};
+
+use crate::database::Database;
+use crate::error::AppError;
+
+const SETTINGS_SYNC_MODE: &str = "management_sync_mode";
+const SETTINGS_SYNC_HOUR: &str = "management_sync_hour";
+const SETTINGS_SYNC_TIMEZONE: &str = "management_sync_timezone";
+const SETTINGS_SYNC_OFFSET_MINUTES: &str = "management_sync_offset_minutes";
+const SETTINGS_SYNC_INTERVAL_HOURS: &str = "management_sync_interval_hours";
+const SETTINGS_SYNC_JITTER_MINUTES: &str = "management_sync_jitter_minutes";
+const SETTINGS_PAUSE_ENABLED: &str = "management_pause_enabled";
+const SETTINGS_PAUSE_START_MINUTE: &str = "management_pause_start_minute";
+const SETTINGS_PAUSE_END_MINUTE: &str = "management_pause_end_minute";
+const SETTINGS_PAUSE_DAYS: &str = "management_pause_days";
+
+const DEFAULT_HOUR: u32 = 4;
+const DEFAULT_OFFSET_MINUTES: i32 = 8 * 60;
+const DEFAULT_INTERVAL_HOURS: u32 = 24;
+const MAX_INTERVAL_HOURS: u32 = 24 * 7;
+const DEFAULT_JITTER_MINUTES: u32 = 120;
+const MAX_JITTER_MINUTES: u32 = 12 * 60;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const DEFAULT_PAUSE_START_MINUTE: u32 = 9 * 60;
+const DEFAULT_PAUSE_END_MINUTE: u32 = 18 * 60;
+
+/// 失败后最多重试的次数，超过后回到正常计划
+pub const MAX_RETRY_ATTEMPTS: u32 = 8;
+const RETRY_BASE_MINUTES: i64 = 5;
+const RETRY_MAX_MINUTES: i64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScheduleMode {
+    /// 每天固定整点
+    #[default]
+    Daily,
+    /// 距上次同步每隔 N 小时
+    Interval,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScheduleTimezone {
+    /// 系统本地时区（跟随夏令时）
+    Local,
+    /// 固定 UTC 偏移（无夏令时）
+    #[default]
+    FixedOffset,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ManagementSyncSchedule {
+    pub mode: ScheduleMode,
+    pub hour: u32,
+    pub timezone: ScheduleTimezone,
+    pub offset_minutes: i32,
+    pub interval_hours: u32,
+    /// 每日模式下按设备错开的时间窗口（分钟），0 表示不错开
+    pub jitter_minutes: u32,
+}
+
+impl Default for ManagementSyncSchedule {
+    fn default() -> Self {
+        Self {
+            mode: ScheduleMode::Daily,
+            hour: DEFAULT_HOUR,
+            timezone: ScheduleTimezone::FixedOffset,
+            offset_minutes: DEFAULT_OFFSET_MINUTES,
+            interval_hours: DEFAULT_INTERVAL_HOURS,
+            jitter_minutes: DEFAULT_JITTER_MINUTES,
+        }
+    }
+}
+
+impl ManagementSyncSchedule {
+    /// 读取计划；无效的存储值逐项回退到默认值
+    pub fn load(db: &Database) -> Result<Self, AppError> {
+        let defaults = Self::default();
+        let schedule = Self {
+            mode: read_setting(db, SETTINGS_SYNC_MODE, |value| match value {
+                "daily" => Some(ScheduleMode::Daily),
+                "interval" => Some(ScheduleMode::Interval),
+                _ => None,
+            })?
+            .unwrap_or(defaults.mode),
+            hour: read_setting(db, SETTINGS_SYNC_HOUR, |value| value.parse().ok())?
+                .filter(|hour| *hour < 24)
+                .unwrap_or(defaults.hour),
+            timezone: read_setting(db, SETTINGS_SYNC_TIMEZONE, |value| match value {
+                "local" => Some(ScheduleTimezone::Local),
+                "fixed-offset" => Some(ScheduleTimezone::FixedOffset),
+                _ => None,
+            })?
+            .unwrap_or(defaults.timezone),
+            offset_minutes: read_setting(db, SETTINGS_SYNC_OFFSET_MINUTES, |value| {
+                value.parse().ok()
+            })?
+            .filter(|minutes| fixed_offset(*minutes).is_some())
+            .unwrap_or(defaults.offset_minutes),
+            interval_hours: read_setting(db, SETTINGS_SYNC_INTERVAL_HOURS, |value| {
+                value.parse().ok()
+            })?
+            .filter(|hours| (1..=MAX_INTERVAL_HOURS).contains(hours))
+            .unwrap_or(defaults.interval_hours),
+            jitter_minutes: read_setting(db, SETTINGS_SYNC_JITTER_MINUTES, |value| {
+                value.parse().ok()
+            })?
+            .filter(|minutes| *minutes <= MAX_JITTER_MINUTES)
+            .unwrap_or(defaults.jitter_minutes),
+        };
+        Ok(schedule)
+    }
+
+    pub fn save(&self, db: &Database) -> Result<(), AppError> {
+        self.validate()?;
+        let mode = match self.mode {
+            ScheduleMode::Daily => "daily",
+            ScheduleMode::Interval => "interval",
+        };
+        let timezone = match self.timezone {
+            ScheduleTimezone::Local => "local",
+            ScheduleTimezone::FixedOffset => "fixed-offset",
+        };
+        db.set_setting(SETTINGS_SYNC_MODE, mode)?;
+        db.set_setting(SETTINGS_SYNC_HOUR, &self.hour.to_string())?;
+        db.set_setting(SETTINGS_SYNC_TIMEZONE, timezone)?;
+        db.set_setting(
+            SETTINGS_SYNC_OFFSET_MINUTES,
+            &self.offset_minutes.to_string(),
+        )?;
+        db.set_setting(
+            SETTINGS_SYNC_INTERVAL_HOURS,
+            &self.interval_hours.to_string(),
+        )?;
+        db.set_setting(
+            SETTINGS_SYNC_JITTER_MINUTES,
+            &self.jitter_minutes.to_string(),
+        )?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.hour >= 24 {
+            return Err(AppError::InvalidInput(format!(
+                "Sync hour must be between 0 and 23, got {}",
+                self.hour
+            )));
+        }
+        if fixed_offset(self.offset_minutes).is_none() {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid UTC offset: {} minutes",
+                self.offset_minutes
+            )));
+        }
+        if !(1..=MAX_INTERVAL_HOURS).contains(&self.interval_hours) {
+            return Err(AppError::InvalidInput(format!(
+                "Sync interval must be between 1 and {MAX_INTERVAL_HOURS} hours, got {}",
+                self.interval_hours
+            )));
+        }
+        if self.jitter_minutes > MAX_JITTER_MINUTES {
+            return Err(AppError::InvalidInput(format!(
+                "Sync jitter must be at most {MAX_JITTER_MINUTES} minutes, got {}",
+                self.jitter_minutes
+            )));
+        }
+        Ok(())
+    }
+
+    /// 计算下一次同步时间；`last_run` 仅用于间隔模式，`device_id` 决定每日模式的错开量
+    pub fn next_run(
+        &self,
+        now: DateTime<Utc>,
+        last_run: Option<DateTime<Utc>>,
+        device_id: &str,
+    ) -> DateTime<Utc> {
+        match self.mode {
+            ScheduleMode::Interval => {
+                let interval = ChronoDuration::hours(i64::from(self.interval_hours));
+                match last_run {
+                    Some(last) => (last + interval).max(now),
+                    None => now + interval,
+                }
+            }
+            ScheduleMode::Daily => {
+                // 找到 now - jitter 之后的整点再加回 jitter，即为严格晚于 now 的错开时间
+                let jitter = device_jitter(device_id, self.jitter_minutes);
+                let shifted = now - jitter;
+                let base = match self.timezone {
+                    ScheduleTimezone::Local => next_daily(&Local, self.hour, shifted),
+                    ScheduleTimezone::FixedOffset => {
+                        // 计划循环里不能 panic：偏移无效时依次退回默认偏移和 UTC
+                        let tz = fixed_offset(self.offset_minutes)
+                            .or_else(|| fixed_offset(DEFAULT_OFFSET_MINUTES))
+                            .unwrap_or_else(|| Utc.fix());
+                        next_daily(&tz, self.hour, shifted)
+                    }
+                };
+                base + jitter
+            }
+        }
+    }
+}
+
+/// 暂停同步的时段（系统本地时间），如工作时间内不希望后台改写配置
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SyncPauseWindow {
+    pub enabled: bool,
+    /// 开始时间，距 0 点的分钟数
+    pub start_minute: u32,
+    /// 结束时间（不含）；早于开始时间表示跨过午夜，在次日结束
+    pub end_minute: u32,
+    /// 时段在哪些日子开始
+    pub days: Vec<Weekday>,
+}
+
+impl Default for SyncPauseWindow {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_minute: DEFAULT_PAUSE_START_MINUTE,
+            end_minute: DEFAULT_PAUSE_END_MINUTE,
+            days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+        }
+    }
+}
+
+impl SyncPauseWindow {
+    /// 读取暂停时段；无效的存储值逐项回退到默认值
+    pub fn load(db: &Database) -> Result<Self, AppError> {
+        let defaults = Self::default();
+        let window = Self {
+            enabled: read_setting(db, SETTINGS_PAUSE_ENABLED, |value| match value {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            })?
+            .unwrap_or(defaults.enabled),
+            start_minute: read_setting(db, SETTINGS_PAUSE_START_MINUTE, |value| {
+                value.parse().ok()
+            })?
+            .filter(|minute| *minute < MINUTES_PER_DAY)
+            .unwrap_or(defaults.start_minute),
+            end_minute: read_setting(db, SETTINGS_PAUSE_END_MINUTE, |value| value.parse().ok())?
+                .filter(|minute| *minute < MINUTES_PER_DAY)
+                .unwrap_or(defaults.end_minute),
+            days: read_setting(db, SETTINGS_PAUSE_DAYS, |value| {
+                value
+                    .split(',')
+                    .filter(|day| !day.is_empty())
+                    .map(|day| {
+                        day.trim()
+                            .parse::<u8>()
+                            .ok()
+                            .and_then(|day| Weekday::try_from(day).ok())
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })?
+            .unwrap_or(defaults.days),
+        };
+        Ok(window)
+    }
+
+    pub fn save(&self, db: &Database) -> Result<(), AppError> {
+        self.validate()?;
+        let days = self
+            .days
+            .iter()
+            .map(|day| day.num_days_from_monday().to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        db.set_setting(
+            SETTINGS_PAUSE_ENABLED,
+            if self.enabled { "true" } else { "false" },
+        )?;
+        db.set_setting(SETTINGS_PAUSE_START_MINUTE, &self.start_minute.to_string())?;
+        db.set_setting(SETTINGS_PAUSE_END_MINUTE, &self.end_minute.to_string())?;
+        db.set_setting(SETTINGS_PAUSE_DAYS, &days)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        for minute in [self.start_minute, self.end_minute] {
+            if minute >= MINUTES_PER_DAY {
+                return Err(AppError::InvalidInput(format!(
+                    "Pause window time must be between 0 and {} minutes, got {minute}",
+                    MINUTES_PER_DAY - 1
+                )));
+            }
+        }
+        if self.start_minute == self.end_minute {
+            return Err(AppError::InvalidInput(
+                "Pause window start and end must differ".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// `at` 落在暂停时段内时返回时段结束时间
+    pub fn paused_until(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.paused_until_in(&Local, at)
+    }
+
+    fn paused_until_in<Tz: TimeZone>(&self, tz: &Tz, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !self.enabled || self.start_minute == self.end_minute {
+            return None;
+        }
+        let local = at.with_timezone(tz);
+        let today = local.date_naive();
+        let minute = local.hour() * 60 + local.minute();
+        let starts_on = |date: NaiveDate| self.days.contains(&date.weekday());
+        let end_date = if self.start_minute < self.end_minute {
+            if !starts_on(today) || !(self.start_minute..self.end_minute).contains(&minute) {
+                return None;
+            }
+            today
+        } else if minute >= self.start_minute && starts_on(today) {
+            today.succ_opt()?
+        } else if minute < self.end_minute && starts_on(today.pred_opt()?) {
+            today
+        } else {
+            return None;
+        };
+        local_minute(tz, end_date, self.end_minute).filter(|end| *end > at)
+    }
+}
+
+/// 第 `attempt` 次重试前的等待时间：5m、15m、45m……封顶 2h；次数用尽返回 `None`
+pub fn retry_delay(attempt: u32) -> Option<ChronoDuration> {
+    if attempt > MAX_RETRY_ATTEMPTS {
+        return None;
+    }
+    let minutes = (0..attempt.saturating_sub(1)).fold(RETRY_BASE_MINUTES, |minutes, _| {
+        (minutes * 3).min(RETRY_MAX_MINUTES)
+    });
+    Some(ChronoDuration::minutes(minutes))
+}
+
+fn read_setting<T>(
+    db: &Database,
+    key: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<Option<T>, AppError> {
+    let Some(raw) = db.get_setting(key)? else {
+        return Ok(None);
+    };
+    let parsed = parse(raw.trim());
+    if parsed.is_none() {
+        log::warn!("Ignoring invalid {key} setting: {raw}");
+    }
+    Ok(parsed)
+}
+
+/// 由设备 ID 哈希得到的固定错开量，落在 `[0, window_minutes)` 分钟内
+fn device_jitter(device_id: &str, window_minutes: u32) -> ChronoDuration {
+    let window_secs = u64::from(window_minutes) * 60;
+    if window_secs == 0 {
+        return ChronoDuration::zero();
+    }
+    let digest = Sha256::digest(device_id.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    let secs = u64::from_be_bytes(bytes) % window_secs;
+    ChronoDuration::seconds(secs as i64)
+}
+
+fn fixed_offset(minutes: i32) -> Option<FixedOffset> {
+    minutes.checked_mul(60).and_then(FixedOffset::east_opt)
+}
+
+/// 本地日期 `date` 的第 `minute` 分钟；夏令时跳过该时刻时顺延一小时
+fn local_minute<Tz: TimeZone>(tz: &Tz, date: NaiveDate, minute: u32) -> Option<DateTime<Utc>> {
+    let naive = date.and_hms_opt(minute / 60, minute % 60, 0)?;
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + ChronoDuration::hours(1)))
+                .earliest()
+        })
+        .map(|value| value.with_timezone(&Utc))
+}
+
+/// 严格晚于 `now` 的下一个 `hour:00`；夏令时跳过该整点时顺延一小时
+fn next_daily<Tz: TimeZone>(tz: &Tz, hour: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+    let mut date = now.with_timezone(tz).date_naive();
+    for _ in 0..3 {
+        let Some(naive) = date.and_hms_opt(hour, 0, 0) else {
+            break;
+        };
+        let candidate = tz
+            .from_local_datetime(&naive)
+            .earliest()
+            .or_else(|| {
+                tz.from_local_datetime(&(naive + ChronoDuration::hours(1)))
+                    .earliest()
+            })
+            .map(|value| value.with_timezone(&Utc));
+        if let Some(candidate) = candidate.filter(|value| *value > now) {
+            return candidate;
+        }
+        match date.succ_opt() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    now + ChronoDuration::days(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .expect("valid timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn unjittered_default_schedule_is_beijing_4am() {
+        let schedule = ManagementSyncSchedule {
+            jitter_minutes: 0,
+            ..ManagementSyncSchedule::default()
+        };
+        // 北京时间 03:00 -> 当天 04:00
+        assert_eq!(
+            schedule.next_run(utc("2025-06-01T19:00:00Z"), None, "device"),
+            utc("2025-06-01T20:00:00Z")
+        );
+        // 正好 04:00 -> 次日
+        assert_eq!(
+            schedule.next_run(utc("2025-06-01T20:00:00Z"), None, "device"),
+            utc("2025-06-02T20:00:00Z")
+        );
+    }
+
+    #[test]
+    fn fixed_negative_offset() {
+        let schedule = ManagementSyncSchedule {
+            hour: 23,
+            offset_minutes: -5 * 60,
+            jitter_minutes: 0,
+            ..ManagementSyncSchedule::default()
+        };
+        assert_eq!(
+            schedule.next_run(utc("2025-01-01T12:00:00Z"), None, "device"),
+            utc("2025-01-02T04:00:00Z")
+        );
+    }
+
+    #[test]
+    fn interval_mode_counts_from_last_run() {
+        let schedule = ManagementSyncSchedule {
+            mode: ScheduleMode::Interval,
+            interval_hours: 6,
+            ..ManagementSyncSchedule::default()
+        };
+        let now = utc("2025-01-01T12:00:00Z");
+        assert_eq!(
+            schedule.next_run(now, Some(utc("2025-01-01T10:00:00Z")), "device"),
+            utc("2025-01-01T16:00:00Z")
+        );
+        // 错过的同步立即执行
+        assert_eq!(
+            schedule.next_run(now, Some(utc("2024-12-31T00:00:00Z")), "device"),
+            now
+        );
+        assert_eq!(
+            schedule.next_run(now, None, "device"),
+            utc("2025-01-01T18:00:00Z")
+        );
+    }
+
+    #[test]
+    fn jitter_is_stable_and_within_window() {
+        let first = device_jitter("device-a", 120);
+        assert_eq!(first, device_jitter("device-a", 120));
+        assert!(first >= ChronoDuration::zero() && first < ChronoDuration::minutes(120));
+        assert_eq!(device_jitter("device-a", 0), ChronoDuration::zero());
+    }
+
+    #[test]
+    fn daily_run_is_shifted_by_device_jitter() {
+        let schedule = ManagementSyncSchedule::default();
+        let jitter = device_jitter("device-a", schedule.jitter_minutes);
+        let four_am = utc("2025-06-01T20:00:00Z");
+
+        let before = four_am - ChronoDuration::hours(1);
+        assert_eq!(
+            schedule.next_run(before, None, "device-a"),
+            four_am + jitter
+        );
+        // 已过 4 点但未到错开时间时仍是当天
+        let inside = four_am + jitter - ChronoDuration::seconds(1);
+        assert_eq!(
+            schedule.next_run(inside, None, "device-a"),
+            four_am + jitter
+        );
+        let after = four_am + jitter;
+        assert_eq!(
+            schedule.next_run(after, None, "device-a"),
+            four_am + ChronoDuration::days(1) + jitter
+        );
+    }
+
+    #[test]
+    fn retry_delay_backs_off_and_gives_up() {
+        let minutes: Vec<i64> = (1..=MAX_RETRY_ATTEMPTS)
+            .map(|attempt| retry_delay(attempt).expect("delay").num_minutes())
+            .collect();
+        assert_eq!(minutes, vec![5, 15, 45, 120, 120, 120, 120, 120]);
+        assert_eq!(retry_delay(MAX_RETRY_ATTEMPTS + 1), None);
+    }
+
+    #[test]
+    fn pause_window_defers_until_its_end() {
+        let beijing = fixed_offset(DEFAULT_OFFSET_MINUTES).unwrap();
+        let work_hours = SyncPauseWindow {
+            enabled: true,
+            ..SyncPauseWindow::default()
+        };
+        // 2025-06-02 是周一：北京时间 10:00 在时段内，推迟到 18:00
+        assert_eq!(
+            work_hours.paused_until_in(&beijing, utc("2025-06-02T02:00:00Z")),
+            Some(utc("2025-06-02T10:00:00Z"))
+        );
+        // 结束时刻不再暂停，开始前、周末与关闭时也不暂停
+        assert_eq!(
+            work_hours.paused_until_in(&beijing, utc("2025-06-02T10:00:00Z")),
+            None
+        );
+        assert_eq!(
+            work_hours.paused_until_in(&beijing, utc("2025-06-02T00:59:00Z")),
+            None
+        );
+        assert_eq!(
+            work_hours.paused_until_in(&beijing, utc("2025-06-01T02:00:00Z")),
+            None
+        );
+        let disabled = SyncPauseWindow::default();
+        assert_eq!(
+            disabled.paused_until_in(&beijing, utc("2025-06-02T02:00:00Z")),
+            None
+        );
+
+        // 跨午夜：周日 22:00 开始的时段在周一 06:00 结束
+        let overnight = SyncPauseWindow {
+            enabled: true,
+            start_minute: 22 * 60,
+            end_minute: 6 * 60,
+            days: vec![Weekday::Sun],
+        };
+        assert_eq!(
+            overnight.paused_until_in(&beijing, utc("2025-06-01T15:00:00Z")),
+            Some(utc("2025-06-01T22:00:00Z"))
+        );
+        assert_eq!(
+            overnight.paused_until_in(&beijing, utc("2025-06-01T20:00:00Z")),
+            Some(utc("2025-06-01T22:00:00Z"))
+        );
+        // 周一 22:00 不在时段内：时段只在周日开始
+        assert_eq!(
+            overnight.paused_until_in(&beijing, utc("2025-06-02T15:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn pause_window_round_trips_and_rejects_empty_windows() {
+        let db = Database::memory().expect("memory db");
+        assert_eq!(
+            SyncPauseWindow::load(&db).unwrap(),
+            SyncPauseWindow::default()
+        );
+        let window = SyncPauseWindow {
+            enabled: true,
+            start_minute: 22 * 60 + 30,
+            end_minute: 7 * 60,
+            days: vec![Weekday::Sat, Weekday::Sun],
+        };
+        window.save(&db).unwrap();
+        assert_eq!(SyncPauseWindow::load(&db).unwrap(), window);
+
+        for invalid in [
+            SyncPauseWindow {
+                end_minute: window.start_minute,
+                ..window.clone()
+            },
+            SyncPauseWindow {
+                start_minute: MINUTES_PER_DAY,
+                ..window.clone()
+            },
+        ] {
+            assert!(invalid.save(&db).is_err());
+        }
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_values() {
+        let valid = ManagementSyncSchedule::default();
+        assert!(valid.validate().is_ok());
+        for invalid in [
+            ManagementSyncSchedule {
+                hour: 24,
+                ..valid.clone()
+            },
+            ManagementSyncSchedule {
+                offset_minutes: 24 * 60,
+                ..valid.clone()
+            },
+            ManagementSyncSchedule {
+                interval_hours: 0,
+                ..valid.clone()
+            },
+            ManagementSyncSchedule {
+                jitter_minutes: MAX_JITTER_MINUTES + 1,
+                ..valid.clone()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+}
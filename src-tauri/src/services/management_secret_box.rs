@@ -0,0 +1,103 @@
+//! 编译期写入的管理密钥的加解密，构建脚本（`build.rs`）与运行时共用
+//!
+//! 构建脚本每次生成随机密钥，按位异或拆成 [`KEY_SHARES`] 份分散写入生成的常量，
+//! 再用 ChaCha20-Poly1305 加密地址、令牌等；运行时合并密钥后认证解密。
+//! 常量名作为附加数据参与认证，篡改密文或调换两个常量都会解密失败。
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+
+pub const KEY_LEN: usize = 32;
+/// 密钥拆成的份数
+pub const KEY_SHARES: usize = 4;
+
+/// 把 `key` 拆成 `random` 加上一份补齐的份额，所有份额异或后还原 `key`
+#[cfg_attr(not(test), allow(dead_code))] // 只在构建脚本中使用
+pub fn split_key(key: &[u8; KEY_LEN], random: &[[u8; KEY_LEN]]) -> Vec<[u8; KEY_LEN]> {
+    let mut last = *key;
+    for share in random {
+        for (byte, mask) in last.iter_mut().zip(share) {
+            *byte ^= mask;
+        }
+    }
+    let mut shares = random.to_vec();
+    shares.push(last);
+    shares
+}
+
+pub fn combine_key(shares: &[[u8; KEY_LEN]]) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    for share in shares {
+        for (byte, mask) in key.iter_mut().zip(share) {
+            *byte ^= mask;
+        }
+    }
+    key
+}
+
+/// 加密为 `nonce || 密文 || 认证标签`
+#[cfg_attr(not(test), allow(dead_code))] // 只在构建脚本中使用
+pub fn seal(key: &[u8; KEY_LEN], nonce: [u8; NONCE_LEN], name: &str, plaintext: &[u8]) -> Vec<u8> {
+    let sealing = LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, key).expect("valid key"));
+    let mut sealed = plaintext.to_vec();
+    sealing
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(name.as_bytes()),
+            &mut sealed,
+        )
+        .expect("plaintext fits in one ChaCha20-Poly1305 message");
+    let mut out = nonce.to_vec();
+    out.append(&mut sealed);
+    out
+}
+
+/// 认证解密 [`seal`] 的输出；`name` 须与加密时一致
+pub fn open(key: &[u8; KEY_LEN], name: &str, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err(format!("{name} is too short to be a sealed secret"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce =
+        Nonce::try_assume_unique_for_key(nonce).map_err(|_| format!("{name} has a bad nonce"))?;
+    let opening = LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, key).expect("valid key"));
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = opening
+        .open_in_place(nonce, Aad::from(name.as_bytes()), &mut buffer)
+        .map_err(|_| format!("{name} failed authentication"))?;
+    Ok(plaintext.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_secrets_round_trip_and_reject_tampering() {
+        let key = [9u8; KEY_LEN];
+        let shares = split_key(&key, &[[1u8; KEY_LEN], [0xF0u8; KEY_LEN], [7u8; KEY_LEN]]);
+        assert_eq!(shares.len(), KEY_SHARES);
+        assert_ne!(shares[KEY_SHARES - 1], key);
+        assert_eq!(combine_key(&shares), key);
+
+        let sealed = seal(&key, [3u8; NONCE_LEN], "URL", b"https://manage.example.com");
+        assert!(!sealed
+            .windows(b"manage".len())
+            .any(|window| window == b"manage"));
+        assert_eq!(
+            open(&key, "URL", &sealed).unwrap(),
+            b"https://manage.example.com"
+        );
+        assert_eq!(
+            open(&key, "TOKEN", &seal(&key, [4u8; NONCE_LEN], "TOKEN", b"")).unwrap(),
+            b""
+        );
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(open(&key, "URL", &tampered).is_err());
+        // 常量名参与认证：调换到另一个常量同样失败
+        assert!(open(&key, "TOKEN", &sealed).is_err());
+        assert!(open(&[8u8; KEY_LEN], "URL", &sealed).is_err());
+        assert!(open(&key, "URL", &sealed[..4]).is_err());
+    }
+}
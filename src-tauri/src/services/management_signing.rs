@@ -0,0 +1,56 @@
+//! 校验管理员配置的 Ed25519 签名
+//!
+//! 服务器对存储的管理员配置按规范 JSON（键排序、紧凑格式）签名，签名随同步响应中的
+//! `adminConfigSignature` 下发。客户端用构建时编入的公钥校验，防止服务器被入侵或中间人
+//! （如企业 CA）篡改配置。规范 JSON 由 `cc-switch-protocol` 的 `canonical_json` 生成，与服务器共用。
+
+use base64::Engine;
+use cc_switch_protocol::canonical_json;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde_json::Value;
+
+/// 用 base64 公钥校验 `config` 的 base64 签名
+pub fn verify(public_key: &str, config: &Value, signature: &str) -> Result<(), String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let public_key = engine
+        .decode(public_key.trim())
+        .map_err(|err| format!("invalid config signing public key: {err}"))?;
+    if public_key.len() != 32 {
+        return Err(format!(
+            "config signing public key must be 32 bytes, got {}",
+            public_key.len()
+        ));
+    }
+    let signature = engine
+        .decode(signature.trim())
+        .map_err(|err| format!("invalid config signature encoding: {err}"))?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(canonical_json(config).as_bytes(), &signature)
+        .map_err(|_| "config signature does not match".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    #[test]
+    fn verifies_signatures_over_the_canonical_form() {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7u8; 32]).unwrap();
+        let public_key = engine.encode(key_pair.public_key().as_ref());
+        let config = serde_json::json!({"claude": {"currentProviderId": "a"}, "codex": null});
+        let signature = engine.encode(key_pair.sign(canonical_json(&config).as_bytes()));
+
+        // 键顺序不同不影响校验
+        let reordered: Value =
+            serde_json::from_str(r#"{"codex": null, "claude": {"currentProviderId": "a"}}"#)
+                .unwrap();
+        assert_eq!(verify(&public_key, &reordered, &signature), Ok(()));
+
+        let tampered = serde_json::json!({"claude": {"currentProviderId": "b"}, "codex": null});
+        assert!(verify(&public_key, &tampered, &signature).is_err());
+        assert!(verify(&public_key, &config, "not base64!").is_err());
+        assert!(verify("", &config, &signature).is_err());
+    }
+}
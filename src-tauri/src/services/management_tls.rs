@@ -0,0 +1,286 @@
+//! 管理同步的 TLS 设置：额外根证书与证书固定
+//!
+//! 两者互斥：经过 TLS 拦截代理时需要信任代理的根证书，这必然与固定服务器证书冲突。
+//! 固定值可以在构建时编入（`AI_CODE_WITH_MANAGEMENT_PINNED_SPKI`），也可以存于 settings 表，
+//! 只要存在任一固定值，就不允许再配置额外根证书。
+
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::database::Database;
+use crate::error::AppError;
+
+const SETTINGS_CA_BUNDLE_PATH: &str = "management_ca_bundle_path";
+const SETTINGS_PINNED_SPKI: &str = "management_pinned_spki";
+
+/// 固定校验失败时的错误文本，用于在 reqwest 错误链中识别
+const PIN_MISMATCH: &str = "management certificate pin mismatch";
+
+/// 管理同步的 TLS 设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ManagementTlsSettings {
+    /// 额外信任的根证书（PEM，可包含多个证书）
+    pub ca_bundle_path: Option<String>,
+    /// 服务器证书链中任一证书 SPKI 的 SHA-256（base64，可带 `sha256/` 前缀）
+    pub pinned_spki: Vec<String>,
+}
+
+impl ManagementTlsSettings {
+    pub fn load(db: &Database) -> Self {
+        let read = |key: &str| {
+            db.get_setting(key)
+                .ok()
+                .flatten()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            ca_bundle_path: read(SETTINGS_CA_BUNDLE_PATH),
+            pinned_spki: read(SETTINGS_PINNED_SPKI)
+                .map(|value| split_pins(&value))
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn save(&self, db: &Database, compiled_pins: &str) -> Result<(), AppError> {
+        self.validate(compiled_pins)?;
+        db.set_setting(
+            SETTINGS_CA_BUNDLE_PATH,
+            self.ca_bundle_path.as_deref().unwrap_or_default().trim(),
+        )?;
+        db.set_setting(SETTINGS_PINNED_SPKI, &self.pinned_spki.join(","))?;
+        Ok(())
+    }
+
+    pub fn validate(&self, compiled_pins: &str) -> Result<(), AppError> {
+        let pins = self.pins(compiled_pins)?;
+        if self.ca_bundle_path().is_some() && !pins.is_empty() {
+            return Err(AppError::InvalidInput(
+                "A custom CA bundle cannot be combined with certificate pinning".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 按设置配置 reqwest 客户端；配置非法或证书文件无法读取时返回错误而不是静默降级
+    pub fn configure(
+        &self,
+        builder: reqwest::ClientBuilder,
+        compiled_pins: &str,
+    ) -> Result<reqwest::ClientBuilder, AppError> {
+        self.validate(compiled_pins)?;
+
+        if let Some(path) = self.ca_bundle_path() {
+            let pem = std::fs::read(path).map_err(|err| AppError::io(path, err))?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|err| {
+                AppError::InvalidInput(format!("Invalid CA bundle {path}: {err}"))
+            })?;
+            if certificates.is_empty() {
+                return Err(AppError::InvalidInput(format!(
+                    "CA bundle contains no certificates: {path}"
+                )));
+            }
+            return Ok(certificates
+                .into_iter()
+                .fold(builder, |builder, cert| builder.add_root_certificate(cert)));
+        }
+
+        let pins = self.pins(compiled_pins)?;
+        if pins.is_empty() {
+            return Ok(builder);
+        }
+        Ok(builder.use_preconfigured_tls(pinned_tls_config(pins)?))
+    }
+
+    fn ca_bundle_path(&self) -> Option<&str> {
+        self.ca_bundle_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+    }
+
+    /// 合并构建时与设置中的固定值
+    fn pins(&self, compiled_pins: &str) -> Result<Vec<[u8; 32]>, AppError> {
+        split_pins(compiled_pins)
+            .iter()
+            .chain(&self.pinned_spki)
+            .map(|pin| parse_pin(pin))
+            .collect()
+    }
+}
+
+/// 错误链中是否包含证书固定校验失败
+pub fn is_pin_mismatch(err: &(dyn std::error::Error + 'static)) -> bool {
+    error_chain(err).any(|err| err.to_string().contains(PIN_MISMATCH))
+}
+
+/// 错误链中是否包含 TLS / 证书校验失败
+pub fn is_tls_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    error_chain(err).any(|err| {
+        err.downcast_ref::<rustls::Error>().is_some() || {
+            let text = err.to_string().to_lowercase();
+            text.contains("certificate") || text.contains("tls")
+        }
+    })
+}
+
+fn error_chain<'a>(
+    err: &'a (dyn std::error::Error + 'static),
+) -> impl Iterator<Item = &'a (dyn std::error::Error + 'static)> {
+    std::iter::successors(Some(err), |err| err.source())
+}
+
+fn split_pins(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pin| !pin.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_pin(pin: &str) -> Result<[u8; 32], AppError> {
+    let encoded = pin.trim();
+    let encoded = encoded.strip_prefix("sha256/").unwrap_or(encoded);
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "Invalid pinned SPKI hash (expected base64 SHA-256): {pin}"
+            ))
+        })
+}
+
+fn spki_sha256(cert: &CertificateDer<'_>) -> Option<[u8; 32]> {
+    let cert = webpki::EndEntityCert::try_from(cert).ok()?;
+    Some(Sha256::digest(cert.subject_public_key_info().as_ref()).into())
+}
+
+fn pinned_tls_config(pins: Vec<[u8; 32]>) -> Result<rustls::ClientConfig, AppError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|err| AppError::Message(format!("Failed to build TLS verifier: {err}")))?;
+
+    Ok(rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|err| AppError::Message(format!("Failed to configure TLS: {err}")))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier { inner, pins }))
+        .with_no_client_auth())
+}
+
+/// 先按系统内置根证书正常校验证书链，再要求链中任一证书的 SPKI 命中固定值
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: Vec<[u8; 32]>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let pinned = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(spki_sha256)
+            .any(|hash| self.pins.contains(&hash));
+        if pinned {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::General(PIN_MISMATCH.to_string()))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIN: &str = "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+
+    #[test]
+    fn pins_accept_optional_prefix() {
+        assert!(parse_pin(PIN).is_ok());
+        assert_eq!(
+            parse_pin(&format!("sha256/{PIN}")).unwrap(),
+            parse_pin(PIN).unwrap()
+        );
+        assert!(parse_pin("not-base64").is_err());
+        // 长度不是 32 字节
+        assert!(parse_pin("AAAA").is_err());
+    }
+
+    #[test]
+    fn ca_bundle_and_pins_are_exclusive() {
+        let ca_only = ManagementTlsSettings {
+            ca_bundle_path: Some("/etc/ssl/corp.pem".to_string()),
+            pinned_spki: Vec::new(),
+        };
+        assert!(ca_only.validate("").is_ok());
+        assert!(ca_only.validate(PIN).is_err());
+
+        let both = ManagementTlsSettings {
+            pinned_spki: vec![PIN.to_string()],
+            ..ca_only
+        };
+        assert!(both.validate("").is_err());
+    }
+
+    #[test]
+    fn pin_mismatch_is_found_in_error_chain() {
+        let err = std::io::Error::other(rustls::Error::General(PIN_MISMATCH.to_string()));
+        assert!(is_pin_mismatch(&err));
+        assert!(is_tls_error(&err));
+
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert!(!is_pin_mismatch(&refused));
+        assert!(!is_tls_error(&refused));
+    }
+}
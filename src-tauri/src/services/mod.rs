@@ -2,7 +2,24 @@ pub mod config;
 pub mod env_checker;
 pub mod env_manager;
 pub mod mcp;
+pub mod management_apply;
+pub mod management_diagnostics;
+pub mod management_directives;
+pub mod management_error;
+pub mod management_extras;
+pub mod management_failover;
+pub mod management_logs;
+#[cfg(all(test, feature = "management-sync"))]
+pub mod management_mock;
+pub mod management_network;
+pub mod management_privacy;
+pub mod management_profiles;
+pub mod management_schedule;
+#[cfg(feature = "management-sync")]
+pub mod management_secret_box;
+pub mod management_signing;
 pub mod management_sync;
+pub mod management_tls;
 pub mod prompt;
 pub mod provider;
 pub mod proxy;
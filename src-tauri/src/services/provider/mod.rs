@@ -53,6 +53,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_provider_checks_name_and_website() {
+        let provider = |name: &str, website: Option<&str>| {
+            Provider::with_id(
+                "claude".into(),
+                name.into(),
+                json!({ "env": {} }),
+                website.map(str::to_string),
+            )
+        };
+        let validate =
+            |provider: &Provider| ProviderService::validate_provider(&AppType::Claude, provider);
+
+        assert!(validate(&provider("Claude", None)).is_ok());
+        assert!(validate(&provider("Claude", Some(""))).is_ok());
+        assert!(validate(&provider("Claude", Some("https://claude.ai"))).is_ok());
+        assert!(validate(&provider(" ", None)).is_err());
+        assert!(validate(&provider("Claude", Some("claude.ai"))).is_err());
+        assert!(validate(&provider("Claude", Some("ftp://claude.ai"))).is_err());
+    }
+
     #[test]
     fn extract_credentials_returns_expected_values() {
         let provider = Provider::with_id(
@@ -106,7 +127,7 @@ impl ProviderService {
         let mut provider = provider;
         // Normalize Claude model keys
         Self::normalize_provider_if_claude(&app_type, &mut provider);
-        Self::validate_provider_settings(&app_type, &provider)?;
+        Self::validate_provider(&app_type, &provider)?;
 
         // Save to database
         state.db.save_provider(app_type.as_str(), &provider)?;
@@ -414,6 +435,51 @@ impl ProviderService {
         write_gemini_live(provider)
     }
 
+    /// 新增供应商前的完整校验：基础字段 + 各应用的配置结构
+    ///
+    /// 与表单校验保持一致（名称必填，网址可空但必须合法），管理员配置下发前也用它预先校验。
+    pub(crate) fn validate_provider(
+        app_type: &AppType,
+        provider: &Provider,
+    ) -> Result<(), AppError> {
+        if provider.id.trim().is_empty() {
+            return Err(AppError::localized(
+                "provider.id.empty",
+                "供应商 ID 不能为空",
+                "Provider ID must not be empty",
+            ));
+        }
+        if provider.name.trim().is_empty() {
+            return Err(AppError::localized(
+                "provider.name.empty",
+                format!("供应商 {} 的名称不能为空", provider.id),
+                format!("Provider {} name must not be empty", provider.id),
+            ));
+        }
+        if let Some(website) = provider
+            .website_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+        {
+            let valid = url::Url::parse(website)
+                .map(|url| matches!(url.scheme(), "http" | "https"))
+                .unwrap_or(false);
+            if !valid {
+                return Err(AppError::localized(
+                    "provider.website_url.invalid",
+                    format!("供应商 {} 的网址无效: {website}", provider.id),
+                    format!(
+                        "Provider {} has an invalid website URL: {website}",
+                        provider.id
+                    ),
+                ));
+            }
+        }
+
+        Self::validate_provider_settings(app_type, provider)
+    }
+
     fn validate_provider_settings(app_type: &AppType, provider: &Provider) -> Result<(), AppError> {
         match app_type {
             AppType::Claude => {
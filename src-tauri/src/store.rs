@@ -1,11 +1,14 @@
 use crate::database::Database;
 use crate::services::ProxyService;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 /// 全局应用状态
 pub struct AppState {
     pub db: Arc<Database>,
     pub proxy_service: ProxyService,
+    /// 应用退出时触发，正在进行的同步据此中止网络请求
+    pub shutdown: CancellationToken,
 }
 
 impl AppState {
@@ -13,6 +16,10 @@ impl AppState {
     pub fn new(db: Arc<Database>) -> Self {
         let proxy_service = ProxyService::new(db.clone());
 
-        Self { db, proxy_service }
+        Self {
+            db,
+            proxy_service,
+            shutdown: CancellationToken::new(),
+        }
     }
 }
@@ -1,9 +1,7 @@
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, OnceLock};
 
-use cc_switch_lib::{
-    update_settings, AppSettings, AppState, Database, MultiAppConfig, ProxyService,
-};
+use cc_switch_lib::{update_settings, AppSettings, AppState, Database, MultiAppConfig};
 
 /// 为测试设置隔离的 HOME 目录，避免污染真实用户数据。
 pub fn ensure_test_home() -> &'static Path {
@@ -51,8 +49,7 @@ pub fn test_mutex() -> &'static Mutex<()> {
 /// 创建测试用的 AppState，包含一个空的数据库
 pub fn create_test_state() -> Result<AppState, Box<dyn std::error::Error>> {
     let db = Arc::new(Database::init()?);
-    let proxy_service = ProxyService::new(db.clone());
-    Ok(AppState { db, proxy_service })
+    Ok(AppState::new(db))
 }
 
 /// 创建测试用的 AppState，并从 MultiAppConfig 迁移数据
@@ -61,6 +58,5 @@ pub fn create_test_state_with_config(
 ) -> Result<AppState, Box<dyn std::error::Error>> {
     let db = Arc::new(Database::init()?);
     db.migrate_from_json(config)?;
-    let proxy_service = ProxyService::new(db.clone());
-    Ok(AppState { db, proxy_service })
+    Ok(AppState::new(db))
 }
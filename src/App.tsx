@@ -18,6 +18,7 @@ import type { Provider } from "@/types";
 import type { EnvConflict } from "@/types/env";
 import { useProvidersQuery } from "@/lib/query";
 import {
+  managementApi,
   providersApi,
   settingsApi,
   type AppId,
@@ -133,6 +134,63 @@ function App() {
     };
   }, [activeApp, refetch]);
 
+  // 后台管理同步应用了管理员配置后刷新供应商列表
+  useEffect(() => {
+    let unsubscribe: (() => void) | undefined;
+
+    const setupListener = async () => {
+      try {
+        unsubscribe = await managementApi.onSyncFinished(async (event) => {
+          if (event.providersChanged) {
+            await queryClient.invalidateQueries({ queryKey: ["providers"] });
+          }
+        });
+      } catch (error) {
+        console.error(
+          "[App] Failed to subscribe management sync event",
+          error,
+        );
+      }
+    };
+
+    setupListener();
+    return () => {
+      unsubscribe?.();
+    };
+  }, [queryClient]);
+
+  // 本地修改与管理员配置冲突时提示用户
+  useEffect(() => {
+    let unsubscribe: (() => void) | undefined;
+
+    const setupListener = async () => {
+      try {
+        unsubscribe = await managementApi.onSyncConflict((conflict) => {
+          toast.warning(
+            conflict.resolution === "skipped"
+              ? t("managementSync.conflictSkipped", {
+                  defaultValue: "本地供应商已被修改，已跳过管理员配置",
+                })
+              : t("managementSync.conflictBackedUp", {
+                  defaultValue: "本地供应商已被管理员配置覆盖，修改已保存到备份",
+                }),
+            { closeButton: true },
+          );
+        });
+      } catch (error) {
+        console.error(
+          "[App] Failed to subscribe management sync conflict event",
+          error,
+        );
+      }
+    };
+
+    setupListener();
+    return () => {
+      unsubscribe?.();
+    };
+  }, [t]);
+
   // 应用启动时检测所有应用的环境变量冲突
   useEffect(() => {
     const checkEnvOnStartup = async () => {
@@ -5,6 +5,36 @@ export { mcpApi } from "./mcp";
 export { promptsApi } from "./prompts";
 export { usageApi } from "./usage";
 export { vscodeApi } from "./vscode";
+export { managementApi } from "./management";
 export * as configApi from "./config";
 export type { ProviderSwitchEvent } from "./providers";
 export type { Prompt } from "./prompts";
+export type {
+  ManagementSyncStatus,
+  ManagementInfo,
+  ManagementFeatureFlagsChangedEvent,
+  ManagementSyncSchedule,
+  ManagementSyncPauseWindow,
+  ManagementWeekday,
+  ConfigBackupSummary,
+  ManagementSyncFinishedEvent,
+  ManagementApplyMode,
+  ManagementConflictPolicy,
+  ManagementConfigConflict,
+  ManagementProxySettings,
+  ManagementTlsSettings,
+  ManagementSnapshotPrivacy,
+  ManagementConnectionTestResult,
+  ManagementManualSyncResult,
+  ManagementConnectionTestStep,
+  ManagementConnectionTestStepKind,
+  ManagementSyncHistoryEntry,
+  ManagementApplyReport,
+  ManagementApplyConfirmation,
+  ManagementPendingAdminConfig,
+  ManagementAdminConfigPreview,
+  ManagementAppConfigDiff,
+  ManagementSyncError,
+  ManagementSyncMetrics,
+  ManagementSchedulerHealth,
+} from "./management";
@@ -0,0 +1,730 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { AppId } from "./types";
+
+export type ManagementSyncResult = "success" | "failed" | "never";
+
+/** 同步失败的类别；i18nKey 用于本地化提示，detail 为原始信息 */
+export type ManagementSyncError = (
+  | { code: "unreachable" }
+  | { code: "timeout" }
+  | { code: "unauthorized" }
+  | { code: "serverError"; status: number }
+  | { code: "parseError" }
+  | { code: "applyFailed"; app: string; reason: string }
+  | { code: "invalidSignature" }
+  | { code: "disabled" }
+  | { code: "cancelled" }
+  | { code: "tooSoon"; retryAfterSecs: number }
+  | { code: "other" }
+) & {
+  i18nKey: string;
+  detail: string;
+};
+
+export interface ManagementSyncStatus {
+  /** 关闭后不再发起任何同步请求 */
+  enabled: boolean;
+  lastSyncAt: string | null;
+  lastResult: ManagementSyncResult;
+  lastError: string | null;
+  lastSyncError: ManagementSyncError | null;
+  /** 各应用中最旧的已应用版本 */
+  appliedAdminVersion: number | null;
+  appliedAdminVersions: {
+    claude: number | null;
+    codex: number | null;
+    gemini: number | null;
+    /** MCP 服务器与通用配置片段；从未应用时不返回 */
+    extras?: number;
+  };
+  /** 已考虑暂停时段：落在时段内时为时段结束时间 */
+  nextScheduledAt: string;
+  /** 当前处于暂停时段时为时段结束时间，手动同步前应提示用户 */
+  pausedUntil: string | null;
+  retryAttempt: number;
+  nextRetryAt: string | null;
+  retriesExhausted: boolean;
+  /** 最近恢复的备份；下次同步会重新应用管理员配置 */
+  restoredBackupId: number | null;
+  /** 最近一次本地修改冲突；无冲突地应用后清空 */
+  lastConflict: ManagementConfigConflict | null;
+  /** 最近一次应用管理员配置的逐应用结果；失败应用的版本不变，下次同步重试 */
+  lastApply: ManagementApplyReport | null;
+  /** 确认模式下等待批准的管理员配置 */
+  pendingAdminConfig: ManagementPendingAdminConfig | null;
+  /** 当前使用的服务器地址；覆盖地址无效时为空，原因见 serverUrlError */
+  serverUrl: string | null;
+  serverUrlSource: "environment" | "setting" | "build" | null;
+  serverUrlError: string | null;
+  /** 最近一次可用的服务器地址；与 serverUrl 不同说明正在使用备用地址 */
+  activeEndpoint: string | null;
+  /** 服务器时间减本机时间（秒）；尚未测得时为空 */
+  clockOffsetSecs: number | null;
+  /** 本机时钟偏差超过 10 分钟时的提示 */
+  clockSkewWarning: string | null;
+  scheduler: ManagementSchedulerHealth;
+  /** 被服务器封禁时应常驻提示；此时只有手动同步会发出请求 */
+  blocked: ManagementDeviceBlocked | null;
+  /** 服务器要求的最低应用版本，同步照常进行 */
+  upgradeRequired: string | null;
+  /** 等待管理员批准；期间不会收到管理员配置，复查间隔逐渐放宽 */
+  pendingApproval: ManagementPendingApproval | null;
+}
+
+export interface ManagementDeviceBlocked {
+  message: string | null;
+  since: string;
+}
+
+export interface ManagementPendingApproval {
+  since: string;
+  /** 已复查的次数 */
+  checks: number;
+}
+
+/** 服务器消息；同一 id 只推送一次 */
+export interface ManagementServerMessage {
+  id: string;
+  message: string;
+}
+
+/** 功能开关变化：changed 为新增、修改或被移除的开关名，flags 为当前完整集合 */
+export interface ManagementFeatureFlagsChangedEvent {
+  changed: string[];
+  flags: Record<string, unknown>;
+}
+
+/** 设备登记信息，供用户告诉支持人员；不含令牌 */
+export interface ManagementInfo {
+  deviceId: string;
+  /** 启动时通过 CC_SWITCH_MANAGEMENT_ENV 选定的服务器环境 */
+  environment: "production" | "staging";
+  /** 管理服务器的 scheme 与主机，不含路径 */
+  serverHost: string | null;
+  /** 首次同步成功的时间 */
+  enrolledAt: string | null;
+  appliedAdminVersion: number | null;
+  /** 是否收到过管理员配置 */
+  centrallyManaged: boolean;
+}
+
+/** 诊断面板用于判断客户端是否还在与服务器通信 */
+export interface ManagementServerTimeOffset {
+  /** 服务器时间减本机时间（秒）；服务器未返回时间时为 null */
+  offsetSecs: number | null;
+  /** 最近一次收到服务器有效响应的时间 */
+  lastContactAt: string | null;
+  /** 最近联系的服务器 scheme 与主机，不含路径 */
+  endpointHost: string | null;
+}
+
+/** 同步配置档；0 号为默认配置档，其余配置档各自登记到自己的管理服务器 */
+export interface ManagementSyncProfile {
+  id: number;
+  name: string;
+  enabled: boolean;
+  serverHost: string | null;
+  /** 使用配置档自己的令牌，而不是构建时编入的令牌 */
+  tokenOverride: boolean;
+}
+
+/** healthy 为 false 表示计划同步可能不会按时执行（计划任务卡死或刚发生 panic） */
+export interface ManagementSchedulerHealth {
+  running: boolean;
+  healthy: boolean;
+  lastTickAt: string | null;
+  panics: number;
+  lastPanic: string | null;
+  lastPanicAt: string | null;
+  restarts: number;
+}
+
+export interface ManagementApplyReport {
+  adminVersion: number | null;
+  appliedAt: string;
+  /**
+   * error 为 null 表示该应用成功；changed 为 false 表示配置一致、未改动；
+   * partial 表示失败前已改动了部分本地供应商，下次同步会重试
+   */
+  apps: {
+    app: "claude" | "codex" | "gemini";
+    error: string | null;
+    changed: boolean;
+    providers: number;
+    current: string | null;
+    switched: boolean;
+    partial?: boolean;
+  }[];
+}
+
+/** automatic 同步时直接应用；ask 先保存为待确认配置，由用户批准或拒绝 */
+export type ManagementApplyConfirmation = "automatic" | "ask";
+
+export interface ManagementPendingAdminConfig {
+  adminVersion: number | null;
+  receivedAt: string;
+  providerCounts: { claude: number; codex: number; gemini: number };
+}
+
+export interface ManagementProviderRef {
+  id: string;
+  name: string;
+}
+
+/** 与差异应用使用同一份比对结果；error 不为空时该应用不会被应用 */
+export interface ManagementAppConfigDiff {
+  app: "claude" | "codex" | "gemini";
+  added: ManagementProviderRef[];
+  modified: ManagementProviderRef[];
+  removed: ManagementProviderRef[];
+  /** 与本地固定供应商同 ID 的下发条目，保留本地版本 */
+  pinned: ManagementProviderRef[];
+  unchanged: number;
+  currentId: string | null;
+  /** 为空表示不会切换当前供应商 */
+  newCurrentId: string | null;
+  error: string | null;
+}
+
+export interface ManagementAdminConfigPreview {
+  adminVersion: number | null;
+  receivedAt: string;
+  /** 预览的是待确认配置而不是最近一次收到的配置 */
+  pending: boolean;
+  mode: ManagementApplyMode;
+  apps: ManagementAppConfigDiff[];
+}
+
+/** skip 跳过应用并保留本地修改；backup 照常应用，本地状态保存在备份中 */
+export type ManagementConflictPolicy = "skip" | "backup";
+
+export interface ManagementConfigConflict {
+  detectedAt: string;
+  adminVersion: number | null;
+  resolution: "skipped" | "backedUp";
+  backupId: number | null;
+}
+
+export interface ConfigBackupSummary {
+  id: number;
+  createdAt: string;
+  adminVersion: number | null;
+  previousAdminVersion: number | null;
+  providerCounts: { claude: number; codex: number; gemini: number };
+}
+
+export interface ManagementSyncHistoryEntry {
+  id: number;
+  startedAt: string;
+  finishedAt: string;
+  outcome: "success" | "failed" | "rate_limited" | "maintenance";
+  offeredAdminVersion: number | null;
+  appliedAdminVersion: number | null;
+  error: string | null;
+  payloadBytes: number | null;
+  compressedBytes: number | null;
+  endpoint: string | null;
+  errorCode: ManagementSyncError | null;
+  collectMs: number | null;
+  serializeMs: number | null;
+  networkMs: number | null;
+  applyMs: number | null;
+  /** 快照有变化但未上传的原因；metered 表示计费网络上只发了心跳 */
+  snapshotSkipped: "metered" | null;
+}
+
+/** 单次同步的分阶段耗时（毫秒）；未执行到的阶段为 null */
+export interface ManagementSyncMetrics {
+  startedAt: string;
+  outcome: ManagementSyncHistoryEntry["outcome"];
+  durationMs: number | null;
+  collectMs: number | null;
+  serializeMs: number | null;
+  networkMs: number | null;
+  applyMs: number | null;
+  payloadBytes: number | null;
+  compressedBytes: number | null;
+}
+
+export interface ManagementSyncSchedule {
+  mode: "daily" | "interval";
+  /** 0-23，daily 模式下使用 */
+  hour: number;
+  timezone: "local" | "fixed-offset";
+  /** 相对 UTC 的分钟数，fixed-offset 时使用 */
+  offsetMinutes: number;
+  /** 1-168，interval 模式下使用 */
+  intervalHours: number;
+  /** 0-720，daily 模式下按设备错开的分钟窗口 */
+  jitterMinutes: number;
+}
+
+export type ManagementWeekday =
+  | "Mon"
+  | "Tue"
+  | "Wed"
+  | "Thu"
+  | "Fri"
+  | "Sat"
+  | "Sun";
+
+/** 暂停同步的时段（系统本地时间），时段内到点的后台同步推迟到时段结束 */
+export interface ManagementSyncPauseWindow {
+  enabled: boolean;
+  /** 0-1439，距 0 点的分钟数 */
+  startMinute: number;
+  /** 0-1439，不含；早于开始时间表示在次日结束 */
+  endMinute: number;
+  /** 时段在哪些日子开始 */
+  days: ManagementWeekday[];
+}
+
+/** replace 清空本地供应商后写入；merge 保留仅存在于本地的供应商 */
+export type ManagementApplyMode = "replace" | "merge";
+
+/** 未设置 url 时使用 HTTPS_PROXY / HTTP_PROXY / NO_PROXY 环境变量 */
+export interface ManagementProxySettings {
+  /** http://、https://、socks5:// 或 socks5h:// */
+  url: string | null;
+  username: string | null;
+  password: string | null;
+}
+
+/** 额外根证书与证书固定互斥（构建时编入的固定值同样计入） */
+/** 快照上传时密钥的处理：原样、脱敏（保留末 4 位）或用构建时编入的公钥加密 */
+export type ManagementSnapshotPrivacy = "full" | "redacted" | "encrypted";
+
+export interface ManagementTlsSettings {
+  /** PEM 文件路径，可包含多个根证书 */
+  caBundlePath: string | null;
+  /** SPKI 的 SHA-256（base64，可带 sha256/ 前缀） */
+  pinnedSpki: string[];
+}
+
+export type ManagementConnectionTestStepKind = "dns" | "connect" | "tls" | "auth";
+
+export interface ManagementConnectionTestStep {
+  step: ManagementConnectionTestStepKind;
+  ok: boolean;
+  elapsedMs: number;
+  status: number | null;
+  error: ManagementSyncError | null;
+}
+
+export interface ManagementConnectionTestResult {
+  ok: boolean;
+  url: string;
+  status: number | null;
+  elapsedMs: number;
+  error: string | null;
+  failedStep: ManagementConnectionTestStepKind | null;
+  steps: ManagementConnectionTestStep[];
+}
+
+/** 手动同步的结果；同步进行中时返回那一次的结果 */
+export interface ManagementManualSyncResult {
+  ok: boolean;
+  error: ManagementSyncError | null;
+}
+
+export interface ManagementSyncFinishedEvent {
+  appliedAdminVersion: number | null;
+  providersChanged: boolean;
+  conflict: ManagementConfigConflict | null;
+}
+
+export interface ManagementSyncFailedEvent {
+  error: string;
+  syncError: ManagementSyncError;
+}
+
+export const managementApi = {
+  async getSyncStatus(): Promise<ManagementSyncStatus> {
+    return invoke("get_management_sync_status");
+  },
+
+  async getSyncSchedule(): Promise<ManagementSyncSchedule> {
+    return invoke("get_management_sync_schedule");
+  },
+
+  async setSyncSchedule(
+    schedule: ManagementSyncSchedule,
+  ): Promise<ManagementSyncSchedule> {
+    return invoke("set_management_sync_schedule", { schedule });
+  },
+
+  async getPauseWindow(): Promise<ManagementSyncPauseWindow> {
+    return invoke("get_management_pause_window");
+  },
+
+  async setPauseWindow(
+    window: ManagementSyncPauseWindow,
+  ): Promise<ManagementSyncPauseWindow> {
+    return invoke("set_management_pause_window", { window });
+  },
+
+  async listConfigBackups(): Promise<ConfigBackupSummary[]> {
+    return invoke("list_config_backups");
+  },
+
+  async restoreConfigBackup(id: number): Promise<void> {
+    return invoke("restore_config_backup", { id });
+  },
+
+  async exportProviderConfig(path: string): Promise<void> {
+    return invoke("export_provider_config", { path });
+  },
+
+  async importProviderConfig(
+    path: string,
+    mode: ManagementApplyMode,
+  ): Promise<ManagementApplyReport> {
+    return invoke("import_provider_config", { path, mode });
+  },
+
+  async getMergeMode(): Promise<ManagementApplyMode> {
+    return invoke("get_management_merge_mode");
+  },
+
+  async setMergeMode(mode: ManagementApplyMode): Promise<ManagementApplyMode> {
+    return invoke("set_management_merge_mode", { mode });
+  },
+
+  async getApplyConfirmation(): Promise<ManagementApplyConfirmation> {
+    return invoke("get_management_apply_confirmation");
+  },
+
+  async setApplyConfirmation(
+    confirmation: ManagementApplyConfirmation,
+  ): Promise<ManagementApplyConfirmation> {
+    return invoke("set_management_apply_confirmation", { confirmation });
+  },
+
+  async getPendingAdminConfig(): Promise<ManagementPendingAdminConfig | null> {
+    return invoke("get_pending_admin_config");
+  },
+
+  /** 按正常流程应用待确认配置并立即同步 */
+  async approvePendingAdminConfig(): Promise<void> {
+    return invoke("approve_pending_admin_config");
+  },
+
+  /** 拒绝后同一份配置不再询问，拒绝在下次同步时上报 */
+  async rejectPendingAdminConfig(): Promise<void> {
+    return invoke("reject_pending_admin_config");
+  },
+
+  /** 只读：预览待确认配置或最近一次收到的配置会带来的改动 */
+  async previewAdminConfig(): Promise<ManagementAdminConfigPreview> {
+    return invoke("preview_admin_config");
+  },
+
+  /** 固定的供应商不会被管理员配置覆盖或删除 */
+  async getPinnedProviders(app: AppId): Promise<string[]> {
+    return invoke("get_pinned_providers", { app });
+  },
+
+  async setProviderPinned(
+    app: AppId,
+    id: string,
+    pinned: boolean,
+  ): Promise<string[]> {
+    return invoke("set_provider_pinned", { app, id, pinned });
+  },
+
+  /** 上传快照时是否去掉固定的供应商，默认上传 */
+  async getExcludePinned(): Promise<boolean> {
+    return invoke("get_management_exclude_pinned");
+  },
+
+  async setExcludePinned(enabled: boolean): Promise<void> {
+    return invoke("set_management_exclude_pinned", { enabled });
+  },
+
+  /** 上传快照时是否带上 MCP 服务器与通用配置片段，默认不上传 */
+  async getIncludeExtras(): Promise<boolean> {
+    return invoke("get_management_include_extras");
+  },
+
+  async setIncludeExtras(enabled: boolean): Promise<void> {
+    return invoke("set_management_include_extras", { enabled });
+  },
+
+  /** 开启后拒绝应用未签名的管理员配置 */
+  async getRequireSignedConfigs(): Promise<boolean> {
+    return invoke("get_management_require_signed_configs");
+  },
+
+  async setRequireSignedConfigs(enabled: boolean): Promise<void> {
+    return invoke("set_management_require_signed_configs", { enabled });
+  },
+
+  async getConflictPolicy(): Promise<ManagementConflictPolicy> {
+    return invoke("get_management_conflict_policy");
+  },
+
+  async setConflictPolicy(
+    policy: ManagementConflictPolicy,
+  ): Promise<ManagementConflictPolicy> {
+    return invoke("set_management_conflict_policy", { policy });
+  },
+
+  /** 放弃本地修改，下次同步应用管理员配置 */
+  async resolveConflict(): Promise<void> {
+    return invoke("resolve_management_conflict");
+  },
+
+  async getProxySettings(): Promise<ManagementProxySettings> {
+    return invoke("get_management_proxy_settings");
+  },
+
+  async setProxySettings(
+    settings: ManagementProxySettings,
+  ): Promise<ManagementProxySettings> {
+    return invoke("set_management_proxy_settings", { settings });
+  },
+
+  async getSyncEnabled(): Promise<boolean> {
+    return invoke("get_management_sync_enabled");
+  },
+
+  /** 重新开启后会很快补一次同步 */
+  async setSyncEnabled(enabled: boolean): Promise<void> {
+    return invoke("set_management_sync_enabled", { enabled });
+  },
+
+  async listSyncProfiles(): Promise<ManagementSyncProfile[]> {
+    return invoke("list_sync_profiles");
+  },
+
+  /** 地址与令牌传 null 时使用构建时编入的值；新配置档默认关闭 */
+  async createSyncProfile(
+    name: string,
+    url: string | null,
+    token: string | null,
+  ): Promise<ManagementSyncProfile> {
+    return invoke("create_sync_profile", { name, url, token });
+  },
+
+  async setSyncProfileEnabled(
+    id: number,
+    enabled: boolean,
+  ): Promise<ManagementSyncProfile> {
+    return invoke("set_sync_profile_enabled", { id, enabled });
+  },
+
+  async getSyncProfileStatus(id: number): Promise<ManagementSyncStatus> {
+    return invoke("get_sync_profile_status", { id });
+  },
+
+  async getUrlOverride(): Promise<string | null> {
+    return invoke("get_management_url_override");
+  },
+
+  /** 必须是 https（回环地址或开启 management_allow_insecure 时允许 http）；传 null 恢复构建时地址 */
+  async setUrlOverride(url: string | null): Promise<void> {
+    return invoke("set_management_url_override", { url });
+  },
+
+  async getFallbackUrls(): Promise<string[]> {
+    return invoke("get_management_fallback_urls");
+  },
+
+  /** 主地址连接失败或返回 5xx 时按顺序尝试；校验规则与覆盖地址相同 */
+  async setFallbackUrls(urls: string[]): Promise<string[]> {
+    return invoke("set_management_fallback_urls", { urls });
+  },
+
+  async getSnapshotPrivacy(): Promise<ManagementSnapshotPrivacy> {
+    return invoke("get_management_snapshot_privacy");
+  },
+
+  async setSnapshotPrivacy(
+    privacy: ManagementSnapshotPrivacy,
+  ): Promise<ManagementSnapshotPrivacy> {
+    return invoke("set_management_snapshot_privacy", { privacy });
+  },
+
+  async getTlsSettings(): Promise<ManagementTlsSettings> {
+    return invoke("get_management_tls_settings");
+  },
+
+  async setTlsSettings(
+    settings: ManagementTlsSettings,
+  ): Promise<ManagementTlsSettings> {
+    return invoke("set_management_tls_settings", { settings });
+  },
+
+  async syncNow(): Promise<ManagementManualSyncResult> {
+    return invoke("sync_management_now");
+  },
+
+  async testConnection(): Promise<ManagementConnectionTestResult> {
+    return invoke("test_management_connection");
+  },
+
+  async getReportHostname(): Promise<boolean> {
+    return invoke("get_management_report_hostname");
+  },
+
+  async setReportHostname(enabled: boolean): Promise<void> {
+    return invoke("set_management_report_hostname", { enabled });
+  },
+
+  /** 计费网络（热点、蜂窝）上是否仍上传完整快照，默认只发心跳 */
+  async getFullSyncOnMetered(): Promise<boolean> {
+    return invoke("get_management_full_sync_on_metered");
+  },
+
+  async setFullSyncOnMetered(enabled: boolean): Promise<void> {
+    return invoke("set_management_full_sync_on_metered", { enabled });
+  },
+
+  /** 关闭后管理员的收集日志指令会被确认为 declined */
+  async getLogUploadAllowed(): Promise<boolean> {
+    return invoke("get_management_log_upload_allowed");
+  },
+
+  async setLogUploadAllowed(allowed: boolean): Promise<void> {
+    return invoke("set_management_log_upload_allowed", { allowed });
+  },
+
+  /** 管理员配置实际改动了本地供应商时是否弹出系统通知 */
+  async getApplyNotification(): Promise<boolean> {
+    return invoke("get_management_apply_notification");
+  },
+
+  async setApplyNotification(enabled: boolean): Promise<void> {
+    return invoke("set_management_apply_notification", { enabled });
+  },
+
+  async getDeviceId(): Promise<string> {
+    return invoke("get_device_id");
+  },
+
+  async getInfo(): Promise<ManagementInfo> {
+    return invoke("get_management_info");
+  },
+
+  async getServerTimeOffset(): Promise<ManagementServerTimeOffset> {
+    return invoke("get_server_time_offset");
+  },
+
+  /** 服务器下发的功能开关；未下发时为 null */
+  async getFeatureFlag(name: string): Promise<unknown> {
+    return invoke("get_feature_flag", { name });
+  },
+
+  /** 重新生成设备 ID 并立即同步，需要用户确认后传 confirm: true */
+  async regenerateDeviceId(confirm: boolean): Promise<string> {
+    return invoke("regenerate_device_id", { confirm });
+  },
+
+  /** 最新在前，默认 20 条，最多 100 条 */
+  async getSyncHistory(limit?: number): Promise<ManagementSyncHistoryEntry[]> {
+    return invoke("get_sync_history", { limit });
+  },
+
+  /** 诊断面板绘图用；最新在前，默认 20 条，最多 100 条 */
+  async getSyncMetrics(limit?: number): Promise<ManagementSyncMetrics[]> {
+    return invoke("get_sync_metrics", { limit });
+  },
+
+  /** 同步诊断日志（滚动保留 3 个文件），用户可附在问题反馈里 */
+  async getSyncLogPath(): Promise<string> {
+    return invoke("get_sync_log_path");
+  },
+
+  async revealSyncLog(): Promise<boolean> {
+    return invoke("reveal_sync_log");
+  },
+
+  async onSyncStarted(handler: () => void): Promise<UnlistenFn> {
+    return await listen("management-sync://started", () => handler());
+  },
+
+  async onSyncFinished(
+    handler: (event: ManagementSyncFinishedEvent) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://finished", (event) => {
+      handler(event.payload as ManagementSyncFinishedEvent);
+    });
+  },
+
+  async onSyncConflict(
+    handler: (event: ManagementConfigConflict) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://conflict", (event) => {
+      handler(event.payload as ManagementConfigConflict);
+    });
+  },
+
+  /** 管理员配置实际改动了本地供应商 */
+  async onAdminConfigApplied(
+    handler: (report: ManagementApplyReport) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://admin-config-applied", (event) => {
+      handler(event.payload as ManagementApplyReport);
+    });
+  },
+
+  async onAdminConfigPending(
+    handler: (pending: ManagementPendingAdminConfig) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://admin-config-pending", (event) => {
+      handler(event.payload as ManagementPendingAdminConfig);
+    });
+  },
+
+  async onFeatureFlagsChanged(
+    handler: (event: ManagementFeatureFlagsChangedEvent) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://feature-flags-changed", (event) => {
+      handler(event.payload as ManagementFeatureFlagsChangedEvent);
+    });
+  },
+
+  /** 被服务器封禁或解封；解封时为 null */
+  async onBlockedChanged(
+    handler: (blocked: ManagementDeviceBlocked | null) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://blocked-changed", (event) => {
+      handler(event.payload as ManagementDeviceBlocked | null);
+    });
+  },
+
+  /** 开始等待管理员批准或已获批准；批准后为 null */
+  async onApprovalChanged(
+    handler: (pending: ManagementPendingApproval | null) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://approval-changed", (event) => {
+      handler(event.payload as ManagementPendingApproval | null);
+    });
+  },
+
+  /** 服务器提出新的最低版本要求；同一版本只推送一次 */
+  async onUpgradeRequired(
+    handler: (event: { version: string }) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://upgrade-required", (event) => {
+      handler(event.payload as { version: string });
+    });
+  },
+
+  async onServerMessage(
+    handler: (message: ManagementServerMessage) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://server-message", (event) => {
+      handler(event.payload as ManagementServerMessage);
+    });
+  },
+
+  async onSyncFailed(
+    handler: (event: ManagementSyncFailedEvent) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("management-sync://failed", (event) => {
+      handler(event.payload as ManagementSyncFailedEvent);
+    });
+  },
+};